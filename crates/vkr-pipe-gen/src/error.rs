@@ -0,0 +1,115 @@
+// Copyright © 2022
+// Author: Antonio Caggiano <info@antoniocaggiano.eu>
+// SPDX-License-Identifier: MIT
+
+use std::{fmt, path::PathBuf};
+
+use proc_macro2::Span;
+
+use crate::ShaderType;
+
+/// Everything that can go wrong while reading a shader crate and generating its pipelines.
+/// The macro entry point turns it into a `compile_error!`.
+#[derive(Debug)]
+pub enum ShaderError {
+    /// The shader crate directory passed to the macro does not exist
+    MissingCrate(PathBuf),
+    /// A file of the shader crate does not exist
+    MissingFile(PathBuf),
+    /// A module declared with `mod name;` in the shader crate has no file
+    MissingModule { module: String, path: PathBuf },
+    /// A file of the shader crate exists but can not be read
+    UnreadableFile(PathBuf, std::io::Error),
+    /// A file of the shader crate is not valid Rust or TOML
+    Parse(PathBuf, String),
+    /// The SPIR-V of the shader crate is not known at build time, named by its environment variable
+    MissingSpirv(String),
+    /// No pipeline can be built from the shader crate
+    NoEntryPoints(PathBuf),
+    /// A pipeline is missing one of its shader functions
+    MissingEntryPoint { pipeline: String, stage: ShaderType },
+    /// A shader function name can not be turned into a pipeline name
+    InvalidEntryPointName { function: String, span: Span },
+    /// An argument of a shader function has a type pipewriter does not handle
+    UnsupportedType {
+        what: &'static str,
+        ty: String,
+        span: Span,
+    },
+    /// A `spirv` or `pipewriter` attribute has a wrong or missing value
+    InvalidAttribute { message: String, span: Span },
+}
+
+impl ShaderError {
+    pub fn span(&self) -> Span {
+        match self {
+            ShaderError::UnsupportedType { span, .. } => *span,
+            ShaderError::InvalidAttribute { span, .. } => *span,
+            ShaderError::InvalidEntryPointName { span, .. } => *span,
+            _ => Span::call_site(),
+        }
+    }
+
+    pub fn to_compile_error(&self) -> proc_macro2::TokenStream {
+        syn::Error::new(self.span(), self).to_compile_error()
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::MissingCrate(path) => write!(
+                f,
+                "Missing shader crate {}: the path passed to pipewriter is relative to the directory of the Cargo.toml of the crate invoking it",
+                path.display()
+            ),
+            ShaderError::MissingFile(path) => write!(
+                f,
+                "Missing file {}: check the shader crate path passed to pipewriter",
+                path.display()
+            ),
+            ShaderError::MissingModule { module, path } => write!(
+                f,
+                "Missing file {} for module {} of the shader crate",
+                path.display(),
+                module
+            ),
+            ShaderError::UnreadableFile(path, err) => {
+                write!(f, "Failed to read {}: {}", path.display(), err)
+            }
+            ShaderError::Parse(path, err) => {
+                write!(f, "Failed to parse {}: {}", path.display(), err)
+            }
+            ShaderError::MissingSpirv(name) => write!(
+                f,
+                "Missing SPIR-V to reflect: set the {} environment variable to its path, as spirv-builder does in a build script",
+                name
+            ),
+            ShaderError::NoEntryPoints(path) => write!(
+                f,
+                "No shader entry points found in {}: a pipeline needs a #[spirv(vertex)] and a #[spirv(fragment)] function, or a #[spirv(compute(threads(..)))] one",
+                path.display()
+            ),
+            ShaderError::MissingEntryPoint { pipeline, stage } => write!(
+                f,
+                "Missing {:?} entry point for pipeline {}",
+                stage, pipeline
+            ),
+            ShaderError::InvalidEntryPointName { function, .. } => write!(
+                f,
+                "Can not name a pipeline after shader function {}: expected an ASCII prefix and a stage suffix, such as main_vs",
+                function
+            ),
+            ShaderError::UnsupportedType { what, ty, .. } => {
+                write!(f, "Unsupported {} type: {}", what, ty)
+            }
+            ShaderError::InvalidAttribute { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<ShaderError> for proc_macro2::TokenStream {
+    fn from(err: ShaderError) -> Self {
+        err.to_compile_error()
+    }
+}
@@ -0,0 +1,4775 @@
+// Copyright © 2021-2022
+// Author: Antonio Caggiano <info@antoniocaggiano.eu>
+// SPDX-License-Identifier: MIT
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::{
+    Camelcase, CrateModule, DescriptorType, Pipeline, PushConstant, ShaderError, ShaderType,
+    Uniform, VertexInput,
+};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Imports and types shared by all the generated pipelines, to be generated once before them
+pub fn header() -> TokenStream {
+    quote! {
+        use std::{collections::HashMap, ffi::{CStr, CString}, rc::Rc};
+        use ash::{vk, Device};
+        use vkr_core::{Dev, Pass, ShaderModule, Pipeline, Texture, Frame, Model, Node};
+        use vkr_util::Handle;
+
+        /// A descriptor expected by a pipeline, as declared by the arguments of its shaders
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct UniformInfo {
+            pub name: &'static str,
+            pub set: u32,
+            pub binding: u32,
+            pub descriptor_type: vk::DescriptorType,
+            pub stages: vk::ShaderStageFlags,
+        }
+
+        /// Vertex buffers layout expected by a pipeline, as read by its vertex shader
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct VertexLayout {
+            /// Size in bytes of a vertex
+            pub stride: u32,
+            /// Size in bytes of the per-instance data, 0 without per-instance inputs
+            pub instance_stride: u32,
+            /// Attributes sorted by location
+            pub attributes: &'static [VertexAttributeInfo],
+        }
+
+        /// A vertex attribute read by a pipeline from one of its vertex buffers
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct VertexAttributeInfo {
+            pub location: u32,
+            pub binding: u32,
+            pub format: vk::Format,
+            pub offset: u32,
+        }
+
+        /// Formats of the attachments a pipeline renders to with `VK_KHR_dynamic_rendering`,
+        /// instead of the attachments of a render pass
+        #[derive(Clone, Debug, Default, PartialEq, Eq)]
+        pub struct RenderingFormats {
+            /// Format of each color attachment, as many as the color outputs of the fragment shader
+            pub color: Vec<vk::Format>,
+            /// Format of the depth attachment, `UNDEFINED` without depth
+            pub depth: vk::Format,
+            /// Format of the stencil attachment, `UNDEFINED` without stencil
+            pub stencil: vk::Format,
+        }
+
+        /// `VkPipelineRenderingCreateInfoKHR`, which the Vulkan headers of ash 0.33 predate
+        #[allow(dead_code)]
+        #[repr(C)]
+        struct PipelineRenderingCreateInfo {
+            s_type: vk::StructureType,
+            p_next: *const std::ffi::c_void,
+            view_mask: u32,
+            color_attachment_count: u32,
+            p_color_attachment_formats: *const vk::Format,
+            depth_attachment_format: vk::Format,
+            stencil_attachment_format: vk::Format,
+        }
+
+        #[allow(dead_code)]
+        impl PipelineRenderingCreateInfo {
+            /// `VK_STRUCTURE_TYPE_PIPELINE_RENDERING_CREATE_INFO_KHR`
+            const STRUCTURE_TYPE: vk::StructureType = vk::StructureType::from_raw(1000044002);
+
+            fn new(formats: &RenderingFormats, view_mask: u32) -> Self {
+                Self {
+                    s_type: Self::STRUCTURE_TYPE,
+                    p_next: std::ptr::null(),
+                    view_mask,
+                    color_attachment_count: formats.color.len() as u32,
+                    p_color_attachment_formats: formats.color.as_ptr(),
+                    depth_attachment_format: formats.depth,
+                    stencil_attachment_format: formats.stencil,
+                }
+            }
+        }
+    }
+}
+
+/// Imports needed by the descriptors-only scaffolding
+pub fn descriptors_header() -> TokenStream {
+    quote! {
+        use std::{collections::HashMap, rc::Rc};
+        use ash::{vk, Device};
+        use vkr_core::Texture;
+
+        /// A descriptor expected by a pipeline, as declared by the arguments of its shaders
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct UniformInfo {
+            pub name: &'static str,
+            pub set: u32,
+            pub binding: u32,
+            pub descriptor_type: vk::DescriptorType,
+            pub stages: vk::ShaderStageFlags,
+        }
+    }
+}
+
+/// Returns the identifier of a generated item, such as `PipelineMain` or `write_set_0`.
+/// Pipeline names are valid identifiers, as `get_pipelines` rejects the others.
+fn item_ident(name: String) -> syn::Ident {
+    syn::Ident::new(&name, proc_macro2::Span::call_site())
+}
+
+/// Generates a `Debug` impl for a generated struct, listing the given fields.
+/// Vulkan handles are printed as their raw `u64`, as the structs holding them are not `Debug`.
+fn debug_impl(name: &syn::Ident, fields: &[(&str, TokenStream)]) -> TokenStream {
+    let name_str = name.to_string();
+    let field_names = fields.iter().map(|(field, _)| field);
+    let values = fields.iter().map(|(_, value)| value);
+
+    quote! {
+        impl std::fmt::Debug for #name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct(#name_str)
+                    #( .field(#field_names, &#values) )*
+                    .finish()
+            }
+        }
+    }
+}
+
+/// Returns the raw value of a Vulkan handle field of `self`, to be printed by a `Debug` impl
+fn raw_handle(field: &str) -> TokenStream {
+    let field = item_ident(field.to_string());
+    quote! { ash::vk::Handle::as_raw(self.#field) }
+}
+
+/// Generates the `get_cache` and `reset_cache` methods of a struct holding descriptor caches,
+/// where `new_cache` creates the caches missing up to the requested index
+fn cache_methods(cache_name: &syn::Ident, new_cache: TokenStream) -> TokenStream {
+    quote! {
+        pub fn get_cache(&mut self, index: usize) -> &mut #cache_name {
+            while index >= self.caches.len() {
+                self.caches.push(#new_cache);
+            }
+
+            &mut self.caches[index]
+        }
+
+        /// Frees the descriptor sets of the cache at `index`, such as the one of a material no longer drawn
+        pub fn reset_cache(&mut self, index: usize) {
+            if let Some(cache) = self.caches.get_mut(index) {
+                cache.reset();
+            }
+        }
+    }
+}
+
+/// Returns the Vulkan formats of the vertex attributes needed by an argument type.
+/// Types unknown to pipewriter are looked up into the vertex formats of the shader crate.
+pub(crate) fn get_formats(
+    arg_type: &syn::Ident,
+    vertex_formats: &HashMap<String, Vec<String>>,
+) -> Result<Vec<String>, ShaderError> {
+    match arg_type.to_string().as_str() {
+        "Vec4" => Ok(vec!["R32G32B32A32_SFLOAT".into()]),
+        "Vec3" => Ok(vec!["R32G32B32_SFLOAT".into()]),
+        "Vec2" => Ok(vec!["R32G32_SFLOAT".into()]),
+        "f32" => Ok(vec!["R32_SFLOAT".into()]),
+        "u32" => Ok(vec!["R32_UINT".into()]),
+        "i32" => Ok(vec!["R32_SINT".into()]),
+        "UVec4" => Ok(vec!["R32G32B32A32_UINT".into()]),
+        "UVec3" => Ok(vec!["R32G32B32_UINT".into()]),
+        "UVec2" => Ok(vec!["R32G32_UINT".into()]),
+        "IVec4" => Ok(vec!["R32G32B32A32_SINT".into()]),
+        "IVec3" => Ok(vec!["R32G32B32_SINT".into()]),
+        "IVec2" => Ok(vec!["R32G32_SINT".into()]),
+        // Double precision vertex formats are optional, so check the format features of the device
+        "DVec4" => Ok(vec!["R64G64B64A64_SFLOAT".into()]),
+        "DVec3" => Ok(vec!["R64G64B64_SFLOAT".into()]),
+        "DVec2" => Ok(vec!["R64G64_SFLOAT".into()]),
+        "f64" => Ok(vec!["R64_SFLOAT".into()]),
+        // Matrices take one attribute location per column
+        "Mat4" => Ok(vec!["R32G32B32A32_SFLOAT".into(); 4]),
+        "Mat3" => Ok(vec!["R32G32B32_SFLOAT".into(); 3]),
+        name => vertex_formats
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ShaderError::UnsupportedType {
+                what: "vertex attribute",
+                ty: name.to_string(),
+                span: arg_type.span(),
+            }),
+    }
+}
+
+/// Returns the pseudo-format of padding bytes within a vertex, which take no attribute location
+pub(crate) fn get_padding_format(size: usize) -> String {
+    format!("PAD{}", size)
+}
+
+/// Returns the size of a padding pseudo-format, or `None` for a proper Vulkan format
+fn get_padding_size(format: &str) -> Option<usize> {
+    format.strip_prefix("PAD")?.parse().ok()
+}
+
+/// Returns the size in bytes of a Vulkan format, by summing the bits of its components.
+/// For example `R32G32_SFLOAT` is 8 bytes and `A2B10G10R10_SNORM_PACK32` is 4 bytes.
+pub(crate) fn get_format_size(format: &str) -> usize {
+    if let Some(size) = get_padding_size(format) {
+        return size;
+    }
+
+    if let Some(pack) = format.split('_').find_map(|part| part.strip_prefix("PACK")) {
+        // Unknown formats have no size, which callers report as an error
+        return pack.parse::<usize>().map_or(0, |bits| bits / 8);
+    }
+
+    let components = format.split('_').next().unwrap_or_default();
+    let bits: usize = components
+        .split(|c: char| c.is_ascii_alphabetic())
+        .filter(|bits| !bits.is_empty())
+        .filter_map(|bits| bits.parse::<usize>().ok())
+        .sum();
+
+    bits / 8
+}
+
+/// Returns the number of attribute locations taken by a Vulkan format,
+/// which is 2 for the 64-bit formats with 3 or 4 components, and 1 for the others
+fn get_format_locations(format: &str) -> u32 {
+    let components = format.split('_').next().unwrap_or_default();
+    if components.matches("64").count() > 2 {
+        2
+    } else {
+        1
+    }
+}
+
+/// Returns the Vulkan formats of a vertex input, preferring its format override if any.
+/// An override can only replace the format of a type taking a single attribute location.
+fn get_input_formats(
+    input: &VertexInput,
+    vertex_formats: &HashMap<String, Vec<String>>,
+) -> Result<Vec<String>, ShaderError> {
+    let formats = get_formats(&input.ty, vertex_formats)?;
+    match &input.format {
+        Some(format) if formats.len() != 1 => Err(ShaderError::InvalidAttribute {
+            message: format!(
+                "Can not override the format of {}, as it takes {} attribute locations",
+                input.ty,
+                formats.len()
+            ),
+            span: format.span(),
+        }),
+        Some(format) if get_format_size(&format.value()) == 0 => {
+            Err(ShaderError::InvalidAttribute {
+                message: format!("Unknown size of vertex format {}", format.value()),
+                span: format.span(),
+            })
+        }
+        Some(format) => Ok(vec![format.value()]),
+        None => Ok(formats),
+    }
+}
+
+/// A vertex attribute read by a pipeline from one of its vertex buffers
+struct VertexAttribute {
+    location: usize,
+    format: String,
+    offset: usize,
+    binding: u32,
+}
+
+/// A vertex buffer binding of a pipeline, stepping once per vertex or once per instance
+struct VertexBinding {
+    binding: u32,
+    stride: usize,
+    instance: bool,
+}
+
+/// Returns the formats of a shared vertex layout which are not padding,
+/// with their offsets, and the stride of the layout
+fn get_layout_attributes(
+    layout: &str,
+    vertex_formats: &HashMap<String, Vec<String>>,
+) -> Result<(Vec<(String, usize)>, usize), ShaderError> {
+    let layout_formats =
+        vertex_formats
+            .get(layout)
+            .ok_or_else(|| ShaderError::UnsupportedType {
+                what: "vertex layout",
+                ty: layout.to_string(),
+                span: proc_macro2::Span::call_site(),
+            })?;
+    // Padding advances the offset of the next attribute without being an attribute itself
+    let mut layout_attributes = vec![];
+    let mut stride = 0;
+    for format in layout_formats {
+        if get_padding_size(format).is_none() {
+            layout_attributes.push((format.clone(), stride));
+        }
+        stride += get_format_size(format);
+    }
+    Ok((layout_attributes, stride))
+}
+
+/// Returns the vertex attributes of a pipeline and its vertex bindings, sorted by index.
+/// By default vertex inputs are packed one after the other, while a pipeline with a shared
+/// `vertex_layout` reads a subset of its attributes, keeping the stride of the whole layout.
+/// Inputs with another binding, such as per-instance inputs, are packed one after the other in that binding.
+fn get_vertex_attributes(
+    pipeline: &Pipeline,
+    vertex_formats: &HashMap<String, Vec<String>>,
+) -> Result<(Vec<VertexAttribute>, Vec<VertexBinding>), ShaderError> {
+    let layout = match &pipeline.state.vertex_layout {
+        Some(layout) => Some((layout, get_layout_attributes(layout, vertex_formats)?)),
+        None => None,
+    };
+
+    let mut attributes = vec![];
+    let mut location = 0;
+    // The vertex binding is always there, even without vertex inputs
+    let mut bindings = vec![VertexBinding {
+        binding: 0,
+        stride: 0,
+        instance: false,
+    }];
+    // Index of the next vertex input, which reads the same attribute of a shared vertex layout
+    let mut vertex_index = 0;
+
+    for input in &pipeline.inputs {
+        let formats = get_input_formats(input, vertex_formats)?;
+        let binding = input.binding.unwrap_or(input.instance as u32);
+
+        let index = match bindings.iter().position(|b| b.binding == binding) {
+            Some(index) => index,
+            None => {
+                bindings.push(VertexBinding {
+                    binding,
+                    stride: 0,
+                    instance: input.instance,
+                });
+                bindings.len() - 1
+            }
+        };
+        // Attributes of a binding step at the same rate, except for the vertex binding without attributes yet
+        let first = !attributes
+            .iter()
+            .any(|a: &VertexAttribute| a.binding == binding);
+        if first {
+            bindings[index].instance = input.instance;
+        } else if bindings[index].instance != input.instance {
+            return Err(ShaderError::InvalidAttribute {
+                message: format!(
+                    "Vertex input {} of pipeline {} can not mix per-vertex and per-instance inputs in binding {}",
+                    input.ty, pipeline.name, binding
+                ),
+                span: input.ty.span(),
+            });
+        }
+
+        if let (Some((layout, (layout_attributes, _))), 0, false) =
+            (&layout, binding, input.instance)
+        {
+            if formats.len() != 1 {
+                return Err(ShaderError::InvalidAttribute {
+                    message: format!(
+                        "Vertex input {} of pipeline {} needs to take a single location to read from vertex layout {}",
+                        input.ty,
+                        pipeline.name,
+                        layout
+                    ),
+                    span: input.ty.span(),
+                });
+            }
+
+            let attribute = input.attribute.unwrap_or(vertex_index) as usize;
+            let (layout_format, offset) =
+                layout_attributes
+                    .get(attribute)
+                    .ok_or_else(|| ShaderError::InvalidAttribute {
+                        message: format!("Vertex layout {} has no attribute {}", layout, attribute),
+                        span: input.ty.span(),
+                    })?;
+
+            let format = input
+                .format
+                .as_ref()
+                .map_or_else(|| layout_format.clone(), |format| format.value());
+            let locations = get_format_locations(&format);
+            attributes.push(VertexAttribute {
+                location,
+                format,
+                offset: *offset,
+                binding,
+            });
+            location += locations as usize;
+            vertex_index += 1;
+            continue;
+        }
+
+        for format in formats {
+            let size = get_format_size(&format);
+            if get_padding_size(&format).is_none() {
+                let locations = get_format_locations(&format);
+                attributes.push(VertexAttribute {
+                    location,
+                    format,
+                    offset: bindings[index].stride,
+                    binding,
+                });
+                location += locations as usize;
+            }
+            bindings[index].stride += size;
+        }
+    }
+
+    // Vertex inputs read from a shared vertex layout keep the stride of the whole layout
+    if let (Some((_, (_, stride))), false) = (&layout, bindings[0].instance) {
+        bindings[0].stride = *stride;
+    }
+
+    bindings.sort_by_key(|b| b.binding);
+    Ok((attributes, bindings))
+}
+
+pub fn set_layout_bindings(uniforms: &[Uniform], set: u32) -> TokenStream {
+    let mut gen = quote! {};
+
+    let set_uniforms = uniforms.iter().filter(|u| u.descriptor_set == set);
+
+    for uniform in set_uniforms {
+        let binding = uniform.binding;
+        let descriptor_type = uniform.descriptor_type;
+        let stages = &uniform.stages;
+        let count = uniform.count;
+        gen.extend(quote! {
+            vk::DescriptorSetLayoutBinding::builder()
+                .binding(#binding)
+                .descriptor_type(#descriptor_type)
+                .descriptor_count(#count)
+                .stage_flags(#( #stages )|*)
+                .build(),
+        });
+    }
+
+    gen
+}
+
+/// Returns the `vk::DescriptorBindingFlags` of each binding of a set, in the order of
+/// `set_layout_bindings`, or `None` when no binding of the set has flags
+fn set_layout_binding_flags(uniforms: &[Uniform], set: u32) -> Option<TokenStream> {
+    let set_uniforms: Vec<&Uniform> = uniforms
+        .iter()
+        .filter(|u| u.descriptor_set == set)
+        .collect();
+    if set_uniforms.iter().all(|u| u.binding_flags.is_empty()) {
+        return None;
+    }
+
+    let flags = set_uniforms.iter().map(|uniform| {
+        let flags = uniform
+            .binding_flags
+            .iter()
+            .map(|flag| item_ident(flag.to_string()));
+        quote! { vk::DescriptorBindingFlags::from_raw(0 #( | vk::DescriptorBindingFlags::#flags.as_raw() )*) }
+    });
+    Some(quote! { #( #flags, )* })
+}
+
+/// Generates `reflection()`, returning a `UniformInfo` for each binding of the pipeline
+/// sorted by set and binding, with the stages of the shaders declaring it
+fn reflection_method(uniforms: &[Uniform]) -> TokenStream {
+    let mut bindings: Vec<&Uniform> = uniforms.iter().collect();
+    bindings.sort_by_key(|uniform| (uniform.descriptor_set, uniform.binding));
+
+    let infos = bindings.iter().map(|uniform| {
+        let name = uniform.name.to_string();
+        let set = uniform.descriptor_set;
+        let binding = uniform.binding;
+        let stages = &uniform.stages;
+        let descriptor_type = uniform.descriptor_type;
+        quote! {
+            UniformInfo {
+                name: #name,
+                set: #set,
+                binding: #binding,
+                descriptor_type: #descriptor_type,
+                stages: vk::ShaderStageFlags::from_raw(0 #( | #stages.as_raw() )*),
+            }
+        }
+    });
+
+    quote! {
+        /// Returns the descriptors this pipeline expects, sorted by set and binding
+        pub fn reflection() -> &'static [UniformInfo] {
+            const UNIFORMS: &[UniformInfo] = &[ #( #infos, )* ];
+            UNIFORMS
+        }
+    }
+}
+
+fn get_sorted_sets(uniforms: &[Uniform]) -> Vec<u32> {
+    let sets: HashSet<_> = uniforms.iter().map(|u| u.descriptor_set).collect();
+    let mut sets: Vec<_> = sets.into_iter().collect();
+    sets.sort();
+    sets
+}
+
+/// Returns the number of set layouts of a pipeline, one for each set up to the highest one used,
+/// so that the index of a layout is the number of its set
+fn get_set_count(uniforms: &[Uniform]) -> u32 {
+    uniforms
+        .iter()
+        .map(|u| u.descriptor_set + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+pub fn set_layouts_methods(uniforms: &[Uniform], origin: &str) -> TokenStream {
+    let set_layout_msg = format!(
+        "Failed to create Vulkan descriptor set layout for {}",
+        origin
+    );
+
+    let mut gen = quote! {
+        pub fn create_set_layout(
+            device: &Device,
+            bindings: &[vk::DescriptorSetLayoutBinding],
+        ) -> vk::DescriptorSetLayout {
+            Self::try_create_set_layout(device, bindings).expect(#set_layout_msg)
+        }
+
+        pub fn try_create_set_layout(
+            device: &Device,
+            bindings: &[vk::DescriptorSetLayoutBinding],
+        ) -> Result<vk::DescriptorSetLayout, vk::Result> {
+            let set_layout_info = vk::DescriptorSetLayoutCreateInfo::builder()
+                .bindings(bindings)
+                .build();
+            unsafe { device.create_descriptor_set_layout(&set_layout_info, None) }
+        }
+
+        /// Creates a set layout with the flags of each binding, such as `PARTIALLY_BOUND` for arrays
+        /// of descriptors. Bindings updated after bind need a pool created with `UPDATE_AFTER_BIND`.
+        pub fn create_set_layout_with_flags(
+            device: &Device,
+            bindings: &[vk::DescriptorSetLayoutBinding],
+            binding_flags: &[vk::DescriptorBindingFlags],
+        ) -> vk::DescriptorSetLayout {
+            Self::try_create_set_layout_with_flags(device, bindings, binding_flags)
+                .expect(#set_layout_msg)
+        }
+
+        pub fn try_create_set_layout_with_flags(
+            device: &Device,
+            bindings: &[vk::DescriptorSetLayoutBinding],
+            binding_flags: &[vk::DescriptorBindingFlags],
+        ) -> Result<vk::DescriptorSetLayout, vk::Result> {
+            let update_after_bind = binding_flags
+                .iter()
+                .any(|flags| flags.contains(vk::DescriptorBindingFlags::UPDATE_AFTER_BIND));
+            let flags = if update_after_bind {
+                vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL
+            } else {
+                vk::DescriptorSetLayoutCreateFlags::empty()
+            };
+
+            let mut binding_flags_info = vk::DescriptorSetLayoutBindingFlagsCreateInfo::builder()
+                .binding_flags(binding_flags)
+                .build();
+            let set_layout_info = vk::DescriptorSetLayoutCreateInfo::builder()
+                .flags(flags)
+                .bindings(bindings)
+                .push_next(&mut binding_flags_info)
+                .build();
+            unsafe { device.create_descriptor_set_layout(&set_layout_info, None) }
+        }
+    };
+
+    let sets = get_sorted_sets(uniforms);
+    let set_count = get_set_count(uniforms);
+
+    let mut set_layouts = quote! {};
+    for set in 0..set_count {
+        // Sets skipped by the shaders get an empty layout, keeping the following ones at their index
+        if !sets.contains(&set) {
+            set_layouts.extend(quote! {
+                Self::try_create_set_layout(device, &[]),
+            });
+            continue;
+        }
+
+        let bindings = set_layout_bindings(uniforms, set);
+        set_layouts.extend(match set_layout_binding_flags(uniforms, set) {
+            Some(binding_flags) => quote! {
+                Self::try_create_set_layout_with_flags(
+                    device,
+                    &[
+                        #bindings
+                    ],
+                    &[
+                        #binding_flags
+                    ]
+                ),
+            },
+            None => quote! {
+                Self::try_create_set_layout(
+                    device,
+                    &[
+                        #bindings
+                    ]
+                ),
+            },
+        })
+    }
+
+    let set_count = set_count as usize;
+    let binding_counts = sets.iter().map(|set| {
+        uniforms
+            .iter()
+            .filter(|u| u.descriptor_set == *set)
+            .map(|u| u.binding)
+            .collect::<HashSet<_>>()
+            .len()
+    });
+
+    let (dynamic_sets, dynamic_counts): (Vec<_>, Vec<_>) = sets
+        .iter()
+        .map(|set| {
+            let count = uniforms
+                .iter()
+                .filter(|u| {
+                    u.descriptor_set == *set
+                        && u.descriptor_type == DescriptorType::UniformBufferDynamic
+                })
+                .count();
+            (*set, count)
+        })
+        .filter(|(_, count)| *count > 0)
+        .unzip();
+
+    let reflection = reflection_method(uniforms);
+
+    gen.extend(quote! {
+        #reflection
+
+        pub fn new_set_layouts(device: &Device) -> Vec<vk::DescriptorSetLayout> {
+            Self::try_new_set_layouts(device).expect(#set_layout_msg)
+        }
+
+        /// Creates the descriptor set layouts of this pipeline, destroying the ones already created
+        /// when any of them fails
+        pub fn try_new_set_layouts(device: &Device) -> Result<Vec<vk::DescriptorSetLayout>, vk::Result> {
+            let results: Vec<Result<vk::DescriptorSetLayout, vk::Result>> = vec![
+                #set_layouts
+            ];
+            if let Some(err) = results.iter().find_map(|result| result.err()) {
+                for set_layout in results.iter().flatten() {
+                    unsafe { device.destroy_descriptor_set_layout(*set_layout, None) };
+                }
+                return Err(err);
+            }
+            Ok(results.into_iter().flatten().collect())
+        }
+
+        /// Returns the number of descriptor set layouts of this pipeline, up to the highest set used,
+        /// including the empty layouts of the sets skipped by the shaders
+        pub fn set_count() -> usize {
+            #set_count
+        }
+
+        /// Returns the number of dynamic offsets expected when binding a descriptor set
+        pub fn dynamic_offset_count(set: u32) -> usize {
+            match set {
+                #( #dynamic_sets => #dynamic_counts, )*
+                _ => 0,
+            }
+        }
+
+        /// Returns the number of bindings of a descriptor set, or 0 for a set not used by this pipeline
+        pub fn binding_count(set: u32) -> usize {
+            match set {
+                #( #sets => #binding_counts, )*
+                _ => 0,
+            }
+        }
+    });
+
+    gen
+}
+
+pub fn write_set_methods(uniforms: &[Uniform]) -> TokenStream {
+    let mut gen = quote! {};
+
+    // Arrays of descriptors are written by element, with a method for each array
+    for uniform in uniforms.iter().filter(|u| u.count > 1) {
+        let name = &uniform.name;
+        let binding = uniform.binding;
+        let descriptor_type = uniform.descriptor_type;
+        let ty = uniform.get_write_set_type();
+        let setter = uniform.get_info_setter();
+        let info = uniform.get_descriptor_info(&quote! { resource });
+        let count = uniform.count;
+        let write_name = item_ident(format!("write_{}", name));
+        let msg = format!(
+            "Elements {{}}..{{}} out of the {} descriptors of {}",
+            count, name
+        );
+        gen.extend(quote! {
+            /// Writes consecutive elements of an array of descriptors, starting from `first_element`
+            pub fn #write_name(&self, set: vk::DescriptorSet, first_element: u32, resources: &[&#ty]) {
+                let end = first_element as usize + resources.len();
+                assert!(end <= #count as usize, #msg, first_element, end);
+                if resources.is_empty() {
+                    return;
+                }
+
+                let infos: Vec<_> = resources.iter().map(|resource| #info).collect();
+                let writes = [
+                    vk::WriteDescriptorSet::builder()
+                        .dst_set(set)
+                        .dst_binding(#binding)
+                        .dst_array_element(first_element)
+                        .descriptor_type(#descriptor_type)
+                        .#setter(&infos)
+                        .build(),
+                ];
+
+                unsafe {
+                    self.device.update_descriptor_sets(&writes, &[]);
+                }
+            }
+        });
+    }
+
+    for set in get_sorted_sets(uniforms) {
+        let set_uniforms: Vec<&Uniform> = uniforms
+            .iter()
+            .filter(|u| u.descriptor_set == set)
+            .collect();
+        let mut writes = quote! {};
+        // The infos are bound to locals, as the writes only keep raw pointers to them
+        let mut infos = quote! {};
+
+        for uniform in &set_uniforms {
+            let name = &uniform.name;
+            let binding = uniform.binding;
+            let descriptor_type = uniform.descriptor_type;
+            let setter = uniform.get_info_setter();
+            let infos_name = item_ident(format!("{}_infos", name));
+            if uniform.count > 1 {
+                // Arrays are written from their first element, up to the length of the slice
+                let count = uniform.count;
+                let info = uniform.get_descriptor_info(&quote! { resource });
+                let msg = format!(
+                    "Expected 1 to {} descriptors for {}, found {{}}",
+                    count, name
+                );
+                infos.extend(quote! {
+                    assert!(!#name.is_empty() && #name.len() <= #count as usize, #msg, #name.len());
+                    let #infos_name: Vec<_> = #name.iter().map(|resource| #info).collect();
+                });
+            } else {
+                let info = uniform.get_info();
+                infos.extend(quote! {
+                    let #infos_name = [#info];
+                });
+            }
+            writes.extend(quote! {
+                vk::WriteDescriptorSet::builder()
+                    .dst_set(set)
+                    .dst_binding(#binding)
+                    .dst_array_element(0)
+                    .descriptor_type(#descriptor_type)
+                    .#setter(&#infos_name)
+                    .build(),
+            });
+        }
+
+        let args = set_uniforms.iter().map(|u| {
+            let name = &u.name;
+            let ty = u.get_write_set_type();
+            if u.count > 1 {
+                quote! { #name: &[&#ty] }
+            } else {
+                quote! { #name: &#ty }
+            }
+        });
+
+        // Uniform buffers need to be large enough for the range bound
+        let range_checks = set_uniforms
+            .iter()
+            .filter(|u| u.count == 1)
+            .filter_map(|u| {
+                let name = &u.name;
+                let range = u.range? as u64;
+                let ty = &u.ty;
+                let msg = format!(
+                    "Buffer {} is smaller than the {} bytes of {}",
+                    name,
+                    range,
+                    quote!(#ty)
+                );
+                Some(quote! {
+                    debug_assert!(#name.size >= #range, #msg);
+                })
+            });
+
+        let arguments = quote! {
+            &self,
+            set: vk::DescriptorSet
+            #( ,#args )*
+        };
+
+        let write_set_sign = item_ident(format!("write_set_{}", set));
+        gen.extend(quote! {
+            pub fn #write_set_sign(
+                #arguments
+            ) {
+                #( #range_checks )*
+                #infos
+
+                let writes = [
+                    #writes
+                ];
+
+                unsafe {
+                    self.device.update_descriptor_sets(&writes, &[]);
+                }
+            }
+        });
+    }
+
+    gen
+}
+
+/// Generates a `Pipeline{Name}Set{N}` struct for each descriptor set, with a field for each binding
+/// named after its uniform, and a `write_set_N_with` method taking it instead of positional arguments
+fn set_structs(pipeline: &Pipeline) -> TokenStream {
+    let pipeline_name = item_ident(pipeline.type_name(""));
+
+    let mut structs = quote! {};
+    let mut methods = quote! {};
+
+    for set in get_sorted_sets(&pipeline.uniforms) {
+        let set_uniforms: Vec<&Uniform> = pipeline
+            .uniforms
+            .iter()
+            .filter(|u| u.descriptor_set == set)
+            .collect();
+
+        let fields = set_uniforms.iter().map(|u| {
+            let name = &u.name;
+            let ty = u.get_write_set_type();
+            if u.count > 1 {
+                quote! { pub #name: &'a [&'a #ty] }
+            } else {
+                quote! { pub #name: &'a #ty }
+            }
+        });
+
+        let set_name = item_ident(format!("{}Set{}", pipeline_name, set));
+        let doc = format!(
+            " Resources of descriptor set {} of `{}`, to be written with `write_set_{}_with`.",
+            set, pipeline_name, set
+        );
+        structs.extend(quote! {
+            #[doc = #doc]
+            #[derive(Clone, Copy)]
+            pub struct #set_name<'a> {
+                #( #fields, )*
+            }
+        });
+
+        let write_set = item_ident(format!("write_set_{}", set));
+        let write_set_with = item_ident(format!("write_set_{}_with", set));
+        let args = set_uniforms.iter().map(|u| &u.name);
+        methods.extend(quote! {
+            pub fn #write_set_with(&self, set: vk::DescriptorSet, resources: &#set_name) {
+                self.#write_set(set #( , resources.#args )*);
+            }
+        });
+    }
+
+    if methods.is_empty() {
+        return structs;
+    }
+
+    quote! {
+        #structs
+
+        impl #pipeline_name {
+            #methods
+        }
+    }
+}
+
+/// Returns the `crate::Pipeline` string used to tell the user
+/// which pipeline a generated panic message is coming from
+fn get_origin(crate_module: &CrateModule, pipeline: &Pipeline) -> String {
+    format!("{}::{}", crate_module.name, pipeline.name)
+}
+
+/// Generates the cache of a pipeline, owning the descriptor pool its sets are allocated from
+fn descriptor_pool(
+    pipeline_cache_name: &syn::Ident,
+    origin: &str,
+    uniforms: &[Uniform],
+) -> TokenStream {
+    // Count the descriptors of each type
+    let mut pool_counts: Vec<(DescriptorType, u32)> = vec![];
+    for uniform in uniforms {
+        match pool_counts
+            .iter_mut()
+            .find(|(ty, _)| *ty == uniform.descriptor_type)
+        {
+            Some((_, count)) => *count += uniform.count,
+            None => pool_counts.push((uniform.descriptor_type, uniform.count)),
+        }
+    }
+    let (pool_types, pool_counts): (Vec<_>, Vec<_>) = pool_counts.into_iter().unzip();
+
+    // Sets with bindings updated after bind can only be allocated from pools supporting it
+    let update_after_bind = uniforms
+        .iter()
+        .any(|u| u.binding_flags.contains(&"UPDATE_AFTER_BIND"));
+    let pool_flags = if update_after_bind {
+        quote! { vk::DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET | vk::DescriptorPoolCreateFlags::UPDATE_AFTER_BIND }
+    } else {
+        quote! { vk::DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET }
+    };
+    let set_count = get_set_count(uniforms);
+
+    let pool_msg = format!("Failed to create Vulkan descriptor pool for {}", origin);
+    let allocate_msg = format!("Failed to allocate Vulkan descriptor sets for {}", origin);
+    let free_msg = format!(
+        "Failed to free {{}} Vulkan descriptor sets for {}: {{:?}}",
+        origin
+    );
+    let debug = debug_impl(
+        pipeline_cache_name,
+        &[
+            ("pool", raw_handle("pool")),
+            (
+                "sets",
+                quote! { self.sets.values().map(Vec::len).sum::<usize>() },
+            ),
+        ],
+    );
+
+    quote! {
+        pub struct #pipeline_cache_name {
+            sets: HashMap<usize, Vec<vk::DescriptorSet>>,
+            pool: vk::DescriptorPool,
+            pub device: Rc<Device>,
+        }
+
+        impl #pipeline_cache_name {
+            fn create_pool(device: &Device, pool_sizes: &[vk::DescriptorPoolSize], max_sets: u32) -> vk::DescriptorPool {
+                let create_info = vk::DescriptorPoolCreateInfo::builder()
+                    .pool_sizes(pool_sizes)
+                    .max_sets(max_sets)
+                    .flags(#pool_flags)
+                    .build();
+
+                unsafe { device.create_descriptor_pool(&create_info, None) }
+                    .expect(#pool_msg)
+            }
+
+            /// Default number of copies of each descriptor set allocated per frame in flight,
+            /// such as one per node or material
+            pub const DEFAULT_SETS_PER_FRAME: u32 = 16;
+
+            /// Returns the pool sizes needed to allocate `set_copies` copies of each descriptor set of the pipeline
+            pub fn pool_sizes(set_copies: u32) -> Vec<vk::DescriptorPoolSize> {
+                vec![
+                    #(
+                        vk::DescriptorPoolSize::builder()
+                            .ty(#pool_types)
+                            .descriptor_count(#pool_counts * set_copies)
+                            .build(),
+                    )*
+                ]
+            }
+
+            /// Returns the maximum number of sets needed to allocate `set_copies` copies of each descriptor set
+            pub fn max_sets(set_copies: u32) -> u32 {
+                (#set_count * set_copies).max(1)
+            }
+
+            /// Returns the default descriptor pool sizes, holding `DEFAULT_SETS_PER_FRAME` copies of each set per frame in flight
+            pub fn default_pool_sizes(frames_in_flight: u32) -> Vec<vk::DescriptorPoolSize> {
+                Self::pool_sizes(Self::DEFAULT_SETS_PER_FRAME * frames_in_flight)
+            }
+
+            /// Returns the default maximum number of sets, scaled by the frames in flight
+            pub fn default_max_sets(frames_in_flight: u32) -> u32 {
+                Self::max_sets(Self::DEFAULT_SETS_PER_FRAME * frames_in_flight)
+            }
+
+            pub fn new(device: &Rc<Device>) -> Self {
+                Self::with_frames_in_flight(device, 1)
+            }
+
+            /// Returns a cache whose pool can hold the descriptors of multiple frames in flight
+            pub fn with_frames_in_flight(device: &Rc<Device>, frames_in_flight: u32) -> Self {
+                Self::with_capacity(device, Self::DEFAULT_SETS_PER_FRAME, frames_in_flight)
+            }
+
+            /// Returns a cache whose pool can hold `sets_per_frame` copies of each descriptor set
+            /// for each of the frames in flight
+            pub fn with_capacity(device: &Rc<Device>, sets_per_frame: u32, frames_in_flight: u32) -> Self {
+                let set_copies = sets_per_frame * frames_in_flight;
+                let pool_sizes = Self::pool_sizes(set_copies);
+                let max_sets = Self::max_sets(set_copies);
+                let pool = Self::create_pool(device, &pool_sizes, max_sets);
+
+                Self {
+                    sets: HashMap::new(),
+                    pool,
+                    device: device.clone(),
+                }
+            }
+
+            /// Replaces the descriptor pool with a new one with different sizes.
+            /// Descriptor sets allocated from the old pool are no longer valid.
+            pub fn resize(&mut self, pool_sizes: &[vk::DescriptorPoolSize], max_sets: u32) {
+                self.sets.clear();
+                unsafe { self.device.destroy_descriptor_pool(self.pool, None) };
+                self.pool = Self::create_pool(&self.device, pool_sizes, max_sets);
+            }
+
+            pub fn allocate(&mut self, layouts: &[vk::DescriptorSetLayout]) -> Vec<vk::DescriptorSet> {
+                let create_info = vk::DescriptorSetAllocateInfo::builder()
+                    .descriptor_pool(self.pool)
+                    .set_layouts(layouts)
+                    .build();
+
+                unsafe { self.device.allocate_descriptor_sets(&create_info) }
+                    .expect(#allocate_msg)
+            }
+
+            /// Returns the descriptor sets of a frame in flight, allocated with `layouts` on first use,
+            /// usually the `get_set_layouts()` of the pipeline, and reused by the following frames
+            /// with the same index until the pool is resized
+            pub fn get_or_allocate_sets(&mut self, frame: usize, layouts: &[vk::DescriptorSetLayout]) -> &[vk::DescriptorSet] {
+                if !self.sets.contains_key(&frame) {
+                    let sets = self.allocate(layouts);
+                    self.sets.insert(frame, sets);
+                }
+
+                &self.sets[&frame]
+            }
+
+            /// Frees the descriptor sets of a frame in flight, returning their space to the pool
+            pub fn free_sets(&mut self, frame: usize) {
+                if let Some(sets) = self.sets.remove(&frame) {
+                    if !sets.is_empty() {
+                        self.free(&sets);
+                    }
+                }
+            }
+
+            /// Frees the descriptor sets of all the frames in flight
+            pub fn reset(&mut self) {
+                let frames: Vec<usize> = self.sets.keys().copied().collect();
+                for frame in frames {
+                    self.free_sets(frame);
+                }
+            }
+
+            pub fn free(&self, descriptors: &[vk::DescriptorSet]) {
+                if let Err(err) = self.try_free(descriptors) {
+                    panic!(#free_msg, descriptors.len(), err);
+                }
+            }
+
+            /// Frees descriptor sets allocated from this pool, returning the error instead of panicking
+            pub fn try_free(&self, descriptors: &[vk::DescriptorSet]) -> Result<(), vk::Result> {
+                unsafe { self.device.free_descriptor_sets(self.pool, descriptors) }
+            }
+        }
+
+        #debug
+
+        impl Drop for #pipeline_cache_name {
+            fn drop(&mut self) {
+                unsafe { self.device.destroy_descriptor_pool(self.pool, None) };
+            }
+        }
+    }
+}
+
+/// Generates `new_layout()` and `try_new_layout()`, which creates a pipeline layout from the given set layouts
+/// and the push constant ranges of the shaders
+fn new_layout_method(origin: &str, push_constants: &[PushConstant]) -> TokenStream {
+    let layout_msg = format!("Failed to create Vulkan pipeline layout for {}", origin);
+
+    let ranges = push_constants.iter().map(|push_constant| {
+        let stages = &push_constant.stages;
+        let size = push_constant.size as u32;
+        quote! {
+            vk::PushConstantRange::builder()
+                .stage_flags(#( #stages )|*)
+                .offset(0)
+                .size(#size)
+                .build()
+        }
+    });
+
+    quote! {
+        /// Returns the push constant ranges of the shaders, all starting at offset 0
+        pub fn push_constant_ranges() -> Vec<vk::PushConstantRange> {
+            vec![ #( #ranges ),* ]
+        }
+
+        pub fn new_layout(device: &Rc<Device>, set_layouts: &[vk::DescriptorSetLayout]) -> vk::PipelineLayout {
+            Self::try_new_layout(device, set_layouts).expect(#layout_msg)
+        }
+
+        pub fn try_new_layout(device: &Rc<Device>, set_layouts: &[vk::DescriptorSetLayout]) -> Result<vk::PipelineLayout, vk::Result> {
+            let push_constant_ranges = Self::push_constant_ranges();
+            let create_info = vk::PipelineLayoutCreateInfo::builder()
+                .set_layouts(set_layouts)
+                .push_constant_ranges(&push_constant_ranges)
+                .build();
+            unsafe { device.create_pipeline_layout(&create_info, None) }
+        }
+    }
+}
+
+/// Generates a `push_{name}()` method for each push constant block, recording an update of its
+/// bytes from a value of the host type matching the one of the shaders
+fn push_constant_methods(pipeline: &Pipeline, origin: &str) -> TokenStream {
+    let mut gen = TokenStream::new();
+
+    for push_constant in &pipeline.push_constants {
+        let name = push_constant.name.to_string();
+        let method = syn::Ident::new(
+            &format!("push_{}", name.trim_start_matches('_')),
+            proc_macro2::Span::call_site(),
+        );
+        let stages = &push_constant.stages;
+        let size = push_constant.size;
+        let size_msg = format!(
+            "Size {{}} of the value pushed as {} of {} is not {}",
+            name, origin, size
+        );
+        let doc = format!(
+            " Records an update of the `{}` push constants, from a value of {} bytes",
+            name, size
+        );
+
+        gen.extend(quote! {
+            #[doc = #doc]
+            pub fn #method<T: Copy>(&self, command_buffer: vk::CommandBuffer, value: &T) {
+                debug_assert!(std::mem::size_of::<T>() == #size, #size_msg, std::mem::size_of::<T>());
+                unsafe {
+                    let bytes = std::slice::from_raw_parts(value as *const T as *const u8, std::mem::size_of::<T>());
+                    self.device.cmd_push_constants(command_buffer, self.layout, #( #stages )|*, 0, bytes);
+                }
+            }
+        });
+    }
+
+    gen
+}
+
+/// Generates a `Pipeline{Name}Spec` struct for the specialization constants of a pipeline,
+/// defaulting to the values declared by the shaders
+fn spec_struct(pipeline: &Pipeline, spec_name: &syn::Ident) -> TokenStream {
+    let names: Vec<_> = pipeline.spec_constants.iter().map(|s| &s.name).collect();
+    // Booleans are 32 bits wide in SPIR-V
+    let types = pipeline
+        .spec_constants
+        .iter()
+        .map(|s| match s.ty.to_string().as_str() {
+            "bool" => quote! { vk::Bool32 },
+            _ => {
+                let ty = &s.ty;
+                quote! { #ty }
+            }
+        });
+    let defaults = pipeline
+        .spec_constants
+        .iter()
+        .map(|s| match s.ty.to_string().as_str() {
+            "bool" => {
+                let default = &s.default;
+                quote! { (#default) as vk::Bool32 }
+            }
+            _ => s.default.clone(),
+        });
+    let ids = pipeline.spec_constants.iter().map(|s| s.id);
+    // All the supported types take 4 bytes, so fields are tightly packed
+    let offsets = (0..pipeline.spec_constants.len()).map(|i| (i * 4) as u32);
+    let count = pipeline.spec_constants.len();
+
+    quote! {
+        /// Specialization constants of the pipeline, defaulting to the values of the shaders
+        #[repr(C)]
+        #[derive(Clone, Copy, Debug)]
+        pub struct #spec_name {
+            #( pub #names: #types, )*
+        }
+
+        impl Default for #spec_name {
+            fn default() -> Self {
+                Self {
+                    #( #names: #defaults, )*
+                }
+            }
+        }
+
+        impl #spec_name {
+            pub fn map_entries() -> [vk::SpecializationMapEntry; #count] {
+                [
+                    #(
+                        vk::SpecializationMapEntry::builder()
+                            .constant_id(#ids)
+                            .offset(#offsets)
+                            .size(4)
+                            .build(),
+                    )*
+                ]
+            }
+
+            pub fn as_bytes(&self) -> &[u8] {
+                unsafe {
+                    std::slice::from_raw_parts(
+                        self as *const Self as *const u8,
+                        std::mem::size_of::<Self>(),
+                    )
+                }
+            }
+        }
+    }
+}
+
+/// Generates a `Pipeline{Name}Spec` struct for the specialization constants of a pipeline
+/// and `new_with_spec()` to override them
+fn spec_constants(pipeline: &Pipeline) -> TokenStream {
+    if pipeline.spec_constants.is_empty() {
+        return TokenStream::new();
+    }
+
+    let pipeline_name = item_ident(pipeline.type_name(""));
+    let spec_name = item_ident(pipeline.type_name("Spec"));
+    let spec_struct = spec_struct(pipeline, &spec_name);
+
+    quote! {
+        #spec_struct
+
+        impl #pipeline_name {
+            /// Returns this pipeline with the given specialization constants
+            pub fn new_with_spec(shader_module: &ShaderModule, render_pass: vk::RenderPass, spec: &#spec_name) -> Self {
+                Self::builder(shader_module, render_pass).spec(*spec).build()
+            }
+        }
+    }
+}
+
+/// Generates a `Pipeline{Name}Builder` with the optional states of a graphics pipeline,
+/// which fall back to the ones of its shaders and their attributes
+fn pipeline_builder(pipeline: &Pipeline, pipeline_msg: &str) -> TokenStream {
+    let pipeline_name = item_ident(pipeline.type_name(""));
+    let builder_name = item_ident(pipeline.type_name("Builder"));
+    let pipeline_cache_name = item_ident(pipeline.cache_name());
+
+    let (spec_field, spec_init, spec_setter, specialization, specialization_arg) =
+        if pipeline.spec_constants.is_empty() {
+            (
+                TokenStream::new(),
+                TokenStream::new(),
+                TokenStream::new(),
+                TokenStream::new(),
+                quote! { None },
+            )
+        } else {
+            let spec_name = item_ident(pipeline.type_name("Spec"));
+            (
+                quote! { spec: #spec_name, },
+                quote! { spec: #spec_name::default(), },
+                quote! {
+                    /// Sets the specialization constants, instead of the default values of the shaders
+                    pub fn spec(mut self, spec: #spec_name) -> Self {
+                        self.spec = spec;
+                        self
+                    }
+                },
+                quote! {
+                    let map_entries = #spec_name::map_entries();
+                    let specialization = vk::SpecializationInfo::builder()
+                        .map_entries(&map_entries)
+                        .data(self.spec.as_bytes())
+                        .build();
+                },
+                quote! { Some(&specialization) },
+            )
+        };
+
+    quote! {
+        /// Options to create a pipeline, falling back to the states of its shaders and their attributes
+        pub struct #builder_name<'s> {
+            shader_module: &'s ShaderModule,
+            render_pass: vk::RenderPass,
+            rendering_formats: Option<RenderingFormats>,
+            front_face: vk::FrontFace,
+            pipeline_cache: vk::PipelineCache,
+            frames_in_flight: u32,
+            sets_per_frame: u32,
+            #spec_field
+        }
+
+        impl<'s> #builder_name<'s> {
+            /// Sets the winding of front faces, such as the opposite one for mirrored geometry
+            pub fn front_face(mut self, front_face: vk::FrontFace) -> Self {
+                self.front_face = front_face;
+                self
+            }
+
+            /// Creates the pipeline for dynamic rendering with these attachment formats,
+            /// which needs a null render pass and the `VK_KHR_dynamic_rendering` extension
+            pub fn rendering_formats(mut self, rendering_formats: RenderingFormats) -> Self {
+                self.rendering_formats = Some(rendering_formats);
+                self
+            }
+
+            /// Creates the pipeline through a `vk::PipelineCache` to reuse previous compilations
+            pub fn pipeline_cache(mut self, pipeline_cache: vk::PipelineCache) -> Self {
+                self.pipeline_cache = pipeline_cache;
+                self
+            }
+
+            /// Sizes the descriptor pools of the caches of the pipeline for multiple frames in flight
+            pub fn frames_in_flight(mut self, frames_in_flight: u32) -> Self {
+                self.frames_in_flight = frames_in_flight;
+                self
+            }
+
+            /// Sizes the descriptor pools of the caches of the pipeline for this many copies of each descriptor set per frame in flight
+            pub fn sets_per_frame(mut self, sets_per_frame: u32) -> Self {
+                self.sets_per_frame = sets_per_frame;
+                self
+            }
+
+            #spec_setter
+
+            pub fn build(self) -> #pipeline_name {
+                self.try_build().expect(#pipeline_msg)
+            }
+
+            /// Returns the pipeline, or the error of the driver when it fails to create it
+            pub fn try_build(self) -> Result<#pipeline_name, vk::Result> {
+                #specialization
+                let mut pipeline = #pipeline_name::try_create(
+                    self.shader_module,
+                    self.render_pass,
+                    self.rendering_formats,
+                    self.front_face,
+                    self.pipeline_cache,
+                    #specialization_arg,
+                )?;
+                pipeline.set_frames_in_flight(self.frames_in_flight);
+                pipeline.set_sets_per_frame(self.sets_per_frame);
+                Ok(pipeline)
+            }
+        }
+
+        impl #pipeline_name {
+            /// Returns a builder to create this pipeline with optional states
+            pub fn builder(shader_module: &ShaderModule, render_pass: vk::RenderPass) -> #builder_name {
+                #builder_name {
+                    shader_module,
+                    render_pass,
+                    rendering_formats: None,
+                    front_face: Self::FRONT_FACE,
+                    pipeline_cache: vk::PipelineCache::null(),
+                    frames_in_flight: 1,
+                    sets_per_frame: #pipeline_cache_name::DEFAULT_SETS_PER_FRAME,
+                    #spec_init
+                }
+            }
+        }
+    }
+}
+
+/// Generates `required_features()` and `required_extensions()`, which return
+/// what a device needs to enable for the given features and extensions
+fn required_features_methods(features: &[&str], extensions: &[&str]) -> TokenStream {
+    let features: Vec<_> = features
+        .iter()
+        .map(|feature| syn::Ident::new(feature, proc_macro2::Span::call_site()))
+        .collect();
+    let feature_msgs = features
+        .iter()
+        .map(|feature| format!("Missing device feature {}", feature));
+    let extensions = extensions.iter().map(|extension| {
+        syn::LitByteStr::new(
+            format!("{}\0", extension).as_bytes(),
+            proc_macro2::Span::call_site(),
+        )
+    });
+
+    quote! {
+        /// Returns the device features to enable before creating this
+        pub fn required_features() -> vk::PhysicalDeviceFeatures {
+            vk::PhysicalDeviceFeatures::builder()
+                #( .#features(true) )*
+                .build()
+        }
+
+        /// Checks in debug builds that the physical device supports the features needed by this
+        pub fn check_features(supported: &vk::PhysicalDeviceFeatures) {
+            #( debug_assert!(supported.#features == vk::TRUE, #feature_msgs); )*
+        }
+
+        /// Returns the device extensions to enable before creating this
+        pub fn required_extensions() -> Vec<&'static CStr> {
+            vec![
+                #( CStr::from_bytes_with_nul(#extensions).expect("Failed to get extension name"), )*
+            ]
+        }
+    }
+}
+
+/// Generates a graphics pipeline with its descriptor cache, which draws through the `Pipeline` trait of vkr
+pub fn pipeline(
+    crate_module: &CrateModule,
+    pipeline: &Pipeline,
+) -> Result<TokenStream, ShaderError> {
+    let pipeline_name = item_ident(pipeline.type_name(""));
+
+    let pipeline_str = pipeline.name.to_camelcase();
+
+    let vs = pipeline.entry_point(ShaderType::Vertex);
+    let fs = pipeline.entry_point(ShaderType::Fragment);
+
+    // Generate bindings
+    let (attributes, bindings) = get_vertex_attributes(pipeline, &crate_module.vertex_formats)?;
+    // The vertex and instance bindings are referred to by their constants, the others by index
+    let binding_tokens = |binding: u32| match binding {
+        0 => (
+            quote! { Self::VERTEX_BINDING },
+            quote! { Self::VERTEX_STRIDE },
+        ),
+        1 if bindings.iter().any(|b| b.binding == 1 && b.instance) => (
+            quote! { Self::INSTANCE_BINDING },
+            quote! { Self::INSTANCE_STRIDE },
+        ),
+        binding => (
+            quote! { #binding },
+            quote! { Self::binding_stride(#binding) },
+        ),
+    };
+    let vertex_bindings = bindings.iter().map(|b| {
+        let (binding, stride) = binding_tokens(b.binding);
+        let input_rate = if b.instance {
+            quote! { vk::VertexInputRate::INSTANCE }
+        } else {
+            quote! { vk::VertexInputRate::VERTEX }
+        };
+        quote! {
+            vk::VertexInputBindingDescription::builder()
+                .binding(#binding)
+                .stride(#stride)
+                .input_rate(#input_rate)
+                .build(),
+        }
+    });
+    let vertex_bindings = quote! { #( #vertex_bindings )* };
+    let stride = bindings[0].stride;
+    let instance_stride = bindings
+        .iter()
+        .find(|b| b.binding == 1 && b.instance)
+        .map_or(0, |b| b.stride as u32);
+    let binding_indices = bindings.iter().map(|b| b.binding);
+    let binding_strides = bindings.iter().map(|b| b.stride as u32);
+
+    let mut vertex_attributes = TokenStream::new();
+    let mut attribute_infos = vec![];
+
+    // Document what the pipeline expects, to be shown on hover
+    let mut docs = vec![
+        format!(
+            " Pipeline generated from the `{}` shaders of `{}`.",
+            pipeline.name, crate_module.name
+        ),
+        String::new(),
+        " # Vertex attributes".to_string(),
+    ];
+
+    for VertexAttribute {
+        location: loc,
+        format,
+        offset,
+        binding: binding_index,
+    } in attributes
+    {
+        let format_ident = syn::Ident::new(&format, proc_macro2::Span::call_site());
+        let (binding, _) = binding_tokens(binding_index);
+
+        let attribute = quote! {
+            vk::VertexInputAttributeDescription::builder()
+                .binding(#binding)
+                .location(#loc as u32)
+                .format(vk::Format::#format_ident)
+                .offset(#offset as u32)
+                .build(),
+        };
+
+        let mut binding_doc = match binding_index {
+            0 => String::new(),
+            binding => format!(" of binding {}", binding),
+        };
+        if bindings
+            .iter()
+            .any(|b| b.binding == binding_index && b.instance)
+        {
+            binding_doc.push_str(" per instance");
+        }
+        docs.push(format!(
+            " - location {}: `{}` at offset {}{}",
+            loc, format, offset, binding_doc
+        ));
+
+        vertex_attributes.extend(attribute);
+
+        let location = loc as u32;
+        let offset = offset as u32;
+        attribute_infos.push(quote! {
+            VertexAttributeInfo {
+                location: #location,
+                binding: #binding_index,
+                format: vk::Format::#format_ident,
+                offset: #offset,
+            }
+        });
+    }
+
+    docs.push(String::new());
+    docs.push(" # Descriptor bindings".to_string());
+    for uniform in &pipeline.uniforms {
+        docs.push(format!(
+            " - set {}, binding {}: `{}` of type `{}` ({})",
+            uniform.descriptor_set,
+            uniform.binding,
+            uniform.name,
+            uniform.descriptor_type.name(),
+            uniform
+                .stages
+                .iter()
+                .map(|stage| format!("{:?}", stage))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    let pipeline_cache_name = item_ident(pipeline.cache_name());
+
+    let origin = get_origin(crate_module, pipeline);
+    let pipeline_cache = descriptor_pool(&pipeline_cache_name, &origin, &pipeline.uniforms);
+    let cache_methods = cache_methods(
+        &pipeline_cache_name,
+        quote! { #pipeline_cache_name::with_capacity(&self.device, self.sets_per_frame, self.frames_in_flight) },
+    );
+
+    let set_layouts_methods = set_layouts_methods(&pipeline.uniforms, &origin);
+    let write_set_methods = write_set_methods(&pipeline.uniforms);
+    let set_structs = set_structs(pipeline);
+    let debug = debug_impl(
+        &pipeline_name,
+        &[
+            ("name", quote! { self.name }),
+            ("pipeline", raw_handle("pipeline")),
+            ("layout", raw_handle("layout")),
+            ("caches", quote! { self.caches.len() }),
+        ],
+    );
+
+    let depth_test = pipeline.state.depth_test;
+    let depth_write = pipeline.state.depth_write;
+    let depth_clamp = pipeline.state.depth_clamp;
+
+    // Reverse-Z maps near to 1 and far to 0 for a better depth precision
+    let (compare_op, min_depth, max_depth) = if pipeline.state.reverse_z.unwrap_or(true) {
+        (quote! { vk::CompareOp::GREATER }, 1.0f32, 0.0f32)
+    } else {
+        (quote! { vk::CompareOp::LESS }, 0.0f32, 1.0f32)
+    };
+    let compare_op = match &pipeline.state.depth_compare {
+        Some(compare_op) => {
+            let compare_op = syn::Ident::new(compare_op, proc_macro2::Span::call_site());
+            quote! { vk::CompareOp::#compare_op }
+        }
+        None => compare_op,
+    };
+    // The stencil test stays disabled unless the pipeline sets its operations
+    let stencil = match &pipeline.state.stencil {
+        Some(stencil) => {
+            let front = &stencil.front;
+            let back = &stencil.back;
+            quote! {
+                .stencil_test_enable(true)
+                .front(#front)
+                .back(#back)
+            }
+        }
+        None => quote! { .stencil_test_enable(false) },
+    };
+    // Depth bias is set when recording commands, so it can be tuned per draw
+    let depth_bias = pipeline.state.depth_bias;
+    let primitive_restart = pipeline.state.primitive_restart;
+    // Line width is set when recording commands as well, such as for debug lines
+    let (line_width_state, line_width_method) = if pipeline.state.dynamic_line_width {
+        (
+            quote! { vk::DynamicState::LINE_WIDTH, },
+            quote! {
+                /// Sets the width of the lines drawn next, which is a dynamic state of this pipeline.
+                /// A width other than 1.0 requires the `wide_lines` feature, within the `line_width_range` limit.
+                pub fn set_line_width(&self, command_buffer: vk::CommandBuffer, line_width: f32) {
+                    unsafe {
+                        self.device.cmd_set_line_width(command_buffer, line_width);
+                    }
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+    let (depth_bias_state, depth_bias_method) = if depth_bias {
+        (
+            quote! { vk::DynamicState::DEPTH_BIAS, },
+            quote! {
+                /// Sets the depth bias of the next draws, which is a dynamic state of this pipeline.
+                /// A non-zero `clamp` requires the `depth_bias_clamp` feature.
+                pub fn set_depth_bias(
+                    &self,
+                    command_buffer: vk::CommandBuffer,
+                    constant_factor: f32,
+                    clamp: f32,
+                    slope_factor: f32,
+                ) {
+                    unsafe {
+                        self.device.cmd_set_depth_bias(command_buffer, constant_factor, clamp, slope_factor);
+                    }
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+    let sample_shading = pipeline.state.sample_shading;
+    let alpha_to_coverage = pipeline.state.alpha_to_coverage;
+    let polygon_mode = syn::Ident::new(
+        pipeline.state.polygon_mode.as_deref().unwrap_or("FILL"),
+        proc_macro2::Span::call_site(),
+    );
+    let samples = syn::Ident::new(
+        &format!("TYPE_{}", pipeline.state.samples),
+        proc_macro2::Span::call_site(),
+    );
+
+    let cull_mode = pipeline.state.cull_mode.as_deref().unwrap_or("NONE");
+    let cull_mode = syn::Ident::new(cull_mode, proc_macro2::Span::call_site());
+    let cull_mode = quote! { vk::CullModeFlags::#cull_mode };
+    let (front_face, mirrored_front_face) = match pipeline.state.front_face.as_deref() {
+        Some("CLOCKWISE") => (
+            quote! { vk::FrontFace::CLOCKWISE },
+            quote! { vk::FrontFace::COUNTER_CLOCKWISE },
+        ),
+        _ => (
+            quote! { vk::FrontFace::COUNTER_CLOCKWISE },
+            quote! { vk::FrontFace::CLOCKWISE },
+        ),
+    };
+
+    // One blend attachment for each color attachment of the subpass,
+    // which defaults to the color outputs of the fragment shader
+    let blend_attachment = match pipeline.state.blend.as_deref() {
+        // Opaque geometry overwrites all the channels
+        Some("OPAQUE") => quote! {
+            vk::PipelineColorBlendAttachmentState::builder()
+                .blend_enable(false)
+                .color_write_mask(vk::ColorComponentFlags::all())
+                .build()
+        },
+        // Additive blending accumulates colors, such as for particles
+        Some("ADDITIVE") => quote! {
+            vk::PipelineColorBlendAttachmentState::builder()
+                .blend_enable(true)
+                .color_write_mask(
+                    vk::ColorComponentFlags::R
+                        | vk::ColorComponentFlags::G
+                        | vk::ColorComponentFlags::B,
+                )
+                .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+                .dst_color_blend_factor(vk::BlendFactor::ONE)
+                .color_blend_op(vk::BlendOp::ADD)
+                .src_alpha_blend_factor(vk::BlendFactor::ONE)
+                .dst_alpha_blend_factor(vk::BlendFactor::ONE)
+                .alpha_blend_op(vk::BlendOp::ADD)
+                .build()
+        },
+        _ => quote! {
+            vk::PipelineColorBlendAttachmentState::builder()
+                .blend_enable(true)
+                .color_write_mask(
+                    vk::ColorComponentFlags::R
+                        | vk::ColorComponentFlags::G
+                        | vk::ColorComponentFlags::B,
+                )
+                .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+                .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                .color_blend_op(vk::BlendOp::ADD)
+                .src_alpha_blend_factor(vk::BlendFactor::ONE)
+                .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
+                .alpha_blend_op(vk::BlendOp::ADD)
+                .build()
+        },
+    };
+    let color_attachments = pipeline.color_attachments();
+    let blend_attachments = vec![blend_attachment; color_attachments];
+    let color_attachment_count = color_attachments as u32;
+
+    let (topology, tessellation, tessellation_state) = match pipeline.state.patch_control_points {
+        Some(patch) => (
+            quote! { vk::PrimitiveTopology::PATCH_LIST },
+            quote! {
+                let tessellation = vk::PipelineTessellationStateCreateInfo::builder()
+                    .patch_control_points(#patch)
+                    .build();
+            },
+            quote! { .tessellation_state(&tessellation) },
+        ),
+        None => (
+            {
+                let topology = pipeline
+                    .state
+                    .topology
+                    .as_deref()
+                    .unwrap_or("TRIANGLE_LIST");
+                let topology = syn::Ident::new(topology, proc_macro2::Span::call_site());
+                quote! { vk::PrimitiveTopology::#topology }
+            },
+            TokenStream::new(),
+            TokenStream::new(),
+        ),
+    };
+
+    let required_features = required_features_methods(&pipeline.features(), &pipeline.extensions());
+
+    let new_layout_method = new_layout_method(&origin, &pipeline.push_constants);
+    let push_constant_methods = push_constant_methods(pipeline, &origin);
+    let vs_entry_msg = format!("Failed to create vertex entry point for {}", origin);
+    let fs_entry_msg = format!("Failed to create fragment entry point for {}", origin);
+    let stage_entries: Vec<_> = pipeline
+        .stages
+        .iter()
+        .map(|stage| {
+            syn::Ident::new(
+                &format!("{}_entry", stage.suffix()),
+                proc_macro2::Span::call_site(),
+            )
+        })
+        .collect();
+    let stage_names = pipeline
+        .stages
+        .iter()
+        .map(|stage| pipeline.entry_point(*stage));
+    let stage_entry_msgs = pipeline
+        .stages
+        .iter()
+        .map(|stage| format!("Failed to create {:?} entry point for {}", stage, origin));
+    let stage_flags = &pipeline.stages;
+    let pipeline_msg = format!("Failed to create Vulkan graphics pipeline for {}", origin);
+    let pop_msg = format!("Failed to pop Vulkan pipeline for {}", origin);
+    let stride_msg = format!(
+        "Vertex stride {{}} of {} exceeds maxVertexInputBindingStride {{}}",
+        origin
+    );
+    let samples_msg = format!(
+        "Sample count {{:?}} of {} not in framebufferColorSampleCounts {{:?}}",
+        origin
+    );
+    let color_attachments_msg = format!(
+        "{{}} color attachments of {} exceed maxColorAttachments {{}}",
+        origin
+    );
+    let binding_count = bindings.len();
+    let primitive_msg = format!("Failed to get model primitive for {}", origin);
+    let binding_count_msg = format!(
+        "{} reads {} vertex bindings, bind them with bind_vertex_buffers() and bind_instance_buffer()",
+        origin, binding_count
+    );
+    let stride = stride as u32;
+    let views = pipeline.state.views;
+    let subpass = pipeline.state.subpass;
+    let view_mask_msg = format!(
+        "View mask {{:#b}} does not match the {} views expected by {}",
+        views, origin
+    );
+    let rendering_pass_msg = format!("Dynamic rendering of {} needs a null render pass", origin);
+    let rendering_formats_msg = format!(
+        "{{}} color formats for the {} color attachments of {}",
+        color_attachments, origin
+    );
+
+    let spec = spec_constants(pipeline);
+    let builder = pipeline_builder(pipeline, &pipeline_msg);
+
+    Ok(quote! {
+        #pipeline_cache
+
+        #set_structs
+
+        #spec
+
+        #builder
+
+        #( #[doc = #docs] )*
+        pub struct #pipeline_name {
+            caches: Vec<#pipeline_cache_name>,
+            pipeline: vk::Pipeline,
+            layout: vk::PipelineLayout,
+            set_layouts: Vec<vk::DescriptorSetLayout>,
+            front_face: vk::FrontFace,
+            render_pass: vk::RenderPass,
+            rendering_formats: Option<RenderingFormats>,
+            pipeline_cache: vk::PipelineCache,
+            specialization: Option<(Vec<vk::SpecializationMapEntry>, Vec<u8>)>,
+            variants: HashMap<(vk::RenderPass, vk::SampleCountFlags), vk::Pipeline>,
+            frames_in_flight: u32,
+            sets_per_frame: u32,
+            device: Rc<Device>,
+            name: String,
+        }
+
+        impl #pipeline_name {
+            /// Binding index of the vertex buffer read by this pipeline
+            pub const VERTEX_BINDING: u32 = 0;
+
+            /// Size in bytes of the vertices expected by this pipeline
+            pub const VERTEX_STRIDE: u32 = #stride;
+
+            /// Type of the indices of the primitives of a vkr_core `Model`, bound by `bind_model_buffers()`
+            pub const INDEX_TYPE: vk::IndexType = vk::IndexType::UINT16;
+
+            /// Binding index of the instance buffer read by this pipeline, if it has per-instance inputs
+            pub const INSTANCE_BINDING: u32 = 1;
+
+            /// Size in bytes of the per-instance data expected by this pipeline, 0 without per-instance inputs
+            pub const INSTANCE_STRIDE: u32 = #instance_stride;
+
+            /// Returns the stride and the attributes of the vertices expected by this pipeline,
+            /// to pack vertex buffers matching its vertex shader
+            pub fn vertex_layout() -> VertexLayout {
+                const ATTRIBUTES: &[VertexAttributeInfo] = &[ #( #attribute_infos, )* ];
+                VertexLayout {
+                    stride: Self::VERTEX_STRIDE,
+                    instance_stride: Self::INSTANCE_STRIDE,
+                    attributes: ATTRIBUTES,
+                }
+            }
+
+            /// Returns the stride in bytes of a vertex binding, or 0 for a binding not used by this pipeline
+            pub fn binding_stride(binding: u32) -> u32 {
+                match binding {
+                    #( #binding_indices => #binding_strides, )*
+                    _ => 0,
+                }
+            }
+
+            /// Number of color attachments expected in the subpass, one per blend attachment
+            pub const COLOR_ATTACHMENT_COUNT: u32 = #color_attachment_count;
+
+            /// Samples per pixel of the attachments of the render pass
+            pub const SAMPLE_COUNT: vk::SampleCountFlags = vk::SampleCountFlags::#samples;
+
+            /// Winding of the front faces used by `new()`
+            pub const FRONT_FACE: vk::FrontFace = #front_face;
+
+            /// Index of the subpass of the render pass this pipeline is created for
+            pub const SUBPASS: u32 = #subpass;
+
+            /// Checks this pipeline against the limits of a physical device in debug builds,
+            /// catching portability issues before the driver does
+            pub fn check_limits(limits: &vk::PhysicalDeviceLimits) {
+                debug_assert!(
+                    Self::VERTEX_STRIDE <= limits.max_vertex_input_binding_stride,
+                    #stride_msg,
+                    Self::VERTEX_STRIDE,
+                    limits.max_vertex_input_binding_stride
+                );
+                debug_assert!(
+                    limits.framebuffer_color_sample_counts.contains(Self::SAMPLE_COUNT),
+                    #samples_msg,
+                    Self::SAMPLE_COUNT,
+                    limits.framebuffer_color_sample_counts
+                );
+                debug_assert!(
+                    Self::COLOR_ATTACHMENT_COUNT <= limits.max_color_attachments,
+                    #color_attachments_msg,
+                    Self::COLOR_ATTACHMENT_COUNT,
+                    limits.max_color_attachments
+                );
+            }
+
+            /// Number of views this pipeline renders at once, to match the view mask of the render pass
+            pub const VIEW_COUNT: u32 = #views;
+
+            /// View mask of this pipeline with dynamic rendering, with a bit for each view, or 0 without multiview
+            pub const VIEW_MASK: u32 = if Self::VIEW_COUNT > 1 { u32::MAX >> (32 - Self::VIEW_COUNT) } else { 0 };
+
+            /// Checks in debug builds that the view mask of the render pass has a view for each one expected by this pipeline
+            pub fn check_view_mask(view_mask: u32) {
+                debug_assert!(
+                    view_mask.count_ones() == Self::VIEW_COUNT || (view_mask == 0 && Self::VIEW_COUNT == 1),
+                    #view_mask_msg,
+                    view_mask
+                );
+            }
+
+            #set_layouts_methods
+
+            #new_layout_method
+
+            pub fn new_impl(layout: vk::PipelineLayout, shader_module: &ShaderModule, vs: &str, fs: &str, render_pass: vk::RenderPass, front_face: vk::FrontFace, pipeline_cache: vk::PipelineCache, specialization: Option<&vk::SpecializationInfo>) -> vk::Pipeline {
+                Self::try_new_impl(layout, shader_module, vs, fs, render_pass, front_face, pipeline_cache, specialization).expect(#pipeline_msg)
+            }
+
+            pub fn try_new_impl(layout: vk::PipelineLayout, shader_module: &ShaderModule, vs: &str, fs: &str, render_pass: vk::RenderPass, front_face: vk::FrontFace, pipeline_cache: vk::PipelineCache, specialization: Option<&vk::SpecializationInfo>) -> Result<vk::Pipeline, vk::Result> {
+                Self::try_new_variant_impl(layout, shader_module, vs, fs, render_pass, Self::SAMPLE_COUNT, None, front_face, pipeline_cache, specialization)
+            }
+
+            /// Creates this pipeline for a render pass whose attachments have `samples` samples,
+            /// instead of the sample count of the shader attributes, or for dynamic rendering
+            /// with the given attachment formats and a null render pass
+            #[allow(clippy::too_many_arguments)]
+            pub fn try_new_variant_impl(layout: vk::PipelineLayout, shader_module: &ShaderModule, vs: &str, fs: &str, render_pass: vk::RenderPass, samples: vk::SampleCountFlags, rendering_formats: Option<&RenderingFormats>, front_face: vk::FrontFace, pipeline_cache: vk::PipelineCache, specialization: Option<&vk::SpecializationInfo>) -> Result<vk::Pipeline, vk::Result> {
+                let vs_entry = CString::new(vs).expect(#vs_entry_msg);
+                let fs_entry = CString::new(fs).expect(#fs_entry_msg);
+
+                #( let #stage_entries = CString::new(#stage_names).expect(#stage_entry_msgs); )*
+
+                // vkr_core has getters for vertex and fragment stages only, so adapt the vertex one
+                let mut stages = [
+                    shader_module.get_vert(&vs_entry),
+                    #(
+                        {
+                            let mut stage = shader_module.get_vert(&#stage_entries);
+                            stage.stage = #stage_flags;
+                            stage
+                        },
+                    )*
+                    shader_module.get_frag(&fs_entry)
+                ];
+                if let Some(specialization) = specialization {
+                    for stage in &mut stages {
+                        stage.p_specialization_info = specialization;
+                    }
+                }
+
+                let vertex_bindings = [
+                    #vertex_bindings
+                ];
+                let vertex_attributes = [
+                    #vertex_attributes
+                ];
+                let vertex_input = vk::PipelineVertexInputStateCreateInfo::builder()
+                    .vertex_attribute_descriptions(&vertex_attributes)
+                    .vertex_binding_descriptions(&vertex_bindings)
+                    .build();
+
+                let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::builder()
+                    .topology(#topology)
+                    .primitive_restart_enable(#primitive_restart)
+                    .build();
+
+                #tessellation
+
+                let depth_stencil = vk::PipelineDepthStencilStateCreateInfo::builder()
+                    .depth_test_enable(#depth_test)
+                    .depth_write_enable(#depth_write)
+                    .depth_compare_op(#compare_op)
+                    .depth_bounds_test_enable(false)
+                    #stencil
+                    .build();
+
+                let rasterization = vk::PipelineRasterizationStateCreateInfo::builder()
+                    .line_width(1.0)
+                    .depth_clamp_enable(#depth_clamp)
+                    .rasterizer_discard_enable(false)
+                    .polygon_mode(vk::PolygonMode::#polygon_mode)
+                    .cull_mode(#cull_mode)
+                    .front_face(front_face)
+                    .depth_bias_enable(#depth_bias)
+                    .build();
+
+                // Viewport and scissor are dynamic states, set with `viewport()` and `scissor()`
+                // when recording commands, so only their count is needed here
+                let view = vk::PipelineViewportStateCreateInfo::builder()
+                    .viewport_count(1)
+                    .scissor_count(1)
+                    .build();
+
+                let multisample = vk::PipelineMultisampleStateCreateInfo::builder()
+                    .rasterization_samples(samples)
+                    .sample_shading_enable(#sample_shading)
+                    .min_sample_shading(1.0)
+                    .alpha_to_coverage_enable(#alpha_to_coverage)
+                    .alpha_to_one_enable(false)
+                    .build();
+
+                let blend_attachments: [vk::PipelineColorBlendAttachmentState; #color_attachments] = [
+                    #( #blend_attachments ),*
+                ];
+
+                let blend = vk::PipelineColorBlendStateCreateInfo::builder()
+                    .logic_op_enable(false)
+                    .attachments(&blend_attachments)
+                    .build();
+
+                let states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR, #depth_bias_state #line_width_state];
+                let dynamics = vk::PipelineDynamicStateCreateInfo::builder()
+                    .dynamic_states(&states)
+                    .build();
+
+                // Views of dynamic rendering are set by the view mask of the pipeline instead of the render pass
+                let rendering = rendering_formats.map(|formats| {
+                    debug_assert!(render_pass == vk::RenderPass::null(), #rendering_pass_msg);
+                    debug_assert!(formats.color.len() == #color_attachments, #rendering_formats_msg, formats.color.len());
+                    PipelineRenderingCreateInfo::new(formats, Self::VIEW_MASK)
+                });
+
+                let mut create_info = vk::GraphicsPipelineCreateInfo::builder()
+                    .stages(&stages)
+                    .layout(layout)
+                    .render_pass(render_pass)
+                    .subpass(Self::SUBPASS)
+                    .vertex_input_state(&vertex_input)
+                    .input_assembly_state(&input_assembly)
+                    #tessellation_state
+                    .depth_stencil_state(&depth_stencil)
+                    .rasterization_state(&rasterization)
+                    .viewport_state(&view)
+                    .multisample_state(&multisample)
+                    .color_blend_state(&blend)
+                    .dynamic_state(&dynamics)
+                    .build();
+                if let Some(rendering) = &rendering {
+                    create_info.p_next = rendering as *const PipelineRenderingCreateInfo as *const std::ffi::c_void;
+                }
+
+                let pipelines = unsafe { shader_module.device.create_graphics_pipelines(pipeline_cache, &[create_info], None) };
+                let mut pipelines = pipelines.map_err(|(_, err)| err)?;
+                let pipeline = pipelines.pop().expect(#pop_msg);
+
+                Ok(pipeline)
+            }
+
+            pub fn new(shader_module: &ShaderModule, render_pass: vk::RenderPass) -> Self {
+                Self::builder(shader_module, render_pass).build()
+            }
+
+            /// Returns a variant of this pipeline with the opposite winding of front faces,
+            /// for rendering mirrored geometry such as reflections
+            pub fn new_mirrored(shader_module: &ShaderModule, render_pass: vk::RenderPass) -> Self {
+                Self::builder(shader_module, render_pass).front_face(#mirrored_front_face).build()
+            }
+
+            pub fn with_front_face(shader_module: &ShaderModule, render_pass: vk::RenderPass, front_face: vk::FrontFace) -> Self {
+                Self::builder(shader_module, render_pass).front_face(front_face).build()
+            }
+
+            /// Returns this pipeline, or the error of the driver when it fails to create it
+            pub fn try_new(shader_module: &ShaderModule, render_pass: vk::RenderPass) -> Result<Self, vk::Result> {
+                Self::builder(shader_module, render_pass).try_build()
+            }
+
+            pub fn try_with_front_face(shader_module: &ShaderModule, render_pass: vk::RenderPass, front_face: vk::FrontFace) -> Result<Self, vk::Result> {
+                Self::builder(shader_module, render_pass).front_face(front_face).try_build()
+            }
+
+            /// Returns this pipeline, created through a `vk::PipelineCache` to reuse previous compilations
+            pub fn with_cache(shader_module: &ShaderModule, render_pass: vk::RenderPass, pipeline_cache: vk::PipelineCache) -> Self {
+                Self::builder(shader_module, render_pass).pipeline_cache(pipeline_cache).build()
+            }
+
+            pub fn try_with_cache(shader_module: &ShaderModule, render_pass: vk::RenderPass, pipeline_cache: vk::PipelineCache) -> Result<Self, vk::Result> {
+                Self::builder(shader_module, render_pass).pipeline_cache(pipeline_cache).try_build()
+            }
+
+            fn try_create(shader_module: &ShaderModule, render_pass: vk::RenderPass, rendering_formats: Option<RenderingFormats>, front_face: vk::FrontFace, pipeline_cache: vk::PipelineCache, specialization: Option<&vk::SpecializationInfo>) -> Result<Self, vk::Result> {
+                let name = String::from(#pipeline_str);
+                let device = shader_module.device.clone();
+                let set_layouts = Self::try_new_set_layouts(&shader_module.device)?;
+                let layout = match Self::try_new_layout(&shader_module.device, &set_layouts) {
+                    Ok(layout) => layout,
+                    Err(err) => {
+                        unsafe {
+                            for set_layout in &set_layouts {
+                                device.destroy_descriptor_set_layout(*set_layout, None);
+                            }
+                        }
+                        return Err(err);
+                    }
+                };
+                let pipeline = match Self::try_new_variant_impl(layout, shader_module, #vs, #fs, render_pass, Self::SAMPLE_COUNT, rendering_formats.as_ref(), front_face, pipeline_cache, specialization) {
+                    Ok(pipeline) => pipeline,
+                    Err(err) => {
+                        unsafe {
+                            device.destroy_pipeline_layout(layout, None);
+                            for set_layout in &set_layouts {
+                                device.destroy_descriptor_set_layout(*set_layout, None);
+                            }
+                        }
+                        return Err(err);
+                    }
+                };
+
+                // Keep a copy of the specialization constants to create variants for other render passes
+                let specialization = specialization.map(|info| unsafe {
+                    (
+                        std::slice::from_raw_parts(info.p_map_entries, info.map_entry_count as usize).to_vec(),
+                        std::slice::from_raw_parts(info.p_data as *const u8, info.data_size).to_vec(),
+                    )
+                });
+
+                Ok(Self {
+                    caches: vec![],
+                    pipeline,
+                    layout,
+                    set_layouts,
+                    front_face,
+                    render_pass,
+                    rendering_formats,
+                    pipeline_cache,
+                    specialization,
+                    variants: HashMap::new(),
+                    frames_in_flight: 1,
+                    sets_per_frame: #pipeline_cache_name::DEFAULT_SETS_PER_FRAME,
+                    device,
+                    name
+                })
+            }
+
+            /// Returns a viewport with the depth range expected by this pipeline.
+            /// The viewport is a dynamic state, so set it with `cmd_set_viewport`.
+            pub fn viewport(width: f32, height: f32) -> vk::Viewport {
+                vk::Viewport::builder()
+                    .x(0.0)
+                    .y(0.0)
+                    .width(width)
+                    .height(height)
+                    .min_depth(#min_depth)
+                    .max_depth(#max_depth)
+                    .build()
+            }
+
+            /// Returns a scissor covering a framebuffer of the given size.
+            /// The scissor is a dynamic state, so set it with `cmd_set_scissor`.
+            pub fn scissor(width: u32, height: u32) -> vk::Rect2D {
+                vk::Rect2D::builder()
+                    .offset(vk::Offset2D::builder().x(0).y(0).build())
+                    .extent(vk::Extent2D::builder().width(width).height(height).build())
+                    .build()
+            }
+
+            pub fn get_front_face(&self) -> vk::FrontFace {
+                self.front_face
+            }
+
+            /// Returns this pipeline for another render pass with `samples` samples per attachment,
+            /// such as a multisampled pass resolving into a single sampled one. Each variant is
+            /// created the first time it is requested and kept until this pipeline is dropped.
+            pub fn variant(&mut self, shader_module: &ShaderModule, render_pass: vk::RenderPass, samples: vk::SampleCountFlags) -> vk::Pipeline {
+                self.try_variant(shader_module, render_pass, samples).expect(#pipeline_msg)
+            }
+
+            /// Returns this pipeline for another render pass, or the error of the driver when it fails to create it
+            pub fn try_variant(&mut self, shader_module: &ShaderModule, render_pass: vk::RenderPass, samples: vk::SampleCountFlags) -> Result<vk::Pipeline, vk::Result> {
+                if render_pass == self.render_pass && samples == Self::SAMPLE_COUNT {
+                    return Ok(self.pipeline);
+                }
+                if let Some(pipeline) = self.variants.get(&(render_pass, samples)) {
+                    return Ok(*pipeline);
+                }
+
+                let specialization = self.specialization.as_ref().map(|(map_entries, data)| {
+                    vk::SpecializationInfo::builder()
+                        .map_entries(map_entries)
+                        .data(data)
+                        .build()
+                });
+                let pipeline = Self::try_new_variant_impl(
+                    self.layout,
+                    shader_module,
+                    #vs,
+                    #fs,
+                    render_pass,
+                    samples,
+                    self.rendering_formats.as_ref(),
+                    self.front_face,
+                    self.pipeline_cache,
+                    specialization.as_ref(),
+                )?;
+                self.variants.insert((render_pass, samples), pipeline);
+                Ok(pipeline)
+            }
+
+            /// Binds the variant of this pipeline for a render pass, creating it if needed
+            pub fn bind_variant(&mut self, command_buffer: vk::CommandBuffer, shader_module: &ShaderModule, render_pass: vk::RenderPass, samples: vk::SampleCountFlags) {
+                let pipeline = self.variant(shader_module, render_pass, samples);
+                unsafe {
+                    self.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline);
+                }
+            }
+
+            #depth_bias_method
+
+            #line_width_method
+
+            #required_features
+
+            /// Sets the frames in flight the descriptor pools of new caches are sized for
+            pub fn set_frames_in_flight(&mut self, frames_in_flight: u32) {
+                self.frames_in_flight = frames_in_flight;
+            }
+
+            /// Sets the copies of each descriptor set the pools of new caches can hold per frame in flight
+            pub fn set_sets_per_frame(&mut self, sets_per_frame: u32) {
+                self.sets_per_frame = sets_per_frame;
+            }
+
+            #cache_methods
+
+            /// Binds this pipeline and the given descriptor sets at the graphics bind point,
+            /// starting from set 0, as needed by `bind_impl()` before drawing
+            pub fn bind_pipeline(&self, command_buffer: vk::CommandBuffer, sets: &[vk::DescriptorSet]) {
+                self.bind_pipeline_with_offsets(command_buffer, sets, &[]);
+            }
+
+            /// Binds this pipeline and the given descriptor sets, with an offset for each of their dynamic uniform buffers
+            pub fn bind_pipeline_with_offsets(
+                &self,
+                command_buffer: vk::CommandBuffer,
+                sets: &[vk::DescriptorSet],
+                dynamic_offsets: &[u32],
+            ) {
+                unsafe {
+                    self.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, self.pipeline);
+                    if !sets.is_empty() {
+                        self.device.cmd_bind_descriptor_sets(
+                            command_buffer,
+                            vk::PipelineBindPoint::GRAPHICS,
+                            self.layout,
+                            0,
+                            sets,
+                            dynamic_offsets,
+                        );
+                    }
+                }
+            }
+
+            /// Binds the vertex and index buffers of a mesh and records an indexed draw of `index_count` indices,
+            /// as needed by `draw_impl()`
+            pub fn draw_indexed(
+                &self,
+                command_buffer: vk::CommandBuffer,
+                vertex_buffer: &vkr_core::Buffer,
+                index_buffer: &vkr_core::Buffer,
+                index_type: vk::IndexType,
+                index_count: u32,
+            ) {
+                self.bind_buffers(command_buffer, vertex_buffer, index_buffer, index_type);
+                self.draw_instanced(command_buffer, index_count, 1, 0, 0, 0);
+            }
+
+            /// Binds a vertex buffer at the binding of this pipeline and an index buffer, without drawing,
+            /// so multiple draws can be recorded reusing the same buffers.
+            /// It is only meant for pipelines with a single vertex binding
+            pub fn bind_buffers(
+                &self,
+                command_buffer: vk::CommandBuffer,
+                vertex_buffer: &vkr_core::Buffer,
+                index_buffer: &vkr_core::Buffer,
+                index_type: vk::IndexType,
+            ) {
+                debug_assert!(#binding_count == 1, #binding_count_msg);
+                unsafe {
+                    self.device.cmd_bind_vertex_buffers(
+                        command_buffer,
+                        Self::VERTEX_BINDING,
+                        &[vertex_buffer.buffer],
+                        &[0],
+                    );
+                    self.device.cmd_bind_index_buffer(command_buffer, index_buffer.buffer, 0, index_type);
+                }
+            }
+
+            /// Binds the vertex and index buffers of a primitive of `model` on the command buffer of `frame`,
+            /// like `bind_buffers()` with `INDEX_TYPE`. It takes the primitive as well as the model,
+            /// since each primitive of a `Model` owns its own buffers
+            pub fn bind_model_buffers(&self, frame: &Frame, model: &Model, primitive: Handle<vkr_core::Primitive>) {
+                let primitive = model.primitives.get(primitive).expect(#primitive_msg);
+                let command_buffer = frame.cache.command_buffer.command_buffer;
+                match &primitive.indices {
+                    Some(indices) => self.bind_buffers(command_buffer, &primitive.vertices, indices, Self::INDEX_TYPE),
+                    None => self.bind_vertex_buffers(command_buffer, Self::VERTEX_BINDING, &[&primitive.vertices]),
+                }
+            }
+
+            /// Binds vertex buffers to consecutive bindings starting from `first_binding`,
+            /// for pipelines reading attributes from multiple buffers
+            pub fn bind_vertex_buffers(&self, command_buffer: vk::CommandBuffer, first_binding: u32, buffers: &[&vkr_core::Buffer]) {
+                let vk_buffers: Vec<vk::Buffer> = buffers.iter().map(|buffer| buffer.buffer).collect();
+                let offsets = vec![0; buffers.len()];
+                unsafe {
+                    self.device.cmd_bind_vertex_buffers(command_buffer, first_binding, &vk_buffers, &offsets);
+                }
+            }
+
+            /// Binds a buffer of per-instance data at the instance binding of this pipeline
+            pub fn bind_instance_buffer(&self, command_buffer: vk::CommandBuffer, instance_buffer: &vkr_core::Buffer) {
+                unsafe {
+                    self.device.cmd_bind_vertex_buffers(
+                        command_buffer,
+                        Self::INSTANCE_BINDING,
+                        &[instance_buffer.buffer],
+                        &[0],
+                    );
+                }
+            }
+
+            /// Records an indexed draw exposing all the `cmd_draw_indexed` parameters,
+            /// useful for instanced rendering and batches sharing the same buffers
+            pub fn draw_instanced(
+                &self,
+                command_buffer: vk::CommandBuffer,
+                index_count: u32,
+                instance_count: u32,
+                first_index: u32,
+                vertex_offset: i32,
+                first_instance: u32,
+            ) {
+                unsafe {
+                    self.device.cmd_draw_indexed(
+                        command_buffer,
+                        index_count,
+                        instance_count,
+                        first_index,
+                        vertex_offset,
+                        first_instance,
+                    );
+                }
+            }
+
+            #push_constant_methods
+
+            #write_set_methods
+        }
+
+        impl Pipeline for #pipeline_name {
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+                self
+            }
+
+            fn get_name(&self) -> &String {
+                &self.name
+            }
+
+            fn get_set_layouts(&self) -> &[vk::DescriptorSetLayout] {
+                &self.set_layouts
+            }
+
+            fn get_layout(&self) -> vk::PipelineLayout {
+                self.layout
+            }
+
+            fn get_pipeline(&self) -> vk::Pipeline {
+                self.pipeline
+            }
+
+            fn bind(&self, frame: &mut Frame, model: &Model, node: Handle<Node>) {
+                self.bind_impl(frame, model, node)
+            }
+
+            fn draw(&self, frame: &mut Frame, model: &Model, node: Handle<Node>) {
+                self.draw_impl(frame, model, node)
+            }
+        }
+
+        #debug
+
+        impl Drop for #pipeline_name {
+            fn drop(&mut self) {
+                unsafe {
+                    for variant in self.variants.values() {
+                        self.device.destroy_pipeline(*variant, None);
+                    }
+                    self.device.destroy_pipeline(self.pipeline, None);
+                    self.device.destroy_pipeline_layout(self.layout, None);
+                    for set_layout in &self.set_layouts {
+                        self.device.destroy_descriptor_set_layout(*set_layout, None);
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Generates a compute pipeline, which needs no render pass and is dispatched instead of drawn
+pub fn compute_pipeline(crate_module: &CrateModule, pipeline: &Pipeline) -> TokenStream {
+    let pipeline_name = item_ident(pipeline.type_name(""));
+
+    let pipeline_str = pipeline.name.to_camelcase();
+
+    let cs = pipeline.entry_point(ShaderType::Compute);
+
+    let mut docs = vec![
+        format!(
+            " Compute pipeline generated from the `{}` shader of `{}`.",
+            pipeline.name, crate_module.name
+        ),
+        String::new(),
+        " # Descriptor bindings".to_string(),
+    ];
+    for uniform in &pipeline.uniforms {
+        docs.push(format!(
+            " - set {}, binding {}: `{}` of type `{}`",
+            uniform.descriptor_set,
+            uniform.binding,
+            uniform.name,
+            uniform.descriptor_type.name(),
+        ));
+    }
+
+    let pipeline_cache_name = item_ident(pipeline.cache_name());
+
+    let origin = get_origin(crate_module, pipeline);
+    let pipeline_cache = descriptor_pool(&pipeline_cache_name, &origin, &pipeline.uniforms);
+    let cache_methods = cache_methods(
+        &pipeline_cache_name,
+        quote! { #pipeline_cache_name::with_capacity(&self.device, self.sets_per_frame, self.frames_in_flight) },
+    );
+
+    let set_layouts_methods = set_layouts_methods(&pipeline.uniforms, &origin);
+    let new_layout_method = new_layout_method(&origin, &pipeline.push_constants);
+    let push_constant_methods = push_constant_methods(pipeline, &origin);
+    let write_set_methods = write_set_methods(&pipeline.uniforms);
+    let set_structs = set_structs(pipeline);
+    let debug = debug_impl(
+        &pipeline_name,
+        &[
+            ("name", quote! { self.name }),
+            ("pipeline", raw_handle("pipeline")),
+            ("layout", raw_handle("layout")),
+            ("caches", quote! { self.caches.len() }),
+        ],
+    );
+
+    let cs_entry_msg = format!("Failed to create compute entry point for {}", origin);
+    let pipeline_msg = format!("Failed to create Vulkan compute pipeline for {}", origin);
+    let pop_msg = format!("Failed to pop Vulkan pipeline for {}", origin);
+    let workgroup_msg = format!(
+        "Workgroup size {{:?}} of {} exceeds maxComputeWorkGroupSize {{:?}} or maxComputeWorkGroupInvocations {{}}",
+        origin
+    );
+
+    let [x, y, z] = pipeline.workgroup_size.expect("Not a compute pipeline");
+
+    let (spec, default_specialization) = if pipeline.spec_constants.is_empty() {
+        (
+            TokenStream::new(),
+            quote! { Self::try_create(shader_module, None) },
+        )
+    } else {
+        let spec_name = item_ident(pipeline.type_name("Spec"));
+        let spec_struct = spec_struct(pipeline, &spec_name);
+        (
+            quote! {
+                #spec_struct
+
+                impl #pipeline_name {
+                    /// Returns this pipeline with the given specialization constants
+                    pub fn new_with_spec(shader_module: &ShaderModule, spec: &#spec_name) -> Self {
+                        let map_entries = #spec_name::map_entries();
+                        let specialization = vk::SpecializationInfo::builder()
+                            .map_entries(&map_entries)
+                            .data(spec.as_bytes())
+                            .build();
+                        Self::try_create(shader_module, Some(&specialization)).expect(#pipeline_msg)
+                    }
+                }
+            },
+            quote! {
+                let spec = #spec_name::default();
+                let map_entries = #spec_name::map_entries();
+                let specialization = vk::SpecializationInfo::builder()
+                    .map_entries(&map_entries)
+                    .data(spec.as_bytes())
+                    .build();
+                Self::try_create(shader_module, Some(&specialization))
+            },
+        )
+    };
+
+    quote! {
+        #pipeline_cache
+
+        #set_structs
+
+        #spec
+
+        #( #[doc = #docs] )*
+        pub struct #pipeline_name {
+            caches: Vec<#pipeline_cache_name>,
+            pipeline: vk::Pipeline,
+            layout: vk::PipelineLayout,
+            set_layouts: Vec<vk::DescriptorSetLayout>,
+            frames_in_flight: u32,
+            sets_per_frame: u32,
+            device: Rc<Device>,
+            name: String,
+        }
+
+        impl #pipeline_name {
+            /// Threads per workgroup declared by the compute shader
+            pub const WORKGROUP_SIZE: [u32; 3] = [#x, #y, #z];
+
+            /// Checks this pipeline against the limits of a physical device in debug builds,
+            /// catching portability issues before the driver does
+            pub fn check_limits(limits: &vk::PhysicalDeviceLimits) {
+                let size = Self::WORKGROUP_SIZE;
+                let max_size = limits.max_compute_work_group_size;
+                debug_assert!(
+                    size.iter().zip(&max_size).all(|(size, max)| size <= max)
+                        && size.iter().product::<u32>() <= limits.max_compute_work_group_invocations,
+                    #workgroup_msg,
+                    size,
+                    max_size,
+                    limits.max_compute_work_group_invocations
+                );
+            }
+
+            #set_layouts_methods
+
+            #new_layout_method
+
+            pub fn new_impl(layout: vk::PipelineLayout, shader_module: &ShaderModule, cs: &str, specialization: Option<&vk::SpecializationInfo>) -> vk::Pipeline {
+                Self::try_new_impl(layout, shader_module, cs, specialization).expect(#pipeline_msg)
+            }
+
+            pub fn try_new_impl(layout: vk::PipelineLayout, shader_module: &ShaderModule, cs: &str, specialization: Option<&vk::SpecializationInfo>) -> Result<vk::Pipeline, vk::Result> {
+                let cs_entry = CString::new(cs).expect(#cs_entry_msg);
+
+                // vkr_core has no getter for compute stages, so adapt the vertex one
+                let mut stage = shader_module.get_vert(&cs_entry);
+                stage.stage = vk::ShaderStageFlags::COMPUTE;
+                if let Some(specialization) = specialization {
+                    stage.p_specialization_info = specialization;
+                }
+
+                let create_info = vk::ComputePipelineCreateInfo::builder()
+                    .stage(stage)
+                    .layout(layout)
+                    .build();
+
+                let pipelines = unsafe { shader_module.device.create_compute_pipelines(vk::PipelineCache::null(), &[create_info], None) };
+                let mut pipelines = pipelines.map_err(|(_, err)| err)?;
+                let pipeline = pipelines.pop().expect(#pop_msg);
+
+                Ok(pipeline)
+            }
+
+            pub fn new(shader_module: &ShaderModule) -> Self {
+                Self::try_new(shader_module).expect(#pipeline_msg)
+            }
+
+            /// Returns this pipeline, or the error of the driver when it fails to create it
+            pub fn try_new(shader_module: &ShaderModule) -> Result<Self, vk::Result> {
+                #default_specialization
+            }
+
+            fn try_create(shader_module: &ShaderModule, specialization: Option<&vk::SpecializationInfo>) -> Result<Self, vk::Result> {
+                let name = String::from(#pipeline_str);
+                let device = shader_module.device.clone();
+                let set_layouts = Self::try_new_set_layouts(&shader_module.device)?;
+                let layout = match Self::try_new_layout(&shader_module.device, &set_layouts) {
+                    Ok(layout) => layout,
+                    Err(err) => {
+                        unsafe {
+                            for set_layout in &set_layouts {
+                                device.destroy_descriptor_set_layout(*set_layout, None);
+                            }
+                        }
+                        return Err(err);
+                    }
+                };
+                let pipeline = match Self::try_new_impl(layout, shader_module, #cs, specialization) {
+                    Ok(pipeline) => pipeline,
+                    Err(err) => {
+                        unsafe {
+                            device.destroy_pipeline_layout(layout, None);
+                            for set_layout in &set_layouts {
+                                device.destroy_descriptor_set_layout(*set_layout, None);
+                            }
+                        }
+                        return Err(err);
+                    }
+                };
+
+                Ok(Self {
+                    caches: vec![],
+                    pipeline,
+                    layout,
+                    set_layouts,
+                    frames_in_flight: 1,
+                    sets_per_frame: #pipeline_cache_name::DEFAULT_SETS_PER_FRAME,
+                    device,
+                    name
+                })
+            }
+
+            pub fn get_name(&self) -> &String {
+                &self.name
+            }
+
+            pub fn get_set_layouts(&self) -> &[vk::DescriptorSetLayout] {
+                &self.set_layouts
+            }
+
+            pub fn get_layout(&self) -> vk::PipelineLayout {
+                self.layout
+            }
+
+            pub fn get_pipeline(&self) -> vk::Pipeline {
+                self.pipeline
+            }
+
+            /// Sets the frames in flight the descriptor pools of new caches are sized for
+            pub fn set_frames_in_flight(&mut self, frames_in_flight: u32) {
+                self.frames_in_flight = frames_in_flight;
+            }
+
+            /// Sets the copies of each descriptor set the pools of new caches can hold per frame in flight
+            pub fn set_sets_per_frame(&mut self, sets_per_frame: u32) {
+                self.sets_per_frame = sets_per_frame;
+            }
+
+            #cache_methods
+
+            /// Binds this pipeline and the given descriptor sets at the compute bind point
+            pub fn bind(&self, command_buffer: vk::CommandBuffer, sets: &[vk::DescriptorSet]) {
+                self.bind_with_offsets(command_buffer, sets, &[]);
+            }
+
+            /// Binds this pipeline and the given descriptor sets, with an offset for each of their dynamic uniform buffers
+            pub fn bind_with_offsets(
+                &self,
+                command_buffer: vk::CommandBuffer,
+                sets: &[vk::DescriptorSet],
+                dynamic_offsets: &[u32],
+            ) {
+                unsafe {
+                    self.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::COMPUTE, self.pipeline);
+                    if !sets.is_empty() {
+                        self.device.cmd_bind_descriptor_sets(
+                            command_buffer,
+                            vk::PipelineBindPoint::COMPUTE,
+                            self.layout,
+                            0,
+                            sets,
+                            dynamic_offsets,
+                        );
+                    }
+                }
+            }
+
+            /// Records a dispatch of the given number of workgroups, each of `WORKGROUP_SIZE` threads
+            pub fn dispatch(&self, command_buffer: vk::CommandBuffer, x: u32, y: u32, z: u32) {
+                unsafe {
+                    self.device.cmd_dispatch(command_buffer, x, y, z);
+                }
+            }
+
+            #push_constant_methods
+
+            #write_set_methods
+        }
+
+        #debug
+
+        impl Drop for #pipeline_name {
+            fn drop(&mut self) {
+                unsafe {
+                    self.device.destroy_pipeline(self.pipeline, None);
+                    self.device.destroy_pipeline_layout(self.layout, None);
+                    for set_layout in &self.set_layouts {
+                        self.device.destroy_descriptor_set_layout(*set_layout, None);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Generates only the descriptor set layouts, pipeline layout, and write-set helpers of a pipeline,
+/// so the user can build the `vk::Pipeline` with custom state
+pub fn descriptors(crate_module: &CrateModule, pipeline: &Pipeline) -> TokenStream {
+    let pipeline_name = item_ident(pipeline.type_name(""));
+
+    let pipeline_str = pipeline.name.to_camelcase();
+
+    let pipeline_cache_name = item_ident(pipeline.cache_name());
+
+    let origin = get_origin(crate_module, pipeline);
+    let pipeline_cache = descriptor_pool(&pipeline_cache_name, &origin, &pipeline.uniforms);
+    let cache_methods = cache_methods(
+        &pipeline_cache_name,
+        quote! { #pipeline_cache_name::new(&self.device) },
+    );
+    let set_layouts_methods = set_layouts_methods(&pipeline.uniforms, &origin);
+    let new_layout_method = new_layout_method(&origin, &pipeline.push_constants);
+    let push_constant_methods = push_constant_methods(pipeline, &origin);
+    let write_set_methods = write_set_methods(&pipeline.uniforms);
+    let set_structs = set_structs(pipeline);
+    let debug = debug_impl(
+        &pipeline_name,
+        &[
+            ("name", quote! { self.name }),
+            ("layout", raw_handle("layout")),
+            ("caches", quote! { self.caches.len() }),
+        ],
+    );
+
+    let doc = format!(
+        " Descriptor set layouts and pipeline layout of the `{}` shaders of `{}`.",
+        pipeline.name, crate_module.name
+    );
+    let layouts_msg = format!("Failed to create Vulkan layouts for {}", origin);
+
+    quote! {
+        #pipeline_cache
+
+        #set_structs
+
+        #[doc = #doc]
+        pub struct #pipeline_name {
+            caches: Vec<#pipeline_cache_name>,
+            layout: vk::PipelineLayout,
+            set_layouts: Vec<vk::DescriptorSetLayout>,
+            device: Rc<Device>,
+            name: String,
+        }
+
+        impl #pipeline_name {
+            #set_layouts_methods
+
+            #new_layout_method
+
+            pub fn new(device: &Rc<Device>) -> Self {
+                Self::try_new(device).expect(#layouts_msg)
+            }
+
+            pub fn try_new(device: &Rc<Device>) -> Result<Self, vk::Result> {
+                let set_layouts = Self::try_new_set_layouts(device)?;
+                let layout = match Self::try_new_layout(device, &set_layouts) {
+                    Ok(layout) => layout,
+                    Err(err) => {
+                        unsafe {
+                            for set_layout in &set_layouts {
+                                device.destroy_descriptor_set_layout(*set_layout, None);
+                            }
+                        }
+                        return Err(err);
+                    }
+                };
+
+                Ok(Self {
+                    caches: vec![],
+                    layout,
+                    set_layouts,
+                    device: device.clone(),
+                    name: String::from(#pipeline_str),
+                })
+            }
+
+            pub fn get_name(&self) -> &String {
+                &self.name
+            }
+
+            pub fn get_set_layouts(&self) -> &[vk::DescriptorSetLayout] {
+                &self.set_layouts
+            }
+
+            pub fn get_layout(&self) -> vk::PipelineLayout {
+                self.layout
+            }
+
+            #cache_methods
+
+            #push_constant_methods
+
+            #write_set_methods
+        }
+
+        #debug
+
+        impl Drop for #pipeline_name {
+            fn drop(&mut self) {
+                unsafe {
+                    self.device.destroy_pipeline_layout(self.layout, None);
+                    for set_layout in &self.set_layouts {
+                        self.device.destroy_descriptor_set_layout(*set_layout, None);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Generates the enum of the pipelines of a crate and the cache creating them, whose names start
+/// with `prefix` when the macro has one, as in `GfxShaderCrate` and `GfxCache`
+pub fn cache(
+    crate_module: &CrateModule,
+    pipelines: &[Pipeline],
+    prefix: Option<&str>,
+) -> TokenStream {
+    let enum_name = item_ident(format!(
+        "{}Shader{}",
+        prefix.unwrap_or_default(),
+        crate_module.name.to_camelcase()
+    ));
+    let cache_name = item_ident(format!("{}Cache", prefix.unwrap_or("Pipeline")));
+    let cache_builder_name = item_ident(format!("{}CacheBuilder", prefix.unwrap_or("Pipeline")));
+
+    // SPIR-V built by rust-gpu is found through the environment variable named after the crate
+    let code = match &crate_module.spirv_path {
+        Some(path) => {
+            let path = path.display().to_string();
+            quote! { include_bytes!(#path) }
+        }
+        None => {
+            let shader_spv = format!("{}.spv", crate_module.name.replace('-', "_"));
+            quote! { include_bytes!(env!(#shader_spv)) }
+        }
+    };
+
+    // Compute pipelines are not drawn, so they are created from the shader module by the user
+    let pipelines: Vec<&Pipeline> = pipelines.iter().filter(|p| !p.is_compute()).collect();
+
+    let pipeline_names = pipelines.iter().map(|m| item_ident(m.name.to_camelcase()));
+
+    let pipeline_types: Vec<syn::Ident> = pipelines
+        .iter()
+        .map(|m| item_ident(m.type_name("")))
+        .collect();
+
+    let pipeline_new = pipelines.iter().map(|m| {
+        let variant = item_ident(m.name.to_camelcase());
+        let pipeline_name = item_ident(m.type_name(""));
+        quote! {
+            #enum_name::#variant => {
+                let mut builder = #pipeline_name::builder(shader_module, render_pass)
+                    .pipeline_cache(pipeline_cache)
+                    .frames_in_flight(frames_in_flight);
+                if let Some(sets_per_frame) = sets_per_frame {
+                    builder = builder.sets_per_frame(sets_per_frame);
+                }
+                Box::new(builder.build())
+            }
+        }
+    });
+
+    let pipeline_try_new = pipelines.iter().map(|m| {
+        let variant = item_ident(m.name.to_camelcase());
+        let pipeline_name = item_ident(m.type_name(""));
+        quote! {
+            #enum_name::#variant => {
+                let mut builder = #pipeline_name::builder(shader_module, render_pass)
+                    .pipeline_cache(pipeline_cache)
+                    .frames_in_flight(frames_in_flight);
+                if let Some(sets_per_frame) = sets_per_frame {
+                    builder = builder.sets_per_frame(sets_per_frame);
+                }
+                builder.try_build().map(|p| Box::new(p) as Box<dyn Pipeline>)
+            }
+        }
+    });
+
+    let pipeline_variant = pipelines.iter().map(|m| {
+        let variant = item_ident(m.name.to_camelcase());
+        let pipeline_name = item_ident(m.type_name(""));
+        quote! {
+            #enum_name::#variant => pipeline
+                .as_any_mut()
+                .downcast_mut::<#pipeline_name>()
+                .unwrap()
+                .variant(shader_module, render_pass, samples)
+        }
+    });
+
+    let shaders: Vec<TokenStream> = pipelines
+        .iter()
+        .map(|m| {
+            let variant = item_ident(m.name.to_camelcase());
+            quote! { #enum_name::#variant }
+        })
+        .collect();
+
+    let pipeline_count = pipelines.len();
+    let debug = debug_impl(
+        &cache_name,
+        &[
+            ("render_pass", raw_handle("render_pass")),
+            ("pipeline_cache", raw_handle("pipeline_cache")),
+            (
+                "pipelines",
+                quote! { self.pipelines.iter().flatten().count() },
+            ),
+            ("frames_in_flight", quote! { self.frames_in_flight }),
+            ("spv_path", quote! { self.spv_path }),
+        ],
+    );
+
+    let eager_msg = format!(
+        "Failed to create pipelines of {}: {{:?}}",
+        crate_module.name
+    );
+
+    let created_msg = format!(
+        "Pipeline {{:?}} of {} has already been created",
+        crate_module.name
+    );
+
+    let features: BTreeSet<&str> = pipelines.iter().flat_map(|p| p.features()).collect();
+    let extensions: BTreeSet<&str> = pipelines.iter().flat_map(|p| p.extensions()).collect();
+    let required_features = required_features_methods(
+        &features.into_iter().collect::<Vec<_>>(),
+        &extensions.into_iter().collect::<Vec<_>>(),
+    );
+
+    let pipeline_init = pipelines.iter().map(|_| quote! { None });
+
+    let pipeline_cache_msg = format!(
+        "Failed to create Vulkan pipeline cache for {}",
+        crate_module.name
+    );
+
+    let read_msg = format!(
+        "Failed to read SPIR-V of {} from {{}}: {{}}",
+        crate_module.name
+    );
+
+    quote! {
+        #[derive(Copy,Clone,Debug)]
+        pub enum #enum_name {
+            #( #pipeline_names, )*
+        }
+
+        impl #enum_name {
+            fn create_pipeline(&self, shader_module: &ShaderModule, render_pass: vk::RenderPass, pipeline_cache: vk::PipelineCache, frames_in_flight: u32, sets_per_frame: Option<u32>) -> Box<dyn Pipeline> {
+                match *self {
+                    #( #pipeline_new, )*
+                }
+            }
+
+            fn try_create_pipeline(&self, shader_module: &ShaderModule, render_pass: vk::RenderPass, pipeline_cache: vk::PipelineCache, frames_in_flight: u32, sets_per_frame: Option<u32>) -> Result<Box<dyn Pipeline>, vk::Result> {
+                match *self {
+                    #( #pipeline_try_new, )*
+                }
+            }
+
+            fn pipeline_variant(&self, pipeline: &mut dyn Pipeline, shader_module: &ShaderModule, render_pass: vk::RenderPass, samples: vk::SampleCountFlags) -> vk::Pipeline {
+                match *self {
+                    #( #pipeline_variant, )*
+                }
+            }
+        }
+
+        /// Options of a pipeline cache, falling back to the defaults of `new()`
+        pub struct #cache_builder_name<'d> {
+            dev: &'d Dev,
+            render_pass: Option<vk::RenderPass>,
+            render_passes: [Option<vk::RenderPass>; #pipeline_count],
+            frames_in_flight: u32,
+            sets_per_frame: Option<u32>,
+            spv_path: Option<std::path::PathBuf>,
+            pipeline_cache_path: Option<std::path::PathBuf>,
+            eager: bool,
+        }
+
+        impl<'d> #cache_builder_name<'d> {
+            /// Creates pipelines for an external render pass, instead of the default vkr_core pass
+            pub fn render_pass(mut self, render_pass: vk::RenderPass) -> Self {
+                self.render_pass = Some(render_pass);
+                self
+            }
+
+            /// Creates one pipeline for its own render pass, such as a shadow or post-processing pass,
+            /// while the others keep using the render pass of the cache
+            pub fn pipeline_render_pass(mut self, shader: #enum_name, render_pass: vk::RenderPass) -> Self {
+                self.render_passes[shader as usize] = Some(render_pass);
+                self
+            }
+
+            /// Sizes the descriptor pools of the pipelines for multiple frames in flight
+            pub fn frames_in_flight(mut self, frames_in_flight: u32) -> Self {
+                self.frames_in_flight = frames_in_flight;
+                self
+            }
+
+            /// Sizes the descriptor pools of the pipelines for this many copies of each descriptor set per frame in flight,
+            /// instead of the `DEFAULT_SETS_PER_FRAME` of each pipeline cache
+            pub fn sets_per_frame(mut self, sets_per_frame: u32) -> Self {
+                self.sets_per_frame = Some(sets_per_frame);
+                self
+            }
+
+            /// Reads the SPIR-V of the shader crate from a file at runtime,
+            /// instead of the one embedded at build time
+            pub fn spv_path<P: Into<std::path::PathBuf>>(mut self, path: P) -> Self {
+                self.spv_path = Some(path.into());
+                self
+            }
+
+            /// Initializes the `vk::PipelineCache` with the data previously saved to a file with `save_to()`,
+            /// skipping the compilations done by previous runs. A missing file is fine on the first run.
+            pub fn pipeline_cache_path<P: Into<std::path::PathBuf>>(mut self, path: P) -> Self {
+                self.pipeline_cache_path = Some(path.into());
+                self
+            }
+
+            /// Creates all the pipelines on build, instead of on first use
+            pub fn eager(mut self, eager: bool) -> Self {
+                self.eager = eager;
+                self
+            }
+
+            pub fn build(self) -> #cache_name {
+                let (pass, render_pass) = match self.render_pass {
+                    Some(render_pass) => (None, render_pass),
+                    None => {
+                        let pass = Pass::new(self.dev);
+                        let render_pass = pass.render;
+                        (Some(pass), render_pass)
+                    }
+                };
+
+                let pipelines = [
+                    #( #pipeline_init, )*
+                ];
+
+                // Data from another driver or device is ignored by the implementation
+                let initial_data = self
+                    .pipeline_cache_path
+                    .and_then(|path| std::fs::read(path).ok())
+                    .unwrap_or_default();
+                let create_info = vk::PipelineCacheCreateInfo::builder()
+                    .initial_data(&initial_data)
+                    .build();
+                let pipeline_cache = unsafe { self.dev.device.create_pipeline_cache(&create_info, None) }
+                    .expect(#pipeline_cache_msg);
+
+                let mut cache = #cache_name {
+                    pass,
+                    render_pass,
+                    render_passes: self.render_passes,
+                    frames_in_flight: self.frames_in_flight,
+                    sets_per_frame: self.sets_per_frame,
+                    pipelines,
+                    shader_module: None,
+                    spv_path: self.spv_path,
+                    pipeline_cache,
+                    device: self.dev.device.clone(),
+                };
+
+                if self.eager {
+                    if let Err(failures) = cache.validate() {
+                        panic!(#eager_msg, failures);
+                    }
+                }
+
+                cache
+            }
+        }
+
+        pub struct #cache_name {
+            pass: Option<Pass>,
+            render_pass: vk::RenderPass,
+            render_passes: [Option<vk::RenderPass>; #pipeline_count],
+            frames_in_flight: u32,
+            sets_per_frame: Option<u32>,
+            pipelines: [Option<Box<dyn Pipeline>>;#pipeline_count],
+            shader_module: Option<ShaderModule>,
+            spv_path: Option<std::path::PathBuf>,
+            pipeline_cache: vk::PipelineCache,
+            device: Rc<Device>,
+        }
+
+        impl #cache_name {
+            /// Returns an empty pipeline cache
+            pub fn new(dev: &Dev) -> Self {
+                Self::builder(dev).build()
+            }
+
+            /// Returns an empty pipeline cache creating its pipelines for an existing render pass
+            pub fn with_render_pass(dev: &Dev, render_pass: vk::RenderPass) -> Self {
+                Self::builder(dev).render_pass(render_pass).build()
+            }
+
+            /// Returns an empty pipeline cache reading the SPIR-V of the shader crate from `path`
+            pub fn from_spv_path<P: Into<std::path::PathBuf>>(dev: &Dev, path: P) -> Self {
+                Self::builder(dev).spv_path(path).build()
+            }
+
+            /// Returns a builder to configure a pipeline cache
+            pub fn builder(dev: &Dev) -> #cache_builder_name {
+                #cache_builder_name {
+                    dev,
+                    render_pass: None,
+                    render_passes: [None; #pipeline_count],
+                    frames_in_flight: 1,
+                    sets_per_frame: None,
+                    spv_path: None,
+                    pipeline_cache_path: None,
+                    eager: false,
+                }
+            }
+
+            /// Returns the vkr_core pass created by this cache, if not using an external render pass
+            pub fn get_pass(&self) -> Option<&Pass> {
+                self.pass.as_ref()
+            }
+
+            pub fn get_render_pass(&self) -> vk::RenderPass {
+                self.render_pass
+            }
+
+            /// Returns the render pass a pipeline is created for, which is the one of the cache
+            /// unless overridden with `pipeline_render_pass()`
+            pub fn get_pipeline_render_pass(&self, shader: #enum_name) -> vk::RenderPass {
+                self.render_passes[shader as usize].unwrap_or(self.render_pass)
+            }
+
+            #required_features
+
+            /// Checks all the pipelines against the limits of a physical device in debug builds
+            pub fn check_limits(limits: &vk::PhysicalDeviceLimits) {
+                #( #pipeline_types::check_limits(limits); )*
+            }
+
+            /// Returns the shader module of the crate, loading it on first use
+            /// from the SPIR-V path of the cache, if any, or from the embedded SPIR-V.
+            /// Compute pipelines are created from it with `Pipeline{Name}::new()`.
+            pub fn get_shader_module(&mut self) -> &ShaderModule {
+                if self.shader_module.is_none() {
+                    const CODE: &[u8] = #code;
+                    let shader_module = match &self.spv_path {
+                        Some(path) => {
+                            let code = std::fs::read(path)
+                                .unwrap_or_else(|err| panic!(#read_msg, path.display(), err));
+                            ShaderModule::new(&self.device, &code)
+                        }
+                        None => ShaderModule::new(&self.device, CODE),
+                    };
+                    self.shader_module = Some(shader_module);
+                }
+
+                self.shader_module.as_ref().unwrap()
+            }
+
+            /// Writes the data of the `vk::PipelineCache` to a file,
+            /// to initialize the cache of the next run with `pipeline_cache_path()`
+            pub fn save_to<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+                let data = unsafe { self.device.get_pipeline_cache_data(self.pipeline_cache) }
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+                std::fs::write(path, data)
+            }
+
+            /// Drops the shader module and all the pipelines created from it, reading the SPIR-V again
+            /// from the path of the cache. Pipelines are then recreated on their next use.
+            /// Wait for the device to finish using the pipelines before reloading.
+            pub fn reload(&mut self) {
+                for pipeline in &mut self.pipelines {
+                    *pipeline = None;
+                }
+                self.shader_module = None;
+                self.get_shader_module();
+            }
+
+            fn create_pipeline(&mut self, shader: #enum_name) {
+                assert!(self.pipelines[shader as usize].is_none(), #created_msg, shader);
+
+                let render_pass = self.get_pipeline_render_pass(shader);
+                let frames_in_flight = self.frames_in_flight;
+                let sets_per_frame = self.sets_per_frame;
+                let pipeline_cache = self.pipeline_cache;
+                let shader_module = self.get_shader_module();
+                let pipeline = shader.create_pipeline(shader_module, render_pass, pipeline_cache, frames_in_flight, sets_per_frame);
+                self.pipelines[shader as usize] = Some(pipeline);
+            }
+
+            /// Eagerly creates all the pipelines not created yet, returning the ones which failed
+            /// with their errors. Useful to check the whole shader crate is healthy.
+            pub fn validate(&mut self) -> Result<(), Vec<(#enum_name, vk::Result)>> {
+                let mut failures = vec![];
+
+                let shaders: [#enum_name; #pipeline_count] = [ #( #shaders, )* ];
+                for shader in shaders {
+                    if self.pipelines[shader as usize].is_some() {
+                        continue;
+                    }
+
+                    let render_pass = self.get_pipeline_render_pass(shader);
+                    let frames_in_flight = self.frames_in_flight;
+                    let sets_per_frame = self.sets_per_frame;
+                    let pipeline_cache = self.pipeline_cache;
+                    let shader_module = self.get_shader_module();
+                    match shader.try_create_pipeline(shader_module, render_pass, pipeline_cache, frames_in_flight, sets_per_frame) {
+                        Ok(pipeline) => self.pipelines[shader as usize] = Some(pipeline),
+                        Err(err) => failures.push((shader, err)),
+                    }
+                }
+
+                if failures.is_empty() {
+                    Ok(())
+                } else {
+                    Err(failures)
+                }
+            }
+
+            /// Returns the pipelines created so far, in declaration order,
+            /// to tell which ones have been compiled without creating the others
+            pub fn instantiated(&self) -> Vec<#enum_name> {
+                let shaders: [#enum_name; #pipeline_count] = [ #( #shaders, )* ];
+                shaders
+                    .iter()
+                    .copied()
+                    .filter(|shader| self.pipelines[*shader as usize].is_some())
+                    .collect()
+            }
+
+            pub fn get(&mut self, shader: #enum_name) -> &Box<dyn Pipeline> {
+                if self.pipelines[shader as usize].is_none() {
+                    self.create_pipeline(shader)
+                }
+
+                self.pipelines[shader as usize].as_ref().unwrap()
+            }
+
+            pub fn get_mut(&mut self, shader: #enum_name) -> &mut Box<dyn Pipeline> {
+                if self.pipelines[shader as usize].is_none() {
+                    self.create_pipeline(shader)
+                }
+
+                self.pipelines[shader as usize].as_mut().unwrap()
+            }
+
+            /// Returns the variant of a pipeline for another render pass with `samples` samples
+            /// per attachment, creating the pipeline and the variant on first use
+            pub fn get_variant(&mut self, shader: #enum_name, render_pass: vk::RenderPass, samples: vk::SampleCountFlags) -> vk::Pipeline {
+                if self.pipelines[shader as usize].is_none() {
+                    self.create_pipeline(shader)
+                }
+
+                let shader_module = self.shader_module.as_ref().unwrap();
+                let pipeline = self.pipelines[shader as usize].as_mut().unwrap();
+                shader.pipeline_variant(pipeline.as_mut(), shader_module, render_pass, samples)
+            }
+        }
+
+        #debug
+
+        impl Drop for #cache_name {
+            fn drop(&mut self) {
+                unsafe {
+                    self.device.destroy_pipeline_cache(self.pipeline_cache, None);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the module of the `simple` shader crate used by most of the tests
+#[cfg(test)]
+fn simple_crate() -> CrateModule {
+    let crate_dir =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../vkr-pipe-test/shader/simple");
+    CrateModule::new(crate_dir).unwrap()
+}
+
+#[test]
+fn expect_messages() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let main = pipelines.iter().find(|p| p.name == "Main").unwrap();
+
+    let gen = pipeline(&crate_module, main).unwrap().to_string();
+    assert!(gen.contains("Failed to create Vulkan graphics pipeline for simple-shader::Main"));
+    assert!(gen.contains("Failed to create Vulkan pipeline layout for simple-shader::Main"));
+}
+
+#[test]
+fn draw_instanced() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains("cmd_draw_indexed (command_buffer , index_count , instance_count , first_index , vertex_offset , first_instance ,)"));
+}
+
+#[test]
+fn write_set_wrapper_types() {
+    let file = syn::parse_quote! {
+        #[spirv(fragment)]
+        pub fn uniform_fs(
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] color: &Vec4,
+            #[spirv(descriptor_set = 0, binding = 1)] albedo: &SampledImage<Image2d>,
+            out_color: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(vertex)]
+        pub fn uniform_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+
+    let gen = write_set_methods(&pipelines[0].uniforms).to_string();
+    assert!(gen.contains("color : & vkr_core :: Buffer"));
+    assert!(gen.contains("albedo : & Texture"));
+    assert!(gen.contains("buffer (color . buffer)"));
+}
+
+#[test]
+fn bindless_arrays() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn scene_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn scene_fs(
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] tint: &Vec4,
+            #[pipewriter(count = 1024, partially_bound, update_after_bind)]
+            #[spirv(descriptor_set = 1, binding = 0)] textures: &RuntimeArray<SampledImage<Image2d>>,
+            out_color: &mut Vec4,
+        ) {
+        }
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    let uniforms = &pipelines[0].uniforms;
+
+    let layouts = set_layouts_methods(uniforms, "test").to_string();
+    assert!(layouts.contains(
+        "Self :: try_create_set_layout (device , & [vk :: DescriptorSetLayoutBinding :: builder () . binding (0u32) . descriptor_type (vk :: DescriptorType :: UNIFORM_BUFFER) . descriptor_count (1u32)"
+    ));
+    assert!(layouts.contains(". descriptor_count (1024u32)"));
+    assert!(layouts.contains(
+        "& [vk :: DescriptorBindingFlags :: from_raw (0 | vk :: DescriptorBindingFlags :: PARTIALLY_BOUND . as_raw () | vk :: DescriptorBindingFlags :: UPDATE_AFTER_BIND . as_raw ()) ,]"
+    ));
+    assert!(layouts.contains("vk :: DescriptorSetLayoutCreateFlags :: UPDATE_AFTER_BIND_POOL"));
+
+    let pool = descriptor_pool(
+        &item_ident("PipelineCacheScene".to_string()),
+        "test",
+        uniforms,
+    )
+    .to_string();
+    assert!(pool.contains(
+        ". ty (vk :: DescriptorType :: COMBINED_IMAGE_SAMPLER) . descriptor_count (1024u32 * set_copies)"
+    ));
+    assert!(pool.contains(
+        "vk :: DescriptorPoolCreateFlags :: FREE_DESCRIPTOR_SET | vk :: DescriptorPoolCreateFlags :: UPDATE_AFTER_BIND"
+    ));
+
+    let writes = write_set_methods(uniforms).to_string();
+    assert!(writes.contains(
+        "pub fn write_set_0 (& self , set : vk :: DescriptorSet , tint : & vkr_core :: Buffer)"
+    ));
+    assert!(writes.contains(
+        "pub fn write_set_1 (& self , set : vk :: DescriptorSet , textures : & [& Texture])"
+    ));
+    assert!(writes.contains(
+        "let textures_infos : Vec < _ > = textures . iter () . map (| resource | vk :: DescriptorImageInfo :: builder ()"
+    ));
+    assert!(writes.contains(". image_info (& textures_infos)"));
+    assert!(writes.contains(
+        "pub fn write_textures (& self , set : vk :: DescriptorSet , first_element : u32 , resources : & [& Texture])"
+    ));
+    assert!(writes.contains(". dst_array_element (first_element)"));
+}
+
+#[test]
+fn set_structs_output() {
+    let file = syn::parse_quote! {
+        #[spirv(fragment)]
+        pub fn uniform_fs(
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] color: &Vec4,
+            #[spirv(descriptor_set = 0, binding = 1)] albedo: &SampledImage<Image2d>,
+            #[spirv(uniform, descriptor_set = 1, binding = 0)] view: &Mat4,
+            out_color: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(vertex)]
+        pub fn uniform_vs(
+            in_pos: Vec3,
+            #[spirv(uniform, descriptor_set = 1, binding = 0)] view: &Mat4,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+
+    let gen = set_structs(&pipelines[0]).to_string();
+    assert!(gen.contains(
+        "pub struct PipelineUniformSet0 < 'a > { pub color : & 'a vkr_core :: Buffer , pub albedo : & 'a Texture , }"
+    ));
+    assert!(gen.contains(
+        "pub struct PipelineUniformSet1 < 'a > { pub view : & 'a vkr_core :: Buffer , }"
+    ));
+    assert!(gen.contains("pub fn write_set_0_with (& self , set : vk :: DescriptorSet , resources : & PipelineUniformSet0) { self . write_set_0 (set , resources . color , resources . albedo) ; }"));
+}
+
+#[test]
+fn external_vertex_formats() {
+    let mut vertex_formats = HashMap::new();
+    vertex_formats.insert(
+        "StandardVertex".to_string(),
+        vec![
+            "R32G32B32_SFLOAT".to_string(),
+            "R32G32B32_SFLOAT".to_string(),
+            "R32G32_SFLOAT".to_string(),
+        ],
+    );
+
+    let joints: syn::Ident = syn::parse_quote!(UVec4);
+    assert!(get_formats(&joints, &vertex_formats).unwrap() == ["R32G32B32A32_UINT"]);
+
+    let standard_vertex: syn::Ident = syn::parse_quote!(StandardVertex);
+    assert!(
+        get_formats(&standard_vertex, &vertex_formats)
+            .unwrap()
+            .len()
+            == 3
+    );
+    let input = VertexInput {
+        ty: standard_vertex,
+        format: None,
+        attribute: None,
+        instance: false,
+        binding: None,
+    };
+    let size: usize = get_input_formats(&input, &vertex_formats)
+        .unwrap()
+        .iter()
+        .map(|format| get_format_size(format))
+        .sum();
+    assert!(size == 32);
+
+    assert!(get_format_size("B8G8R8A8_UNORM") == 4);
+    assert!(get_format_size("A2B10G10R10_SNORM_PACK32") == 4);
+    assert!(get_format_size("R8G8B8A8_PACKED") == 0);
+}
+
+#[test]
+fn matrix_vertex_attributes() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn instanced_vs(
+            in_pos: Vec3,
+            in_model: Mat4,
+            in_normal: Mat3,
+            in_uv: Vec2,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn instanced_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    let vertex_formats = HashMap::new();
+
+    let (attributes, bindings) = get_vertex_attributes(&pipelines[0], &vertex_formats).unwrap();
+    let stride = bindings[0].stride;
+    assert!(attributes.len() == 9);
+    assert!(stride == 12 + 64 + 36 + 8);
+    assert!(attributes[1].location == 1 && attributes[1].offset == 12);
+    assert!(attributes[4].location == 4 && attributes[4].offset == 60);
+    assert!(attributes[4].format == "R32G32B32A32_SFLOAT");
+    assert!(attributes[5].format == "R32G32B32_SFLOAT" && attributes[5].offset == 76);
+    assert!(attributes[8].location == 8 && attributes[8].offset == 112);
+}
+
+#[test]
+fn scalar_vertex_attributes() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn skinned_vs(
+            in_pos: Vec3,
+            in_weight: f32,
+            in_bone: u32,
+            in_offset: i32,
+            in_uv: Vec2,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn skinned_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    let vertex_formats = HashMap::new();
+
+    let (attributes, bindings) = get_vertex_attributes(&pipelines[0], &vertex_formats).unwrap();
+    let stride = bindings[0].stride;
+    assert!(stride == 32);
+    assert!(attributes[1].format == "R32_SFLOAT" && attributes[1].offset == 12);
+    assert!(attributes[2].format == "R32_UINT" && attributes[2].offset == 16);
+    assert!(attributes[3].format == "R32_SINT" && attributes[3].offset == 20);
+    assert!(attributes[4].offset == 24);
+}
+
+#[test]
+fn pipeline_docs() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let uniform = pipelines.iter().find(|p| p.name == "Uniform").unwrap();
+
+    let gen = pipeline(&crate_module, uniform).unwrap().to_string();
+    assert!(gen.contains("# [doc = \" - location 0: `R32G32B32_SFLOAT` at offset 0\"]"));
+    assert!(
+        gen.contains("# [doc = \" - set 0, binding 0: `view` of type `UNIFORM_BUFFER` (Vertex)\"]")
+    );
+}
+
+#[test]
+fn mirrored_variant() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains("pub fn new_mirrored"));
+    assert!(gen.contains(
+        "Self :: builder (shader_module , render_pass) . front_face (vk :: FrontFace :: CLOCKWISE) . build ()"
+    ));
+    assert!(gen.contains(". front_face (front_face)"));
+}
+
+#[test]
+fn cache_required_features() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(fragment)]
+        #[pipewriter(depth_clamp)]
+        pub fn shadow_fs(out_color: &mut Vec4) {}
+
+        #[spirv(vertex)]
+        pub fn shadow_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        #[pipewriter(sample_shading)]
+        pub fn hair_fs(out_color: &mut Vec4) {}
+
+        #[spirv(vertex)]
+        pub fn hair_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+
+    let shadow = pipelines.iter().find(|p| p.name == "Shadow").unwrap();
+    assert!(shadow.state.features() == ["depth_clamp"]);
+    let hair = pipelines.iter().find(|p| p.name == "Hair").unwrap();
+    assert!(hair.state.features() == ["sample_rate_shading"]);
+
+    let gen = cache(&crate_module, &pipelines, None).to_string();
+    assert!(gen.contains(". depth_clamp (true) . sample_rate_shading (true)"));
+}
+
+#[test]
+fn bgra_vertex_format() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn color_vs(
+            in_pos: Vec3,
+            #[pipewriter(format = "B8G8R8A8_UNORM")] in_color: Vec4,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn color_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    let vertex_formats = HashMap::new();
+
+    let color = &pipelines[0].inputs[1];
+    assert!(get_input_formats(color, &vertex_formats).unwrap() == vec!["B8G8R8A8_UNORM"]);
+    let (attributes, bindings) = get_vertex_attributes(&pipelines[0], &vertex_formats).unwrap();
+    let stride = bindings[0].stride;
+    assert!(stride == 16);
+    assert!(attributes[1].format == "B8G8R8A8_UNORM" && attributes[1].offset == 12);
+}
+
+#[test]
+fn packed_vertex_formats() {
+    let file = syn::parse_quote! {
+        #[repr(C)]
+        pub struct CompressedVertex {
+            pos: Vec3,
+            #[pipewriter(format = "R8G8B8A8_SNORM")]
+            normal: u32,
+            #[pipewriter(format = "R16G16_UNORM")]
+            uv: [u16; 2],
+        }
+
+        #[spirv(vertex)]
+        pub fn mesh_vs(
+            in_vertex: CompressedVertex,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn mesh_fs(out_color: &mut Vec4) {}
+    };
+    let vertex_formats = crate::get_vertex_structs(&file, &HashMap::new());
+    let pipelines = crate::get_pipelines(&file).unwrap();
+
+    let (attributes, bindings) = get_vertex_attributes(&pipelines[0], &vertex_formats).unwrap();
+    assert!(bindings[0].stride == 20);
+    assert!(attributes[1].format == "R8G8B8A8_SNORM" && attributes[1].offset == 12);
+    assert!(attributes[2].format == "R16G16_UNORM" && attributes[2].offset == 16);
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn mesh_vs(
+            #[pipewriter(format = "R16G16_UNROM")] in_uv: Vec2,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn mesh_fs(out_color: &mut Vec4) {}
+    };
+    assert!(matches!(
+        crate::get_pipelines(&file),
+        Err(ShaderError::InvalidAttribute { .. })
+    ));
+}
+
+#[test]
+fn double_vertex_attributes() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn plot_vs(
+            in_pos: DVec3,
+            in_weight: f64,
+            in_dir: DVec4,
+            in_uv: DVec2,
+            in_id: u32,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn plot_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    let vertex_formats = HashMap::new();
+
+    let (attributes, bindings) = get_vertex_attributes(&pipelines[0], &vertex_formats).unwrap();
+    let layout: Vec<_> = attributes
+        .iter()
+        .map(|a| (a.location, a.format.as_str(), a.offset))
+        .collect();
+    assert!(
+        layout
+            == vec![
+                (0, "R64G64B64_SFLOAT", 0),
+                (2, "R64_SFLOAT", 24),
+                (3, "R64G64B64A64_SFLOAT", 32),
+                (5, "R64G64_SFLOAT", 64),
+                (6, "R32_UINT", 80),
+            ]
+    );
+    assert!(bindings[0].stride == 84);
+}
+
+#[test]
+fn vertex_stride_limit() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let secondary = pipelines.iter().find(|p| p.name == "Secondary").unwrap();
+
+    let (attributes, bindings) =
+        get_vertex_attributes(secondary, &crate_module.vertex_formats).unwrap();
+    assert!(bindings[0].stride == 20);
+    assert!(attributes[1].location == 1 && attributes[1].binding == 0);
+    assert!(attributes[1].format == "R32G32_SFLOAT" && attributes[1].offset == 12);
+
+    let gen = pipeline(&crate_module, secondary).unwrap().to_string();
+    assert!(gen.contains("VertexLayout { stride : Self :: VERTEX_STRIDE , instance_stride : Self :: INSTANCE_STRIDE , attributes : ATTRIBUTES , }"));
+    assert!(gen.contains(
+        "Vertex stride {} of simple-shader::Secondary exceeds maxVertexInputBindingStride {}"
+    ));
+}
+
+#[test]
+fn tessellation_patch() {
+    let crate_module = simple_crate();
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn terrain_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(tessellation_control(output_vertices = 3))]
+        pub fn terrain_tcs() {}
+
+        #[spirv(tessellation_evaluation)]
+        pub fn terrain_tes() {}
+
+        #[pipewriter(patch = 3)]
+        #[spirv(fragment)]
+        pub fn terrain_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.patch_control_points == Some(3));
+    assert!(pipelines[0].state.features() == ["tessellation_shader"]);
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains("let tcs_entry = CString :: new (\"terrain_tcs\")"));
+    assert!(gen.contains("stage . stage = vk :: ShaderStageFlags :: TESSELLATION_EVALUATION"));
+    assert!(gen.contains(". topology (vk :: PrimitiveTopology :: PATCH_LIST)"));
+    assert!(gen.contains(". tessellation_state (& tessellation)"));
+}
+
+#[test]
+fn blend_attachments() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn gbuffer_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(color_attachments = 3)]
+        #[spirv(fragment)]
+        pub fn gbuffer_fs(out_albedo: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.color_attachments == Some(3));
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains("[vk :: PipelineColorBlendAttachmentState ; 3usize]"));
+}
+
+#[test]
+fn opaque_blend() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(blend = "opaque")]
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.blend.as_deref() == Some("OPAQUE"));
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains(". blend_enable (false)"));
+    assert!(!gen.contains("ONE_MINUS_SRC_ALPHA"));
+}
+
+#[test]
+fn multisample() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(samples = 4)]
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.samples == 4 && !pipelines[0].state.alpha_to_coverage);
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains("vk :: SampleCountFlags :: TYPE_4"));
+    assert!(gen.contains(". rasterization_samples (samples)"));
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn foliage_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(samples = 4, alpha_to_coverage)]
+        #[spirv(fragment)]
+        pub fn foliage_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.alpha_to_coverage);
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains(". alpha_to_coverage_enable (true)"));
+}
+
+#[test]
+fn subpass_index() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn lighting_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(subpass = 1)]
+        #[spirv(fragment)]
+        pub fn lighting_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.subpass == 1);
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains("pub const SUBPASS : u32 = 1u32"));
+    assert!(gen.contains(". subpass (Self :: SUBPASS)"));
+}
+
+#[test]
+fn dynamic_viewport() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let main = pipelines.iter().find(|p| p.name == "Main").unwrap();
+
+    let gen = pipeline(&crate_module, main).unwrap().to_string();
+    assert!(gen.contains(". viewport_count (1) . scissor_count (1)"));
+    assert!(!gen.contains("1920"));
+    assert!(gen.contains("pub fn scissor (width : u32 , height : u32) -> vk :: Rect2D"));
+}
+
+#[test]
+fn instance_attributes() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn instanced_vs(
+            in_pos: Vec3,
+            #[pipewriter(instance)] in_model: Mat4,
+            in_uv: Vec2,
+            #[pipewriter(instance)] in_tint: Vec4,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn instanced_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+
+    let (attributes, bindings) =
+        get_vertex_attributes(&pipelines[0], &crate_module.vertex_formats).unwrap();
+    assert!(bindings.len() == 2);
+    assert!(bindings[0].stride == 20 && !bindings[0].instance);
+    assert!(bindings[1].stride == 80 && bindings[1].instance);
+    let locations: Vec<_> = attributes
+        .iter()
+        .map(|a| (a.location, a.offset, a.binding))
+        .collect();
+    assert!(
+        locations
+            == [
+                (0, 0, 0),
+                (1, 0, 1),
+                (2, 16, 1),
+                (3, 32, 1),
+                (4, 48, 1),
+                (5, 12, 0),
+                (6, 64, 1),
+            ]
+    );
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains(
+        ". binding (Self :: INSTANCE_BINDING) . stride (Self :: INSTANCE_STRIDE) . input_rate (vk :: VertexInputRate :: INSTANCE)"
+    ));
+    assert!(gen.contains("pub const INSTANCE_STRIDE : u32 = 80u32"));
+    // bind_buffers() alone would leave the instance binding unbound
+    assert!(gen.contains("debug_assert ! (2usize == 1"));
+
+    // Pipelines without per-instance inputs have a single binding
+    let main = crate::get_pipelines(&crate_module.file).unwrap();
+    let main = main.iter().find(|p| p.name == "Main").unwrap();
+    let gen = pipeline(&crate_module, main).unwrap().to_string();
+    assert!(!gen.contains("VertexInputRate :: INSTANCE"));
+}
+
+#[test]
+fn vertex_bindings() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn split_vs(
+            in_pos: Vec3,
+            #[pipewriter(binding = 2)] in_normal: Vec3,
+            #[pipewriter(binding = 2)] in_uv: Vec2,
+            #[pipewriter(instance)] in_tint: Vec4,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn split_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+
+    let (attributes, bindings) =
+        get_vertex_attributes(&pipelines[0], &crate_module.vertex_formats).unwrap();
+    let strides: Vec<_> = bindings.iter().map(|b| (b.binding, b.stride)).collect();
+    assert!(strides == [(0, 12), (1, 16), (2, 20)]);
+    let offsets: Vec<_> = attributes.iter().map(|a| (a.binding, a.offset)).collect();
+    assert!(offsets == [(0, 0), (2, 0), (2, 12), (1, 0)]);
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains(
+        ". binding (2u32) . stride (Self :: binding_stride (2u32)) . input_rate (vk :: VertexInputRate :: VERTEX)"
+    ));
+    assert!(gen.contains("2u32 => 20u32 ,"));
+    assert!(gen.contains("at offset 12 of binding 2"));
+
+    // Per-vertex and per-instance inputs can not share a binding
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn mixed_vs(
+            in_pos: Vec3,
+            #[pipewriter(instance, binding = 0)] in_tint: Vec4,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn mixed_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    assert!(get_vertex_attributes(&pipelines[0], &crate_module.vertex_formats).is_err());
+}
+
+#[test]
+fn wireframe() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn wireframe_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(polygon_mode = "line")]
+        #[spirv(fragment)]
+        pub fn wireframe_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.polygon_mode.as_deref() == Some("LINE"));
+    assert!(pipelines[0].state.features() == ["fill_mode_non_solid"]);
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains(". polygon_mode (vk :: PolygonMode :: LINE)"));
+    assert!(gen.contains("Missing device feature fill_mode_non_solid"));
+}
+
+#[test]
+fn stencil_test() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn mask_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(stencil(compare = "always", pass = "replace", reference = 1))]
+        #[spirv(fragment)]
+        pub fn mask_fs(out_color: &mut Vec4) {}
+
+        #[spirv(vertex)]
+        pub fn decal_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn decal_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    let mask = pipelines.iter().find(|p| p.name == "Mask").unwrap();
+    let decal = pipelines.iter().find(|p| p.name == "Decal").unwrap();
+
+    let stencil = mask.state.stencil.as_ref().unwrap();
+    assert!(stencil.front == stencil.back);
+    assert!(stencil.front.compare == "ALWAYS" && stencil.front.pass == "REPLACE");
+    assert!(stencil.front.fail == "KEEP" && stencil.front.depth_fail == "KEEP");
+    assert!(stencil.front.compare_mask == 255 && stencil.front.reference == 1);
+    assert!(decal.state.stencil.is_none());
+
+    let gen = pipeline(&crate_module, mask).unwrap().to_string();
+    assert!(gen.contains(". stencil_test_enable (true)"));
+    assert!(gen.contains("pass_op : vk :: StencilOp :: REPLACE"));
+
+    let gen = pipeline(&crate_module, decal).unwrap().to_string();
+    assert!(gen.contains(". stencil_test_enable (false)"));
+}
+
+#[test]
+fn dynamic_depth_bias() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn shadow_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(depth_bias)]
+        #[spirv(fragment)]
+        pub fn shadow_fs() {}
+
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    let shadow = pipelines.iter().find(|p| p.name == "Shadow").unwrap();
+    let main = pipelines.iter().find(|p| p.name == "Main").unwrap();
+
+    assert!(shadow.state.depth_bias && !main.state.depth_bias);
+
+    let gen = pipeline(&crate_module, shadow).unwrap().to_string();
+    assert!(gen.contains(". depth_bias_enable (true)"));
+    assert!(gen.contains(
+        "[vk :: DynamicState :: VIEWPORT , vk :: DynamicState :: SCISSOR , vk :: DynamicState :: DEPTH_BIAS ,]"
+    ));
+    assert!(gen.contains("pub fn set_depth_bias"));
+
+    let gen = pipeline(&crate_module, main).unwrap().to_string();
+    assert!(!gen.contains("set_depth_bias"));
+}
+
+#[test]
+fn dynamic_line_width() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn debug_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(topology = "line_list", line_width, depth_bias)]
+        #[spirv(fragment)]
+        pub fn debug_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    let state = &pipelines[0].state;
+    assert!(state.dynamic_line_width && state.depth_bias);
+    assert!(state.topology.as_deref() == Some("LINE_LIST"));
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains(
+        "[vk :: DynamicState :: VIEWPORT , vk :: DynamicState :: SCISSOR , vk :: DynamicState :: DEPTH_BIAS , vk :: DynamicState :: LINE_WIDTH ,]"
+    ));
+    assert!(gen.contains("cmd_set_line_width (command_buffer , line_width)"));
+}
+
+#[test]
+fn bind_and_draw_helpers() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let main = pipelines.iter().find(|p| p.name == "Main").unwrap();
+
+    let gen = pipeline(&crate_module, main).unwrap().to_string();
+    assert!(gen.contains(
+        "self . device . cmd_bind_pipeline (command_buffer , vk :: PipelineBindPoint :: GRAPHICS , self . pipeline) ;"
+    ));
+    assert!(gen.contains(
+        "self . device . cmd_bind_descriptor_sets (command_buffer , vk :: PipelineBindPoint :: GRAPHICS , self . layout , 0 , sets , dynamic_offsets ,) ;"
+    ));
+    assert!(gen.contains(
+        "self . bind_buffers (command_buffer , vertex_buffer , index_buffer , index_type) ; self . draw_instanced (command_buffer , index_count , 1 , 0 , 0 , 0) ;"
+    ));
+}
+
+#[test]
+fn dynamic_rendering() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let main = pipelines.iter().find(|p| p.name == "Main").unwrap();
+
+    let gen = pipeline(&crate_module, main).unwrap().to_string();
+    assert!(gen.contains(
+        "pub fn rendering_formats (mut self , rendering_formats : RenderingFormats) -> Self"
+    ));
+    assert!(gen.contains("PipelineRenderingCreateInfo :: new (formats , Self :: VIEW_MASK)"));
+    assert!(gen.contains(
+        "create_info . p_next = rendering as * const PipelineRenderingCreateInfo as * const std :: ffi :: c_void ;"
+    ));
+    assert!(gen.contains("Dynamic rendering of simple-shader::Main needs a null render pass"));
+
+    let header = header().to_string();
+    assert!(header.contains("pub struct RenderingFormats"));
+    assert!(header.contains("vk :: StructureType :: from_raw (1000044002)"));
+}
+
+#[test]
+fn render_pass_variants() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let main = pipelines.iter().find(|p| p.name == "Main").unwrap();
+
+    let gen = pipeline(&crate_module, main).unwrap().to_string();
+    assert!(gen.contains(
+        "variants : HashMap < (vk :: RenderPass , vk :: SampleCountFlags) , vk :: Pipeline >"
+    ));
+    assert!(gen.contains(
+        "if render_pass == self . render_pass && samples == Self :: SAMPLE_COUNT { return Ok (self . pipeline) ; }"
+    ));
+    assert!(gen.contains("self . variants . insert ((render_pass , samples) , pipeline) ;"));
+    assert!(gen.contains(
+        "for variant in self . variants . values () { self . device . destroy_pipeline (* variant , None) ; }"
+    ));
+
+    let gen = cache(&crate_module, &pipelines, None).to_string();
+    assert!(gen.contains(". downcast_mut :: < PipelineMain > () . unwrap () . variant (shader_module , render_pass , samples)"));
+}
+
+#[test]
+fn back_face_culling() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn opaque_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        #[pipewriter(cull_mode = "back", front_face = "clockwise")]
+        pub fn opaque_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.cull_mode.as_deref() == Some("BACK"));
+    assert!(pipelines[0].state.front_face.as_deref() == Some("CLOCKWISE"));
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains(". cull_mode (vk :: CullModeFlags :: BACK)"));
+    assert!(gen.contains("pub const FRONT_FACE : vk :: FrontFace = vk :: FrontFace :: CLOCKWISE"));
+    assert!(gen.contains(". front_face (vk :: FrontFace :: COUNTER_CLOCKWISE) . build ()"));
+
+    let gen = pipeline(
+        &crate_module,
+        &crate::get_pipelines(&crate_module.file).unwrap()[0],
+    )
+    .unwrap()
+    .to_string();
+    assert!(gen.contains(". cull_mode (vk :: CullModeFlags :: NONE)"));
+}
+
+#[test]
+fn line_topology() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn wire_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        #[pipewriter(topology = "line_list")]
+        pub fn wire_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.topology.as_deref() == Some("LINE_LIST"));
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains(". topology (vk :: PrimitiveTopology :: LINE_LIST)"));
+}
+
+#[test]
+fn compute_pipeline_gen() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(compute(threads(64)))]
+        pub fn blur_cs(
+            #[spirv(global_invocation_id)] id: UVec3,
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] params: &Vec4,
+        ) {
+        }
+
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    let blur = pipelines.iter().find(|p| p.name == "Blur").unwrap();
+
+    let gen = compute_pipeline(&crate_module, blur).to_string();
+    assert!(gen.contains("pub const WORKGROUP_SIZE : [u32 ; 3] = [64u32 , 1u32 , 1u32]"));
+    assert!(gen.contains("vk :: ComputePipelineCreateInfo :: builder ()"));
+    assert!(gen.contains("pub fn new (shader_module : & ShaderModule) -> Self"));
+    assert!(gen.contains(". stage_flags (vk :: ShaderStageFlags :: COMPUTE)"));
+    assert!(!gen.contains("render_pass"));
+
+    // Compute pipelines are not part of the cache
+    let gen = cache(&crate_module, &pipelines, None).to_string();
+    assert!(gen.contains("Main ,"));
+    assert!(!gen.contains("Blur"));
+}
+
+#[test]
+fn geometry_stage() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn particle_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(geometry(input_points = 1, output_triangle_strip = 4))]
+        pub fn particle_gs() {}
+
+        #[spirv(fragment)]
+        pub fn particle_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains("let gs_entry = CString :: new (\"particle_gs\")"));
+    assert!(gen.contains("stage . stage = vk :: ShaderStageFlags :: GEOMETRY"));
+    assert!(gen.contains(". geometry_shader (true)"));
+}
+
+#[test]
+fn push_constant_ranges() {
+    let crate_module = simple_crate();
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(
+            in_pos: Vec3,
+            #[spirv(push_constant)] transform: &Mat4,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn main_fs(#[spirv(push_constant)] transform: &Mat4, out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains(
+        ". stage_flags (vk :: ShaderStageFlags :: VERTEX | vk :: ShaderStageFlags :: FRAGMENT) . offset (0) . size (64u32)"
+    ));
+    assert!(gen.contains(". push_constant_ranges (& push_constant_ranges)"));
+    assert!(gen.contains("pub fn push_transform < T : Copy >"));
+}
+
+#[test]
+fn descriptors_only() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let uniform = pipelines.iter().find(|p| p.name == "Uniform").unwrap();
+
+    let gen = descriptors(&crate_module, uniform).to_string();
+    assert!(gen.contains("pub fn write_set_0"));
+    assert!(gen.contains("pub fn new_layout"));
+    assert!(!gen.contains("new_impl"));
+    assert!(
+        gen.contains("pub fn try_new (device : & Rc < Device >) -> Result < Self , vk :: Result >")
+    );
+    assert!(gen.contains("let set_layouts = Self :: try_new_set_layouts (device) ? ;"));
+    assert!(!gen.contains("impl Pipeline for"));
+}
+
+#[test]
+fn set_and_binding_counts() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let uniform = pipelines.iter().find(|p| p.name == "Uniform").unwrap();
+
+    let gen = set_layouts_methods(&uniform.uniforms, "simple-shader::Uniform").to_string();
+    assert!(gen.contains("pub fn set_count () -> usize { 4usize }"));
+    assert!(gen
+        .contains("0u32 => 1usize , 1u32 => 1usize , 2u32 => 2usize , 3u32 => 2usize , _ => 0 ,"));
+}
+
+#[test]
+fn set_gaps() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(
+            in_pos: Vec3,
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] view: &Mat4,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn main_fs(
+            #[spirv(descriptor_set = 2, binding = 0)] albedo: &SampledImage<Image2d>,
+            out_color: &mut Vec4,
+        ) {
+        }
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    let uniforms = &pipelines[0].uniforms;
+
+    let gen = set_layouts_methods(uniforms, "test").to_string();
+    assert!(gen.contains("pub fn set_count () -> usize { 3usize }"));
+    assert!(gen.contains(
+        "UNIFORM_BUFFER) . descriptor_count (1u32) . stage_flags (vk :: ShaderStageFlags :: VERTEX) . build () ,]) , Self :: try_create_set_layout (device , & []) , Self :: try_create_set_layout (device , & [vk :: DescriptorSetLayoutBinding :: builder () . binding (0u32) . descriptor_type (vk :: DescriptorType :: COMBINED_IMAGE_SAMPLER)"
+    ));
+    assert!(gen.contains("0u32 => 1usize , 2u32 => 1usize , _ => 0 ,"));
+
+    let pool = descriptor_pool(
+        &item_ident("PipelineCacheMain".to_string()),
+        "test",
+        uniforms,
+    )
+    .to_string();
+    assert!(pool.contains("(3u32 * set_copies) . max (1)"));
+}
+
+#[test]
+fn write_set_range_checks() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let uniform = pipelines.iter().find(|p| p.name == "Uniform").unwrap();
+
+    let gen = write_set_methods(&uniform.uniforms).to_string();
+    assert!(gen.contains(
+        "debug_assert ! (view . size >= 64u64 , \"Buffer view is smaller than the 64 bytes of Mat4\")"
+    ));
+    assert!(gen.contains(
+        "debug_assert ! (color . size >= 16u64 , \"Buffer color is smaller than the 16 bytes of Vec4\")"
+    ));
+}
+
+#[test]
+fn write_set_info_locals() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let uniform = pipelines.iter().find(|p| p.name == "Uniform").unwrap();
+
+    // The infos need to outlive the writes pointing to them
+    let gen = write_set_methods(&uniform.uniforms).to_string();
+    assert!(gen.contains("let view_infos = [vk :: DescriptorBufferInfo :: builder ()"));
+    assert!(gen.contains(". buffer_info (& view_infos) . build ()"));
+    assert!(!gen.contains("buffer_info (& ["));
+}
+
+#[test]
+fn uniform_reflection() {
+    let crate_module = simple_crate();
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn lit_vs(
+            in_pos: Vec3,
+            #[spirv(uniform, descriptor_set = 1, binding = 0)] model: &Mat4,
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] view: &Mat4,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn lit_fs(
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] view: &Mat4,
+            out_color: &mut Vec4,
+        ) {
+        }
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+
+    let gen = reflection_method(&pipelines[0].uniforms).to_string();
+    assert!(gen.contains("UniformInfo { name : \"view\" , set : 0u32 , binding : 0u32 , descriptor_type : vk :: DescriptorType :: UNIFORM_BUFFER , stages : vk :: ShaderStageFlags :: from_raw (0 | vk :: ShaderStageFlags :: VERTEX . as_raw () | vk :: ShaderStageFlags :: FRAGMENT . as_raw ()) , } , UniformInfo { name : \"model\" , set : 1u32"));
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains("pub fn reflection () -> & 'static [UniformInfo]"));
+}
+
+#[test]
+fn return_type_output() {
+    let crate_module = simple_crate();
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn main_fs() -> Vec4 {
+            vec4(1.0, 0.0, 0.0, 1.0)
+        }
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    assert!(pipelines[0].color_outputs == 1);
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(
+        gen.matches("PipelineColorBlendAttachmentState :: builder ()")
+            .count()
+            == 1
+    );
+}
+
+#[test]
+fn frames_in_flight() {
+    let name = item_ident("PipelineCacheMain".to_string());
+    let gen = descriptor_pool(&name, "simple-shader::Main", &[]).to_string();
+    assert!(gen.contains("Self :: pool_sizes (Self :: DEFAULT_SETS_PER_FRAME * frames_in_flight)"));
+    assert!(gen.contains("(0u32 * set_copies) . max (1)"));
+    assert!(gen.contains("Self :: with_frames_in_flight (device , 1)"));
+    assert!(gen.contains("let set_copies = sets_per_frame * frames_in_flight ;"));
+}
+
+#[test]
+fn pool_sizes() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let uniform = pipelines.iter().find(|p| p.name == "Uniform").unwrap();
+
+    let name = item_ident("PipelineCacheUniform".to_string());
+    let gen = descriptor_pool(&name, "simple-shader::Uniform", &uniform.uniforms).to_string();
+    assert!(gen.contains(
+        ". ty (vk :: DescriptorType :: UNIFORM_BUFFER) . descriptor_count (3u32 * set_copies)"
+    ));
+    assert!(gen.contains(
+        ". ty (vk :: DescriptorType :: COMBINED_IMAGE_SAMPLER) . descriptor_count (1u32 * set_copies)"
+    ));
+    assert!(gen.contains(
+        ". ty (vk :: DescriptorType :: INPUT_ATTACHMENT) . descriptor_count (2u32 * set_copies)"
+    ));
+    assert!(!gen.contains("STORAGE_BUFFER"));
+    assert!(gen.contains("(4u32 * set_copies) . max (1)"));
+}
+
+#[test]
+fn validate_cache() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+
+    let gen = cache(&crate_module, &pipelines, None).to_string();
+    assert!(gen.contains("pub fn validate (& mut self) -> Result < () , Vec < (ShaderSimpleShader , vk :: Result) >>"));
+    assert!(gen.contains("pub fn instantiated (& self) -> Vec < ShaderSimpleShader >"));
+    assert!(gen.contains(". filter (| shader | self . pipelines [* shader as usize] . is_some ())"));
+    assert!(
+        gen.contains("builder . try_build () . map (| p | Box :: new (p) as Box < dyn Pipeline >)")
+    );
+}
+
+#[test]
+fn cache_builder() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+
+    let gen = cache(&crate_module, &pipelines, None).to_string();
+    assert!(gen.contains("pub fn builder (dev : & Dev) -> PipelineCacheBuilder"));
+    assert!(gen.contains("pub fn new (dev : & Dev) -> Self { Self :: builder (dev) . build () }"));
+    assert!(gen.contains("PipelineMain :: builder (shader_module , render_pass) . pipeline_cache (pipeline_cache) . frames_in_flight (frames_in_flight)"));
+    assert!(gen.contains("Self :: builder (dev) . spv_path (path) . build ()"));
+    assert!(gen.contains("std :: fs :: write (path , data)"));
+    assert!(gen.contains("self . device . destroy_pipeline_cache (self . pipeline_cache , None)"));
+    assert!(gen.contains(
+        "pub fn reload (& mut self) { for pipeline in & mut self . pipelines { * pipeline = None ; } self . shader_module = None ; self . get_shader_module () ; }"
+    ));
+    assert!(gen.contains("Failed to read SPIR-V of simple-shader from {}: {}"));
+    assert!(gen.contains(
+        "pub fn with_render_pass (dev : & Dev , render_pass : vk :: RenderPass) -> Self { Self :: builder (dev) . render_pass (render_pass) . build () }"
+    ));
+    assert!(gen.contains("self . render_passes [shader as usize] . unwrap_or (self . render_pass)"));
+    assert!(gen.contains("let render_pass = self . get_pipeline_render_pass (shader) ;"));
+}
+
+#[test]
+fn shared_vertex_layout() {
+    let mut vertex_formats = HashMap::new();
+    vertex_formats.insert(
+        "StandardVertex".to_string(),
+        vec![
+            "R32G32B32_SFLOAT".to_string(),
+            "R32G32B32_SFLOAT".to_string(),
+            "R32G32_SFLOAT".to_string(),
+        ],
+    );
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        #[pipewriter(vertex_layout = "StandardVertex")]
+        pub fn depth_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn depth_fs() {}
+
+        #[spirv(vertex)]
+        #[pipewriter(vertex_layout = "StandardVertex")]
+        pub fn uv_vs(
+            in_pos: Vec3,
+            #[pipewriter(attribute = 2)] in_uv: Vec2,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn uv_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+
+    let depth = pipelines.iter().find(|p| p.name == "Depth").unwrap();
+    let (attributes, bindings) = get_vertex_attributes(depth, &vertex_formats).unwrap();
+    let stride = bindings[0].stride;
+    assert!(stride == 32);
+    assert!(attributes.len() == 1);
+    assert!(attributes[0].format == "R32G32B32_SFLOAT" && attributes[0].offset == 0);
+
+    let uv = pipelines.iter().find(|p| p.name == "Uv").unwrap();
+    let (attributes, bindings) = get_vertex_attributes(uv, &vertex_formats).unwrap();
+    let stride = bindings[0].stride;
+    assert!(stride == 32);
+    assert!(attributes[1].location == 1);
+    assert!(attributes[1].format == "R32G32_SFLOAT" && attributes[1].offset == 24);
+}
+
+#[test]
+fn padded_vertex_struct() {
+    let file = syn::parse_quote! {
+        #[repr(C)]
+        pub struct PaddedVertex {
+            pos: Vec3,
+            _pad: f32,
+            normal: Vec3,
+            #[pipewriter(pad)]
+            reserved: f32,
+            uv: Vec2,
+        }
+
+        #[spirv(vertex)]
+        #[pipewriter(vertex_layout = "PaddedVertex")]
+        pub fn padded_vs(
+            in_pos: Vec3,
+            #[pipewriter(attribute = 2)] in_uv: Vec2,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn padded_fs(out_color: &mut Vec4) {}
+
+        #[spirv(vertex)]
+        pub fn direct_vs(in_vertex: PaddedVertex, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn direct_fs(out_color: &mut Vec4) {}
+    };
+    let vertex_formats = crate::get_vertex_structs(&file, &HashMap::new());
+    assert!(vertex_formats["PaddedVertex"].len() == 5);
+    let pipelines = crate::get_pipelines(&file).unwrap();
+
+    let padded = pipelines.iter().find(|p| p.name == "Padded").unwrap();
+    let (attributes, bindings) = get_vertex_attributes(padded, &vertex_formats).unwrap();
+    let stride = bindings[0].stride;
+    assert!(stride == 40);
+    assert!(attributes.len() == 2);
+    assert!(attributes[1].format == "R32G32_SFLOAT" && attributes[1].offset == 32);
+
+    let direct = pipelines.iter().find(|p| p.name == "Direct").unwrap();
+    let (attributes, bindings) = get_vertex_attributes(direct, &vertex_formats).unwrap();
+    let stride = bindings[0].stride;
+    assert!(stride == 40);
+    assert!(attributes.len() == 3);
+    assert!(attributes[1].location == 1 && attributes[1].offset == 16);
+    assert!(attributes[2].location == 2 && attributes[2].offset == 32);
+}
+
+#[test]
+fn bind_buffers() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains("pub fn bind_buffers"));
+    assert!(gen.contains(
+        "cmd_bind_vertex_buffers (command_buffer , Self :: VERTEX_BINDING , & [vertex_buffer . buffer] , & [0] ,)"
+    ));
+    assert!(gen.contains(". binding (Self :: VERTEX_BINDING)"));
+    assert!(gen.contains("debug_assert ! (1usize == 1"));
+    assert!(gen.contains("pub const INDEX_TYPE : vk :: IndexType = vk :: IndexType :: UINT16"));
+    assert!(gen.contains("pub fn bind_model_buffers"));
+    assert!(gen.contains("self . bind_buffers (command_buffer , & primitive . vertices , indices , Self :: INDEX_TYPE)"));
+}
+
+#[test]
+fn reverse_z() {
+    let crate_module = simple_crate();
+    let mut pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains(". depth_compare_op (vk :: CompareOp :: GREATER)"));
+    assert!(gen.contains(". min_depth (1f32) . max_depth (0f32)"));
+
+    // A crate with reverse-Z off
+    pipelines[0].state.reverse_z = Some(false);
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains(". depth_compare_op (vk :: CompareOp :: LESS)"));
+    assert!(gen.contains(". min_depth (0f32) . max_depth (1f32)"));
+
+    // An explicit compare op keeps the depth range
+    pipelines[0].state.depth_compare = Some("LESS_OR_EQUAL".into());
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains(". depth_compare_op (vk :: CompareOp :: LESS_OR_EQUAL)"));
+    assert!(gen.contains(". min_depth (0f32) . max_depth (1f32)"));
+}
+
+#[test]
+fn spec_constant_defaults() {
+    let crate_module = simple_crate();
+    let file = syn::parse_quote! {
+        #[spirv(constant_id = 1)]
+        const LIGHT_COUNT: u32 = 8;
+
+        #[spirv(vertex)]
+        pub fn lit_vs(
+            in_pos: Vec3,
+            #[spirv(spec_constant(id = 0, default = 4))] cascade_count: u32,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn lit_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    let lit = &pipelines[0];
+    assert!(lit.inputs.len() == 1);
+    assert!(lit.spec_constants.len() == 2);
+
+    let gen = pipeline(&crate_module, lit).unwrap().to_string();
+    assert!(gen.contains(
+        "pub struct PipelineLitSpec { pub cascade_count : u32 , pub light_count : u32 , }"
+    ));
+    assert!(gen.contains("Self { cascade_count : 4u32 , light_count : 8 , }"));
+    assert!(gen.contains(". constant_id (1u32) . offset (4u32)"));
+    assert!(
+        gen.contains("Self :: builder (shader_module , render_pass) . spec (* spec) . build ()")
+    );
+    assert!(gen.contains("pub fn spec (mut self , spec : PipelineLitSpec) -> Self"));
+    assert!(gen.contains(". data (self . spec . as_bytes ())"));
+}
+
+#[test]
+fn pipeline_builder_defaults() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let main = pipelines.iter().find(|p| p.name == "Main").unwrap();
+
+    let gen = pipeline(&crate_module, main).unwrap().to_string();
+    assert!(gen.contains("pub struct PipelineMainBuilder < 's >"));
+    assert!(gen.contains("front_face : Self :: FRONT_FACE , pipeline_cache : vk :: PipelineCache :: null () , frames_in_flight : 1 , sets_per_frame : PipelineCacheMain :: DEFAULT_SETS_PER_FRAME ,"));
+    assert!(gen.contains("pub fn new (shader_module : & ShaderModule , render_pass : vk :: RenderPass) -> Self { Self :: builder (shader_module , render_pass) . build () }"));
+    assert!(gen.contains("self . front_face , self . pipeline_cache , None ,)"));
+}
+
+#[test]
+fn multiview() {
+    let crate_module = simple_crate();
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        #[pipewriter(views = 2)]
+        pub fn stereo_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn stereo_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.views == 2);
+
+    let gen = pipeline(&crate_module, &pipelines[0]).unwrap().to_string();
+    assert!(gen.contains("pub const VIEW_COUNT : u32 = 2u32"));
+    assert!(gen.contains("VK_KHR_multiview"));
+}
+
+#[test]
+fn type_name_prefix() {
+    let crate_module = simple_crate();
+    let mut pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    for pipeline in &mut pipelines {
+        pipeline.prefix = String::from("Gfx");
+    }
+    let main = pipelines.iter().find(|p| p.name == "Main").unwrap();
+
+    let gen = pipeline(&crate_module, main).unwrap().to_string();
+    assert!(gen.contains("pub struct GfxMain {"));
+    assert!(gen.contains("pub struct GfxMainBuilder < 's >"));
+    assert!(gen.contains("pub struct GfxCacheMain {"));
+    assert!(!gen.contains("PipelineMain"));
+
+    let gen = cache(&crate_module, &pipelines, Some("Gfx")).to_string();
+    assert!(gen.contains("pub enum GfxShaderSimpleShader"));
+    assert!(gen.contains("pub struct GfxCache {"));
+    assert!(gen.contains("pub fn builder (dev : & Dev) -> GfxCacheBuilder"));
+    assert!(gen.contains("GfxMain :: builder (shader_module , render_pass)"));
+    assert!(!gen.contains("PipelineCache {"));
+}
+
+#[test]
+fn debug_impls() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let main = pipelines.iter().find(|p| p.name == "Main").unwrap();
+
+    let gen = pipeline(&crate_module, main).unwrap().to_string();
+    assert!(gen.contains("impl std :: fmt :: Debug for PipelineMain {"));
+    assert!(gen.contains("f . debug_struct (\"PipelineMain\") . field (\"name\" , & self . name) . field (\"pipeline\" , & ash :: vk :: Handle :: as_raw (self . pipeline))"));
+    assert!(gen.contains(". field (\"caches\" , & self . caches . len ()) . finish ()"));
+    assert!(gen.contains("impl std :: fmt :: Debug for PipelineCacheMain {"));
+    assert!(gen.contains("pub fn reset_cache (& mut self , index : usize) { if let Some (cache) = self . caches . get_mut (index) { cache . reset () ; } }"));
+
+    let gen = descriptors(&crate_module, main).to_string();
+    assert!(gen.contains("impl std :: fmt :: Debug for PipelineMain {"));
+    assert!(!gen.contains("as_raw (self . pipeline)"));
+
+    let gen = cache(&crate_module, &pipelines, None).to_string();
+    assert!(gen.contains("impl std :: fmt :: Debug for PipelineCache {"));
+    assert!(gen.contains(
+        ". field (\"pipelines\" , & self . pipelines . iter () . flatten () . count ())"
+    ));
+}
+
+#[test]
+fn layout_errors() {
+    let crate_module = simple_crate();
+    let pipelines = crate::get_pipelines(&crate_module.file).unwrap();
+    let main = pipelines.iter().find(|p| p.name == "Main").unwrap();
+
+    let gen = pipeline(&crate_module, main).unwrap().to_string();
+    assert!(gen
+        .contains("let set_layouts = Self :: try_new_set_layouts (& shader_module . device) ? ;"));
+    assert!(gen.contains(
+        "let layout = match Self :: try_new_layout (& shader_module . device , & set_layouts)"
+    ));
+    assert!(gen.contains("Self :: try_new_layout (device , set_layouts) . expect (\"Failed to create Vulkan pipeline layout for simple-shader::Main\")"));
+    assert!(gen
+        .contains("if let Some (err) = results . iter () . find_map (| result | result . err ())"));
+    assert!(gen.contains("Self :: try_new_set_layouts (device) . expect (\"Failed to create Vulkan descriptor set layout for simple-shader::Main\")"));
+}
+
+#[test]
+fn free_descriptor_sets() {
+    let uniforms = [];
+    let name = item_ident(String::from("PipelineCacheMain"));
+    let gen = descriptor_pool(&name, "simple-shader::Main", &uniforms).to_string();
+    assert!(gen.contains("pub fn try_free (& self , descriptors : & [vk :: DescriptorSet]) -> Result < () , vk :: Result >"));
+    assert!(gen.contains("panic ! (\"Failed to free {} Vulkan descriptor sets for simple-shader::Main: {:?}\" , descriptors . len () , err)"));
+    assert!(!gen.contains("msFailed"));
+    assert!(gen.contains("pub fn get_or_allocate_sets (& mut self , frame : usize , layouts : & [vk :: DescriptorSetLayout]) -> & [vk :: DescriptorSet]"));
+    assert!(gen
+        .contains("let sets = self . allocate (layouts) ; self . sets . insert (frame , sets) ;"));
+    assert!(gen.contains("pub fn free_sets (& mut self , frame : usize) { if let Some (sets) = self . sets . remove (& frame) { if ! sets . is_empty () { self . free (& sets) ; } } }"));
+    assert!(gen.contains("pub fn reset (& mut self)"));
+}
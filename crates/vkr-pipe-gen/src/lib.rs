@@ -0,0 +1,2664 @@
+// Copyright © 2021
+// Author: Antonio Caggiano <info@antoniocaggiano.eu>
+// SPDX-License-Identifier: MIT
+
+use std::collections::{HashMap, HashSet};
+
+use syn::spanned::Spanned;
+
+mod util;
+use util::*;
+
+mod shader;
+use shader::*;
+pub use shader::{DescriptorType, Pipeline, ShaderType, Uniform};
+
+mod module;
+pub use module::CrateModule;
+
+mod spirv;
+
+pub mod gen;
+
+mod error;
+pub use error::ShaderError;
+
+/// Options of the generated code, which the `pipewriter!` macro takes as arguments
+pub struct Options {
+    /// Generates only descriptor and layout code, without graphics pipelines
+    pub descriptors_only: bool,
+    /// Uses reverse-Z for the pipelines without their own depth convention
+    pub reverse_z: bool,
+    /// Start of the names of the generated types, instead of `Pipeline`
+    pub prefix: Option<String>,
+    /// Takes the uniforms from the SPIR-V of the crate instead of its attributes
+    pub reflect: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            descriptors_only: false,
+            reverse_z: true,
+            prefix: None,
+            reflect: false,
+        }
+    }
+}
+
+/// Generates the pipelines of a shader crate, as the `pipewriter!` macro does.
+/// Build scripts can write the result to a file to `include!`, or inspect it.
+pub fn generate(
+    crate_module: &CrateModule,
+    options: &Options,
+) -> Result<proc_macro2::TokenStream, ShaderError> {
+    let mut pipelines = get_pipelines(&crate_module.file)?;
+    if pipelines.is_empty() {
+        return Err(ShaderError::NoEntryPoints(crate_module.shader_path.clone()));
+    }
+
+    // The SPIR-V built by rust-gpu knows the bindings the shaders actually use
+    if options.reflect && crate_module.spirv_path.is_none() {
+        let shader_spv = format!("{}.spv", crate_module.name.replace('-', "_"));
+        let path = std::env::var_os(&shader_spv).ok_or(ShaderError::MissingSpirv(shader_spv))?;
+        let path = std::path::PathBuf::from(path);
+        let code =
+            std::fs::read(&path).map_err(|err| ShaderError::UnreadableFile(path.clone(), err))?;
+        spirv::reflect_uniforms(&mut pipelines, &code)
+            .map_err(|err| ShaderError::Parse(path, err))?;
+    }
+
+    // Pipelines without their own depth convention follow the one of the crate
+    for pipeline in &mut pipelines {
+        pipeline.state.reverse_z.get_or_insert(options.reverse_z);
+        if let Some(prefix) = &options.prefix {
+            pipeline.prefix = prefix.clone();
+        }
+    }
+
+    if options.descriptors_only {
+        let mut gen = gen::descriptors_header();
+        for pipeline in &pipelines {
+            gen.extend(gen::descriptors(crate_module, pipeline));
+        }
+        return Ok(gen);
+    }
+
+    let mut gen = gen::header();
+
+    gen.extend(gen::cache(
+        crate_module,
+        &pipelines,
+        options.prefix.as_deref(),
+    ));
+
+    for pipeline in &pipelines {
+        let pipeline_gen = if pipeline.is_compute() {
+            gen::compute_pipeline(crate_module, pipeline)
+        } else {
+            gen::pipeline(crate_module, pipeline)?
+        };
+        gen.extend(pipeline_gen);
+    }
+
+    Ok(gen)
+}
+
+/// Collects all the pipelines found in a shader file
+pub fn get_pipelines(file: &syn::File) -> Result<Vec<Pipeline>, ShaderError> {
+    // Shader functions may be nested in inline modules
+    let items = get_items(&file.items);
+    let functions = items
+        .iter()
+        .filter_map(|i| inner_value!(i, syn::Item::Fn(f) => f));
+
+    // Fragment shaders may pair with the vertex shader of another pipeline
+    let mut shared_vertices: HashMap<String, Vec<String>> = HashMap::new();
+    for func in functions.clone() {
+        if let Some((vertex, span)) = get_shared_vertex(func)? {
+            let vertex_func = functions
+                .clone()
+                .find(|f| f.sig.ident == vertex && get_shader_type(f) == Some(ShaderType::Vertex))
+                .ok_or_else(|| ShaderError::InvalidAttribute {
+                    message: format!("Expected the name of a vertex shader, not {}", vertex),
+                    span,
+                })?;
+            shared_vertices
+                .entry(vertex_func.sig.ident.to_string())
+                .or_default()
+                .push(get_pipeline_name(func)?);
+        }
+    }
+
+    // Collect names first
+    let mut names: HashSet<String> = functions
+        .clone()
+        .filter(|func| {
+            matches!(
+                get_shader_type(func),
+                Some(ShaderType::Vertex | ShaderType::Compute)
+            )
+        })
+        .map(get_pipeline_name)
+        .collect::<Result<_, _>>()?;
+    names.extend(shared_vertices.values().flatten().cloned());
+
+    // TODO contruct pipelines now and then populate args and uniforms?
+    let mut builders: HashMap<String, PipelineBuilder> = names
+        .into_iter()
+        .map(|name| (name.clone(), Pipeline::builder().name(name)))
+        .collect();
+
+    // Specialization constants declared as constants are visible to all the shaders
+    let const_spec_constants = get_const_spec_constants(file)?;
+
+    // Structs may back uniform buffers and push constants
+    let struct_sizes = get_struct_sizes(file);
+
+    // Output vertices declared by tessellation control shaders
+    let mut output_vertices: HashMap<String, (u32, proc_macro2::Span)> = HashMap::new();
+
+    // Attributes enabling primitive restart, checked against the final topology
+    let mut primitive_restarts: HashMap<String, proc_macro2::Span> = HashMap::new();
+
+    // Vertex outputs and fragment inputs of each shader function, checked against each other
+    let mut interfaces: HashMap<String, Vec<(syn::Ident, String)>> = HashMap::new();
+
+    // Go through all the functions of the file
+    for func in functions {
+        if let Some(vertices) = get_output_vertices(func)? {
+            output_vertices.insert(get_pipeline_name(func)?, vertices);
+        }
+
+        // Analyze spirv attribute
+        if let Some(shader_type) = get_shader_type(func) {
+            for arg in &func.sig.inputs {
+                if let syn::FnArg::Typed(arg) = arg {
+                    if let Some(pipewriter) = get_pipewriter(&arg.attrs) {
+                        check_keys(&pipewriter, ARGUMENT_KEYS)?;
+                    }
+                }
+            }
+
+            // A shared vertex shader is part of the pipelines of its fragment shaders as well
+            let mut names = vec![get_pipeline_name(func)?];
+            if let Some(shared) = shared_vertices.get(&func.sig.ident.to_string()) {
+                names.extend(shared.iter().cloned());
+            }
+
+            for name in names {
+                let builder =
+                    builders
+                        .get_mut(&name)
+                        .ok_or_else(|| ShaderError::MissingEntryPoint {
+                            pipeline: name.clone(),
+                            stage: ShaderType::Vertex,
+                        })?;
+
+                // Entry points are the functions themselves, whatever their suffix
+                let function = func.sig.ident.to_string();
+                if let Some(existing) = builder.entry_point(shader_type, function.clone()) {
+                    return Err(ShaderError::InvalidAttribute {
+                        message: format!(
+                            "Pipeline {} has multiple {:?} shaders: {} and {}",
+                            name, shader_type, existing, function
+                        ),
+                        span: func.sig.ident.span(),
+                    });
+                }
+
+                if shader_type == ShaderType::Vertex {
+                    let inputs = get_vertex_inputs(func)?;
+                    builder.inputs(inputs);
+                    interfaces.insert(function, get_stage_interface(func, true));
+                } else if shader_type == ShaderType::Fragment {
+                    builder.color_outputs(get_color_outputs(func));
+                    interfaces.insert(function, get_stage_interface(func, false));
+                } else if shader_type == ShaderType::Compute {
+                    builder.workgroup_size(get_workgroup_size(func)?);
+                } else {
+                    builder.add_stage(shader_type);
+                }
+
+                // Shaders sharing a binding are merged into one, so they need to agree on its descriptors
+                let uniforms = get_uniforms(func, &struct_sizes)?;
+                for uniform in &uniforms {
+                    let existing = builder.uniforms.iter().find(|u| {
+                        u.descriptor_set == uniform.descriptor_set && u.binding == uniform.binding
+                    });
+                    if let Some(existing) = existing {
+                        if existing.descriptor_type != uniform.descriptor_type
+                            || existing.count != uniform.count
+                        {
+                            return Err(ShaderError::InvalidAttribute {
+                                message: format!(
+                                    "Set {} binding {} of pipeline {} is {} {} as {} but {} {} as {}",
+                                    uniform.descriptor_set,
+                                    uniform.binding,
+                                    name,
+                                    existing.count,
+                                    existing.descriptor_type.name(),
+                                    existing.name,
+                                    uniform.count,
+                                    uniform.descriptor_type.name(),
+                                    uniform.name
+                                ),
+                                span: uniform.name.span(),
+                            });
+                        }
+                    }
+                }
+                builder.add_uniforms(uniforms);
+                builder.add_push_constants(get_push_constants(func, &struct_sizes)?);
+                builder.add_spec_constants(get_spec_constants(func)?);
+
+                if let Some(pipewriter) = get_pipewriter(&func.attrs) {
+                    parse_pipeline_state(&pipewriter, &mut builder.state)?;
+                    if has_meta_path(&pipewriter, "primitive_restart") {
+                        primitive_restarts.insert(name.clone(), pipewriter.span());
+                    }
+                }
+            }
+        }
+    }
+
+    // Vertex shaders shared with other pipelines need no fragment shader of their own
+    builders.retain(|_, builder| {
+        let vertex = builder.entry_points.get(&ShaderType::Vertex);
+        let fragment = builder.entry_points.get(&ShaderType::Fragment);
+        match (vertex, fragment) {
+            (Some(vertex), None) => !shared_vertices.contains_key(vertex),
+            _ => true,
+        }
+    });
+
+    // The patch size needs to match what the tessellation control shader outputs
+    for (name, (vertices, span)) in &output_vertices {
+        if let Some(builder) = builders.get(name) {
+            if let Some(patch) = builder.state.patch_control_points {
+                if patch != *vertices {
+                    return Err(ShaderError::InvalidAttribute {
+                        message: format!(
+                            "Patch size {} of pipeline {} does not match the {} output vertices of its tessellation control shader",
+                            patch, name, vertices
+                        ),
+                        span: *span,
+                    });
+                }
+            }
+        }
+    }
+
+    // Primitive restart only affects strips and fans, so it is likely a mistake on lists and patches
+    for (name, span) in &primitive_restarts {
+        if let Some(builder) = builders.get(name) {
+            let topology = builder.state.topology.as_deref();
+            let restartable = matches!(topology, Some(topology) if topology.contains("STRIP") || topology.contains("FAN"));
+            if !restartable {
+                return Err(ShaderError::InvalidAttribute {
+                    message: format!(
+                        "Primitive restart of pipeline {} has no effect on its {} topology, only on strips and fans",
+                        name,
+                        topology.unwrap_or("TRIANGLE_LIST").to_lowercase()
+                    ),
+                    span: *span,
+                });
+            }
+        }
+    }
+
+    // Graphics pipelines need a fragment shader
+    for (name, builder) in &builders {
+        let graphics = builder.entry_points.contains_key(&ShaderType::Vertex);
+        if graphics && !builder.entry_points.contains_key(&ShaderType::Fragment) {
+            return Err(ShaderError::MissingEntryPoint {
+                pipeline: name.clone(),
+                stage: ShaderType::Fragment,
+            });
+        }
+    }
+
+    // Fragment inputs take the locations of the vertex outputs in declaration order,
+    // so they need to match unless other stages sit in between
+    for (name, builder) in &builders {
+        let vertex = builder.entry_points.get(&ShaderType::Vertex);
+        let fragment = builder.entry_points.get(&ShaderType::Fragment);
+        if let (Some(vertex), Some(fragment)) = (vertex, fragment) {
+            if builder.stages.is_empty() {
+                check_stage_interface(name, &interfaces[vertex], &interfaces[fragment])?;
+            }
+        }
+    }
+
+    // Tessellation needs both stages, drawing patches of the control shader output vertices by default
+    for (name, builder) in &mut builders {
+        let control = builder.stages.contains(&ShaderType::TessellationControl);
+        let evaluation = builder.stages.contains(&ShaderType::TessellationEvaluation);
+        match (control, evaluation) {
+            (true, false) | (false, true) => {
+                return Err(ShaderError::MissingEntryPoint {
+                    pipeline: name.clone(),
+                    stage: if control {
+                        ShaderType::TessellationEvaluation
+                    } else {
+                        ShaderType::TessellationControl
+                    },
+                });
+            }
+            (true, true) if builder.state.patch_control_points.is_none() => {
+                let (vertices, span) = output_vertices.get(name).ok_or_else(|| {
+                    ShaderError::InvalidAttribute {
+                        message: format!(
+                            "Missing patch size of pipeline {}: set #[pipewriter(patch = N)] or the output_vertices of its tessellation control shader",
+                            name
+                        ),
+                        span: proc_macro2::Span::call_site(),
+                    }
+                })?;
+                if builder.state.topology.is_some() {
+                    return Err(ShaderError::InvalidAttribute {
+                        message: "A pipeline with tessellation shaders can only draw patches"
+                            .into(),
+                        span: *span,
+                    });
+                }
+                builder.state.patch_control_points = Some(*vertices);
+            }
+            _ => (),
+        }
+    }
+
+    Ok(builders
+        .into_values()
+        .map(|mut b| {
+            b.add_spec_constants(const_spec_constants.clone());
+            b.spec_constants.sort_by_key(|s| s.id);
+            b.build()
+        })
+        .collect())
+}
+
+/// Returns the vertex shader a fragment shader pairs with when it is not the one
+/// with the same prefix, such as `mesh_vs` for `#[pipewriter(vertex = "mesh_vs")]`
+fn get_shared_vertex(
+    func: &syn::ItemFn,
+) -> Result<Option<(String, proc_macro2::Span)>, ShaderError> {
+    let pipewriter = match get_pipewriter(&func.attrs) {
+        Some(pipewriter) => pipewriter,
+        None => return Ok(None),
+    };
+    for nested in &pipewriter.nested {
+        if let syn::NestedMeta::Meta(syn::Meta::NameValue(name_value)) = nested {
+            if name_value.path.is_ident("vertex") {
+                if get_shader_type(func) != Some(ShaderType::Fragment) {
+                    return Err(ShaderError::InvalidAttribute {
+                        message: "Only fragment shaders can pair with a vertex shader".into(),
+                        span: name_value.path.span(),
+                    });
+                }
+                let vertex =
+                    inner_value!(&name_value.lit, syn::Lit::Str(s) => s).ok_or_else(|| {
+                        ShaderError::InvalidAttribute {
+                            message: "Expected the name of a vertex shader, such as \"mesh_vs\""
+                                .into(),
+                            span: name_value.lit.span(),
+                        }
+                    })?;
+                return Ok(Some((vertex.value(), vertex.span())));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the name of the pipeline of a shader function, which is the camelcase
+/// prefix of the function name, such as `MainMethod` for `main_method_vs`
+fn get_pipeline_name(func: &syn::ItemFn) -> Result<String, ShaderError> {
+    let function = func.sig.ident.to_string();
+    let invalid = || ShaderError::InvalidEntryPointName {
+        function: function.clone(),
+        span: func.sig.ident.span(),
+    };
+
+    let prefix = get_prefix(&function)
+        .filter(|prefix| prefix.is_ascii())
+        .ok_or_else(invalid)?;
+    let name = prefix.to_camelcase();
+
+    // The name ends up in generated types and enum variants, such as `PipelineMainMethod`
+    syn::parse_str::<syn::Ident>(&name).map_err(|_| invalid())?;
+    Ok(name)
+}
+
+/// Validates the type of a specialization constant, which can be a scalar or a bool
+fn get_spec_constant_type(ty: &syn::Type) -> Result<syn::Ident, ShaderError> {
+    let ident = inner_value!(ty, syn::Type::Path(p) => p)
+        .and_then(|p| p.path.get_ident())
+        .filter(|ident| ["u32", "i32", "f32", "bool"].iter().any(|t| *ident == t));
+    ident.cloned().ok_or_else(|| ShaderError::UnsupportedType {
+        what: "specialization constant",
+        ty: quote::quote!(#ty).to_string(),
+        span: ty.span(),
+    })
+}
+
+/// Collects constants declared as `#[spirv(constant_id = N)] const NAME: u32 = DEFAULT;`
+fn get_const_spec_constants(file: &syn::File) -> Result<Vec<SpecConstant>, ShaderError> {
+    let mut ret = vec![];
+
+    let items = get_items(&file.items);
+    let consts = items
+        .iter()
+        .filter_map(|i| inner_value!(i, syn::Item::Const(c) => c));
+    for item in consts {
+        if let Some(spirv) = get_spirv(&item.attrs) {
+            if let Some(id) = get_spirv_value(&spirv, "constant_id")? {
+                let expr = &item.expr;
+                ret.push(SpecConstant {
+                    name: syn::Ident::new(
+                        &item.ident.to_string().to_lowercase(),
+                        item.ident.span(),
+                    ),
+                    ty: get_spec_constant_type(&item.ty)?,
+                    id,
+                    default: quote::quote!(#expr),
+                });
+            }
+        }
+    }
+
+    Ok(ret)
+}
+
+/// Returns the `spec_constant(id = N, default = D)` list of a spirv attribute, if any
+fn get_spec_constant_list(spirv: &syn::MetaList) -> Option<&syn::MetaList> {
+    spirv.nested.iter().find_map(|nested| match nested {
+        syn::NestedMeta::Meta(syn::Meta::List(list)) if list.path.is_ident("spec_constant") => {
+            Some(list)
+        }
+        _ => None,
+    })
+}
+
+/// Collects the arguments of a function declared as `#[spirv(spec_constant(id = N, default = D))]`
+fn get_spec_constants(func: &syn::ItemFn) -> Result<Vec<SpecConstant>, ShaderError> {
+    let mut ret = vec![];
+
+    for arg in &func.sig.inputs {
+        if let syn::FnArg::Typed(arg) = arg {
+            if let Some(spirv) = get_spirv(&arg.attrs) {
+                if let Some(list) = get_spec_constant_list(&spirv) {
+                    let id = get_spirv_value(list, "id")?.ok_or_else(|| {
+                        ShaderError::InvalidAttribute {
+                            message: "Missing id of specialization constant".into(),
+                            span: list.span(),
+                        }
+                    })?;
+                    let default = get_spirv_value(list, "default")?.unwrap_or(0);
+                    let name = get_arg_name(arg).ok_or_else(|| ShaderError::InvalidAttribute {
+                        message: "Expected a name for specialization constant".into(),
+                        span: arg.span(),
+                    })?;
+                    ret.push(SpecConstant {
+                        name: syn::Ident::new(
+                            name.to_string().trim_start_matches('_'),
+                            name.span(),
+                        ),
+                        ty: get_spec_constant_type(&arg.ty)?,
+                        id,
+                        default: quote::quote!(#default),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(ret)
+}
+
+/// Returns the `output_vertices` of a `#[spirv(tessellation_control(output_vertices = N))]` function
+fn get_output_vertices(
+    func: &syn::ItemFn,
+) -> Result<Option<(u32, proc_macro2::Span)>, ShaderError> {
+    if let Some(spirv) = get_spirv(&func.attrs) {
+        for nested in &spirv.nested {
+            if let syn::NestedMeta::Meta(syn::Meta::List(list)) = nested {
+                if list.path.is_ident("tessellation_control") {
+                    if let Some(vertices) = get_spirv_value(list, "output_vertices")? {
+                        return Ok(Some((vertices, list.span())));
+                    }
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Analyzes the attributes of a function, looking for a `MetaList` named `ident`
+fn get_meta_list(attrs: &[syn::Attribute], ident: &str) -> Option<syn::MetaList> {
+    attrs
+        .iter()
+        // which are metas
+        .filter_map(|attr| attr.parse_meta().ok())
+        // which are lists
+        .filter_map(|meta| inner_value!(meta, syn::Meta::List(l) => l))
+        // which idents match
+        .find(|list| list.path.get_ident().is_some() && list.path.get_ident().unwrap() == ident)
+}
+
+/// Analyzes the attributes of a function, looking for a spirv `MetaList`
+fn get_spirv(attrs: &[syn::Attribute]) -> Option<syn::MetaList> {
+    get_meta_list(attrs, "spirv")
+}
+
+/// Analyzes the attributes of a function, looking for a pipewriter `MetaList`
+fn get_pipewriter(attrs: &[syn::Attribute]) -> Option<syn::MetaList> {
+    get_meta_list(attrs, "pipewriter")
+}
+
+/// Primitive topologies accepted by `#[pipewriter(topology = "..")]`,
+/// while patches are drawn by setting a `patch` size instead
+const TOPOLOGIES: &[&str] = &[
+    "point_list",
+    "line_list",
+    "line_strip",
+    "triangle_list",
+    "triangle_strip",
+    "triangle_fan",
+    "line_list_with_adjacency",
+    "line_strip_with_adjacency",
+    "triangle_list_with_adjacency",
+    "triangle_strip_with_adjacency",
+];
+
+/// Cull modes accepted by `#[pipewriter(cull_mode = "..")]`
+const CULL_MODES: &[&str] = &["none", "front", "back", "front_and_back"];
+
+/// Polygon modes accepted by `#[pipewriter(polygon_mode = "..")]`
+const POLYGON_MODES: &[&str] = &["fill", "line", "point"];
+
+/// Blend presets accepted by `#[pipewriter(blend = "..")]`
+const BLEND_MODES: &[&str] = &["opaque", "alpha", "additive"];
+
+/// Depth modes accepted by `#[pipewriter(depth = "..")]`
+const DEPTH_MODES: &[&str] = &["on", "off"];
+
+/// Compare ops accepted by `#[pipewriter(depth_compare = "..")]`
+const COMPARE_OPS: &[&str] = &[
+    "never",
+    "less",
+    "equal",
+    "less_or_equal",
+    "greater",
+    "not_equal",
+    "greater_or_equal",
+    "always",
+];
+
+/// Stencil ops accepted by the `fail`, `pass`, and `depth_fail` of `#[pipewriter(stencil(..))]`
+const STENCIL_OPS: &[&str] = &[
+    "keep",
+    "zero",
+    "replace",
+    "increment_and_clamp",
+    "decrement_and_clamp",
+    "invert",
+    "increment_and_wrap",
+    "decrement_and_wrap",
+];
+
+/// Front faces accepted by `#[pipewriter(front_face = "..")]`
+const FRONT_FACES: &[&str] = &["counter_clockwise", "clockwise"];
+
+/// Returns the uppercase Vulkan name of a string attribute value, which must be one of `values`
+fn get_enum_value(
+    name_value: &syn::MetaNameValue,
+    what: &str,
+    values: &[&str],
+) -> Result<String, ShaderError> {
+    inner_value!(&name_value.lit, syn::Lit::Str(s) => s.value())
+        .filter(|value| values.contains(&value.as_str()))
+        .map(|value| value.to_uppercase())
+        .ok_or_else(|| ShaderError::InvalidAttribute {
+            message: format!("Expected one of the {} {}", what, values.join(", ")),
+            span: name_value.lit.span(),
+        })
+}
+
+/// Updates the stencil operations of a face according to a `stencil(..)` `MetaList`,
+/// leaving the operations it does not mention untouched
+fn parse_stencil_ops(list: &syn::MetaList, ops: &mut StencilOps) -> Result<(), ShaderError> {
+    for nested in &list.nested {
+        let name_value = match nested {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(name_value)) => name_value,
+            nested => {
+                return Err(ShaderError::InvalidAttribute {
+                    message: "Expected a stencil operation, such as pass = \"replace\"".into(),
+                    span: nested.span(),
+                })
+            }
+        };
+        let ident = name_value.path.get_ident().map(|ident| ident.to_string());
+        match ident.as_deref() {
+            Some("fail") => ops.fail = get_enum_value(name_value, "stencil ops", STENCIL_OPS)?,
+            Some("pass") => ops.pass = get_enum_value(name_value, "stencil ops", STENCIL_OPS)?,
+            Some("depth_fail") => {
+                ops.depth_fail = get_enum_value(name_value, "stencil ops", STENCIL_OPS)?
+            }
+            Some("compare") => ops.compare = get_enum_value(name_value, "compare ops", COMPARE_OPS)?,
+            Some(id @ ("compare_mask" | "write_mask" | "reference")) => {
+                let value = get_spirv_value(list, id)?.unwrap();
+                match id {
+                    "compare_mask" => ops.compare_mask = value,
+                    "write_mask" => ops.write_mask = value,
+                    _ => ops.reference = value,
+                }
+            }
+            _ => {
+                return Err(ShaderError::InvalidAttribute {
+                    message: "Expected one of the stencil operations fail, pass, depth_fail, compare, compare_mask, write_mask, reference".into(),
+                    span: name_value.path.span(),
+                })
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Keys of pipewriter attributes of shader functions which are not part of the pipeline state,
+/// such as the shared vertex shader of a fragment shader
+const FUNCTION_KEYS: &[&str] = &["vertex"];
+
+/// Keys of pipewriter attributes of shader arguments, such as the format of a vertex input
+/// or the count of a runtime array of descriptors
+const ARGUMENT_KEYS: &[&str] = &["format", "attribute", "binding", "count"];
+
+/// Checks that the keys of the name-value pairs of a pipewriter attribute are all in `keys`
+fn check_keys(pipewriter: &syn::MetaList, keys: &[&str]) -> Result<(), ShaderError> {
+    for nested in &pipewriter.nested {
+        if let syn::NestedMeta::Meta(syn::Meta::NameValue(name_value)) = nested {
+            if !keys.iter().any(|key| name_value.path.is_ident(key)) {
+                return Err(unknown_attribute(&name_value.path));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the error of a pipewriter attribute named `path` which pipewriter does not know
+fn unknown_attribute(path: &syn::Path) -> ShaderError {
+    ShaderError::InvalidAttribute {
+        message: format!("Unknown pipewriter attribute: {}", quote::quote!(#path)),
+        span: path.span(),
+    }
+}
+
+/// Updates the pipeline `state` according to a pipewriter `MetaList`
+fn parse_pipeline_state(
+    pipewriter: &syn::MetaList,
+    state: &mut PipelineState,
+) -> Result<(), ShaderError> {
+    for nested in &pipewriter.nested {
+        if let syn::NestedMeta::Meta(syn::Meta::NameValue(name_value)) = nested {
+            if name_value.path.is_ident("vertex_layout") {
+                let layout =
+                    inner_value!(&name_value.lit, syn::Lit::Str(s) => s).ok_or_else(|| {
+                        ShaderError::InvalidAttribute {
+                            message:
+                                "Expected the name of a vertex layout, such as \"StandardVertex\""
+                                    .into(),
+                            span: name_value.lit.span(),
+                        }
+                    })?;
+                state.vertex_layout = Some(layout.value());
+            } else if name_value.path.is_ident("reverse_z") {
+                let reverse_z = inner_value!(&name_value.lit, syn::Lit::Bool(b) => b.value)
+                    .ok_or_else(|| ShaderError::InvalidAttribute {
+                        message: "Expected true or false for reverse_z".into(),
+                        span: name_value.lit.span(),
+                    })?;
+                state.reverse_z = Some(reverse_z);
+            } else if name_value.path.is_ident("views") {
+                let views = get_spirv_value(pipewriter, "views")?;
+                // The view mask of a subpass has 32 bits
+                state.views = views
+                    .filter(|views| (1..=32).contains(views))
+                    .ok_or_else(|| ShaderError::InvalidAttribute {
+                        message: "Expected a view count between 1 and 32".into(),
+                        span: name_value.lit.span(),
+                    })?;
+            } else if name_value.path.is_ident("color_attachments") {
+                let color_attachments = get_spirv_value(pipewriter, "color_attachments")?;
+                // Every device supports at least 4 color attachments, while most support 8
+                let color_attachments =
+                    color_attachments
+                        .filter(|count| *count <= 8)
+                        .ok_or_else(|| ShaderError::InvalidAttribute {
+                            message: "Expected a color attachment count between 0 and 8".into(),
+                            span: name_value.lit.span(),
+                        })?;
+                state.color_attachments = Some(color_attachments as usize);
+            } else if name_value.path.is_ident("samples") {
+                let samples = get_spirv_value(pipewriter, "samples")?;
+                // Sample counts are powers of two, as in `vk::SampleCountFlags`
+                state.samples = samples
+                    .filter(|samples| samples.is_power_of_two() && *samples <= 64)
+                    .ok_or_else(|| ShaderError::InvalidAttribute {
+                        message: "Expected a sample count of 1, 2, 4, 8, 16, 32, or 64".into(),
+                        span: name_value.lit.span(),
+                    })?;
+            } else if name_value.path.is_ident("subpass") {
+                state.subpass = get_spirv_value(pipewriter, "subpass")?.unwrap();
+            } else if name_value.path.is_ident("topology") {
+                let topology = get_enum_value(name_value, "topologies", TOPOLOGIES)?;
+                if state.patch_control_points.is_some() {
+                    return Err(ShaderError::InvalidAttribute {
+                        message: "A pipeline with a patch size can only draw patches".into(),
+                        span: name_value.lit.span(),
+                    });
+                }
+                state.topology = Some(topology);
+            } else if name_value.path.is_ident("cull_mode") {
+                state.cull_mode = Some(get_enum_value(name_value, "cull modes", CULL_MODES)?);
+            } else if name_value.path.is_ident("polygon_mode") {
+                state.polygon_mode =
+                    Some(get_enum_value(name_value, "polygon modes", POLYGON_MODES)?);
+            } else if name_value.path.is_ident("blend") {
+                state.blend = Some(get_enum_value(name_value, "blend modes", BLEND_MODES)?);
+            } else if name_value.path.is_ident("depth") {
+                let depth = get_enum_value(name_value, "depth modes", DEPTH_MODES)? == "ON";
+                state.depth_test = depth;
+                state.depth_write = depth;
+            } else if name_value.path.is_ident("depth_compare") {
+                state.depth_compare = Some(get_enum_value(name_value, "compare ops", COMPARE_OPS)?);
+            } else if name_value.path.is_ident("front_face") {
+                state.front_face = Some(get_enum_value(name_value, "front faces", FRONT_FACES)?);
+            } else if name_value.path.is_ident("patch") {
+                if state.topology.is_some() {
+                    return Err(ShaderError::InvalidAttribute {
+                        message: "A pipeline with a topology can not draw patches".into(),
+                        span: name_value.lit.span(),
+                    });
+                }
+                let patch = get_spirv_value(pipewriter, "patch")?;
+                // Every device supports patches of at least 32 control points
+                if !matches!(patch, Some(1..=32)) {
+                    return Err(ShaderError::InvalidAttribute {
+                        message: "Expected a patch size between 1 and 32".into(),
+                        span: name_value.lit.span(),
+                    });
+                }
+                state.patch_control_points = patch;
+            } else if !FUNCTION_KEYS
+                .iter()
+                .any(|key| name_value.path.is_ident(key))
+            {
+                return Err(unknown_attribute(&name_value.path));
+            }
+        }
+        if let syn::NestedMeta::Meta(syn::Meta::List(list)) = nested {
+            // `stencil(..)` applies to both faces, which may be overridden one by one
+            let stencil = state.stencil.get_or_insert_with(StencilState::default);
+            if list.path.is_ident("stencil") {
+                parse_stencil_ops(list, &mut stencil.front)?;
+                parse_stencil_ops(list, &mut stencil.back)?;
+            } else if list.path.is_ident("stencil_front") {
+                parse_stencil_ops(list, &mut stencil.front)?;
+            } else if list.path.is_ident("stencil_back") {
+                parse_stencil_ops(list, &mut stencil.back)?;
+            } else {
+                return Err(ShaderError::InvalidAttribute {
+                    message: "Unknown pipewriter attribute list, expected stencil, stencil_front, or stencil_back".into(),
+                    span: list.path.span(),
+                });
+            }
+        }
+        if let syn::NestedMeta::Meta(syn::Meta::Path(path)) = nested {
+            if let Some(ident) = path.get_ident() {
+                match ident.to_string().as_str() {
+                    "no_depth" => {
+                        state.depth_test = false;
+                        state.depth_write = false;
+                    }
+                    "depth_clamp" => state.depth_clamp = true,
+                    "depth_bias" => state.depth_bias = true,
+                    "primitive_restart" => state.primitive_restart = true,
+                    "line_width" => state.dynamic_line_width = true,
+                    "sample_shading" => state.sample_shading = true,
+                    "alpha_to_coverage" => state.alpha_to_coverage = true,
+                    unknown => {
+                        return Err(ShaderError::InvalidAttribute {
+                            message: format!("Unknown pipewriter attribute: {}", unknown),
+                            span: ident.span(),
+                        })
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[allow(unused)]
+fn dump_meta(list: &syn::MetaList) {
+    for nested in &list.nested {
+        if let syn::NestedMeta::Meta(meta) = nested {
+            if let syn::Meta::Path(path) = meta {
+                if let Some(id) = path.get_ident() {
+                    eprintln!("path: {}", id);
+                }
+            }
+            if let syn::Meta::NameValue(name_value) = meta {
+                if let Some(id) = name_value.path.get_ident() {
+                    eprintln!("path: {}", id);
+                }
+            }
+        }
+    }
+}
+
+fn get_meta_name_value<'m>(list: &'m syn::MetaList, ident: &str) -> Option<&'m syn::MetaNameValue> {
+    for nested in &list.nested {
+        if let syn::NestedMeta::Meta(syn::Meta::NameValue(name_value)) = nested {
+            if let Some(id) = name_value.path.get_ident() {
+                if id == ident {
+                    return Some(name_value);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Returns whether a `MetaList` contains a `Path` named `ident`, such as `push_constant`
+fn has_meta_path(list: &syn::MetaList, ident: &str) -> bool {
+    list.nested.iter().any(|nested| {
+        matches!(nested, syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident(ident))
+    })
+}
+
+/// Analyzes a function attributes, looking for vertex and fragment `Path`s
+/// and returns the corresponding shader type
+fn get_shader_type(func: &syn::ItemFn) -> Option<ShaderType> {
+    if let Some(spirv) = get_spirv(&func.attrs) {
+        for nested in &spirv.nested {
+            if let syn::NestedMeta::Meta(meta) = nested {
+                if let syn::Meta::Path(path) = meta {
+                    if let Some(ident) = path.get_ident() {
+                        if ident == "vertex" {
+                            return Some(ShaderType::Vertex);
+                        } else if ident == "fragment" {
+                            return Some(ShaderType::Fragment);
+                        } else if ident == "geometry" {
+                            return Some(ShaderType::Geometry);
+                        } else if ident == "tessellation_control" {
+                            return Some(ShaderType::TessellationControl);
+                        } else if ident == "tessellation_evaluation" {
+                            return Some(ShaderType::TessellationEvaluation);
+                        }
+                    }
+                }
+                // Compute shaders come with their threads, as in `compute(threads(64))`
+                if let syn::Meta::List(list) = meta {
+                    if list.path.is_ident("compute") {
+                        return Some(ShaderType::Compute);
+                    } else if list.path.is_ident("geometry") {
+                        return Some(ShaderType::Geometry);
+                    } else if list.path.is_ident("tessellation_control") {
+                        return Some(ShaderType::TessellationControl);
+                    } else if list.path.is_ident("tessellation_evaluation") {
+                        return Some(ShaderType::TessellationEvaluation);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Returns the workgroup size of a `#[spirv(compute(threads(x, y, z)))]` function,
+/// where the `y` and `z` dimensions default to 1
+fn get_workgroup_size(func: &syn::ItemFn) -> Result<[u32; 3], ShaderError> {
+    let compute = get_spirv(&func.attrs)
+        .into_iter()
+        .flat_map(|spirv| spirv.nested.into_iter())
+        .filter_map(|nested| inner_value!(nested, syn::NestedMeta::Meta(syn::Meta::List(l)) => l))
+        .find(|list| list.path.is_ident("compute"))
+        .expect("Can not get the workgroup size of this function");
+
+    let threads = compute
+        .nested
+        .iter()
+        .filter_map(|nested| inner_value!(nested, syn::NestedMeta::Meta(syn::Meta::List(l)) => l))
+        .find(|list| list.path.is_ident("threads"));
+    let invalid = |span| ShaderError::InvalidAttribute {
+        message: "Expected between 1 and 3 thread counts, as in compute(threads(8, 8))".into(),
+        span,
+    };
+    let threads = threads.ok_or_else(|| invalid(compute.span()))?;
+
+    let mut size = [1; 3];
+    if threads.nested.is_empty() || threads.nested.len() > size.len() {
+        return Err(invalid(threads.span()));
+    }
+    for (dimension, nested) in size.iter_mut().zip(&threads.nested) {
+        *dimension = inner_value!(nested, syn::NestedMeta::Lit(syn::Lit::Int(i)) => i)
+            .and_then(|i| i.base10_parse::<u32>().ok())
+            .filter(|count| *count > 0)
+            .ok_or_else(|| invalid(nested.span()))?;
+    }
+
+    Ok(size)
+}
+
+fn get_arg_name(arg: &syn::PatType) -> Option<syn::Ident> {
+    match &*arg.pat {
+        syn::Pat::Ident(i) => Some(i.ident.clone()),
+        _ => None,
+    }
+}
+
+fn get_arg_type(arg: &syn::PatType) -> Option<syn::Ident> {
+    match &*arg.ty {
+        syn::Type::Path(p) => {
+            if !p.path.segments.is_empty() {
+                return Some(p.path.segments[0].ident.clone());
+            }
+        }
+        syn::Type::Reference(r) => match &*r.elem {
+            syn::Type::Path(p) => {
+                if !p.path.segments.is_empty() {
+                    return Some(p.path.segments[0].ident.clone());
+                }
+            }
+            syn::Type::Array(a) => {
+                if let syn::Type::Path(p) = &*a.elem {
+                    return p.path.segments.last().map(|seg| seg.ident.clone());
+                }
+            }
+            syn::Type::Macro(m) => {
+                if let Some(ident) = m.mac.path.get_ident() {
+                    return Some(ident.clone());
+                }
+            }
+            syn::Type::BareFn(_) => eprintln!("BF"),
+            syn::Type::Group(_) => eprintln!("G"),
+            syn::Type::ImplTrait(_) => eprintln!("IT"),
+            syn::Type::Infer(_) => eprintln!("In"),
+            syn::Type::Never(_) => eprintln!("Nev"),
+            syn::Type::Paren(_) => eprintln!("Pare"),
+            syn::Type::Ptr(_) => eprintln!("Ptr"),
+            syn::Type::Reference(_) => eprintln!("Ref"),
+            syn::Type::Slice(_) => eprintln!("Slic"),
+            syn::Type::TraitObject(_) => eprintln!("Trai"),
+            syn::Type::Tuple(_) => eprintln!("Tup"),
+            syn::Type::Verbatim(_) => eprintln!("Verb"),
+            syn::Type::__TestExhaustive(_) => eprintln!("Test"),
+        },
+        _ => eprintln!("Unhandled Type"),
+    }
+    None
+}
+
+/// Collects the vertex inputs of a function, with their optional format override.
+/// Builtins such as `#[spirv(vertex_index)]` are skipped, while interpolation qualifiers
+/// such as `#[spirv(flat)]` leave their argument a vertex input.
+fn get_vertex_inputs(func: &syn::ItemFn) -> Result<Vec<VertexInput>, ShaderError> {
+    let mut ret = vec![];
+
+    for arg in &func.sig.inputs {
+        match arg {
+            syn::FnArg::Typed(t) if !is_builtin(&t.attrs) => match &*t.ty {
+                syn::Type::Path(p) => {
+                    let spec_constant = matches!(
+                        get_spirv(&t.attrs),
+                        Some(spirv) if get_spec_constant_list(&spirv).is_some()
+                    );
+                    // Types defined in other crates are resolved through their last segment
+                    if let (Some(seg), false) = (p.path.segments.last(), spec_constant) {
+                        let (format, attribute, instance, binding) = match get_pipewriter(&t.attrs)
+                        {
+                            Some(pipewriter) => (
+                                get_vertex_format(&pipewriter)?,
+                                get_spirv_value(&pipewriter, "attribute")?,
+                                has_meta_path(&pipewriter, "instance"),
+                                get_vertex_binding(&pipewriter)?,
+                            ),
+                            None => (None, None, false, None),
+                        };
+                        ret.push(VertexInput {
+                            ty: seg.ident.clone(),
+                            format,
+                            attribute,
+                            instance,
+                            binding,
+                        });
+                    }
+                }
+                syn::Type::Reference(_) => {
+                    // TODO: look for mutable output values
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+
+    Ok(ret)
+}
+
+/// Returns whether a struct field is padding, either named with a leading underscore
+/// or marked with `#[pipewriter(pad)]`
+fn is_padding(field: &syn::Field) -> bool {
+    let underscore = matches!(&field.ident, Some(ident) if ident.to_string().starts_with('_'));
+    let is_pad = |nested: &syn::NestedMeta| matches!(nested, syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("pad"));
+    let pad = matches!(
+        get_pipewriter(&field.attrs),
+        Some(pipewriter) if pipewriter.nested.iter().any(is_pad)
+    );
+    underscore || pad
+}
+
+/// Returns the vertex formats of a struct field, or `None` if its type is not supported.
+/// A `#[pipewriter(format = "..")]` field takes that format whatever its type,
+/// such as a `u32` holding a packed normal or an `[u16; 2]` holding normalized UVs.
+fn get_field_formats(
+    field: &syn::Field,
+    vertex_formats: &HashMap<String, Vec<String>>,
+) -> Option<Vec<String>> {
+    let format = match get_pipewriter(&field.attrs) {
+        Some(pipewriter) => get_vertex_format(&pipewriter).ok()?,
+        None => None,
+    };
+    if let Some(format) = format {
+        let format = format.value();
+        let size = gen::get_format_size(&format);
+        return match size {
+            0 => None,
+            _ if is_padding(field) => Some(vec![gen::get_padding_format(size)]),
+            _ => Some(vec![format]),
+        };
+    }
+
+    let ty = &inner_value!(&field.ty, syn::Type::Path(p) => p)?
+        .path
+        .segments
+        .last()?
+        .ident;
+
+    let formats = gen::get_formats(ty, vertex_formats).ok()?;
+    if is_padding(field) {
+        let size = formats
+            .iter()
+            .map(|format| gen::get_format_size(format))
+            .sum();
+        Some(vec![gen::get_padding_format(size)])
+    } else {
+        Some(formats)
+    }
+}
+
+/// Returns the vertex formats of the `#[repr(C)]` structs of a shader file, so they can be used
+/// as vertex inputs or vertex layouts. Padding fields take no attribute location, but still
+/// advance the offset of the following fields. Structs with unsupported field types are skipped.
+fn get_vertex_structs(
+    file: &syn::File,
+    vertex_formats: &HashMap<String, Vec<String>>,
+) -> HashMap<String, Vec<String>> {
+    let mut ret = HashMap::new();
+
+    for item in get_items(&file.items) {
+        if let syn::Item::Struct(item) = item {
+            let is_c = |nested: &syn::NestedMeta| matches!(nested, syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("C"));
+            let repr_c = matches!(
+                get_meta_list(&item.attrs, "repr"),
+                Some(repr) if repr.nested.iter().any(is_c)
+            );
+            if !repr_c {
+                continue;
+            }
+
+            let formats = item
+                .fields
+                .iter()
+                .map(|field| get_field_formats(field, vertex_formats))
+                .collect::<Option<Vec<_>>>();
+            if let Some(formats) = formats {
+                ret.insert(item.ident.to_string(), formats.concat());
+            }
+        }
+    }
+
+    ret
+}
+
+/// Interpolation decorations of stage inputs and outputs, the only `spirv` paths which are not builtins
+const INTERPOLATIONS: &[&str] = &["flat", "noperspective", "centroid", "sample", "invariant"];
+
+/// Whether the `spirv` attribute of an argument makes it a builtin or a resource, such as
+/// `#[spirv(position)]` or `#[spirv(storage_buffer, ..)]`, rather than an input or output of its stage
+fn is_builtin(attrs: &[syn::Attribute]) -> bool {
+    let is_builtin_path = |nested: &syn::NestedMeta| match nested {
+        syn::NestedMeta::Meta(syn::Meta::Path(path)) => !INTERPOLATIONS
+            .iter()
+            .any(|interpolation| path.is_ident(interpolation)),
+        _ => false,
+    };
+    matches!(get_spirv(attrs), Some(spirv) if spirv.nested.iter().any(is_builtin_path))
+}
+
+/// Returns the outputs of a vertex function, or the inputs of a fragment function, with the names
+/// of their types. rust-gpu assigns their locations in the order of the arguments.
+fn get_stage_interface(func: &syn::ItemFn, outputs: bool) -> Vec<(syn::Ident, String)> {
+    let mut ret = vec![];
+
+    for arg in &func.sig.inputs {
+        let arg = match arg {
+            syn::FnArg::Typed(t) if !is_builtin(&t.attrs) => t,
+            _ => continue,
+        };
+        if matches!(get_spirv(&arg.attrs), Some(spirv) if get_spec_constant_list(&spirv).is_some())
+        {
+            continue;
+        }
+
+        let ty = match &*arg.ty {
+            syn::Type::Reference(r) if outputs && r.mutability.is_some() => &*r.elem,
+            syn::Type::Reference(_) => continue,
+            _ if outputs => continue,
+            ty => ty,
+        };
+        let ty_name = get_type_ident(ty)
+            .map(|ident| ident.to_string())
+            .unwrap_or_else(|| quote::quote!(#ty).to_string());
+        if let Some(name) = get_arg_name(arg) {
+            ret.push((name, ty_name));
+        }
+    }
+
+    ret
+}
+
+/// Checks each input of a fragment shader against the vertex output at the same location
+fn check_stage_interface(
+    pipeline: &str,
+    outputs: &[(syn::Ident, String)],
+    inputs: &[(syn::Ident, String)],
+) -> Result<(), ShaderError> {
+    for (i, (input, input_ty)) in inputs.iter().enumerate() {
+        let message = match outputs.get(i) {
+            Some((_, output_ty)) if output_ty == input_ty => continue,
+            Some((output, output_ty)) => format!(
+                "Fragment input {}: {} of pipeline {} does not match vertex output {}: {}",
+                input, input_ty, pipeline, output, output_ty
+            ),
+            None => format!(
+                "Fragment input {} of pipeline {} has no matching vertex output, as the vertex shader writes {} outputs",
+                input,
+                pipeline,
+                outputs.len()
+            ),
+        };
+        return Err(ShaderError::InvalidAttribute {
+            message,
+            span: input.span(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Counts the color outputs of a fragment function, which are its mutable reference
+/// arguments that are not builtins, plus its return value if any
+fn get_color_outputs(func: &syn::ItemFn) -> usize {
+    let arg_outputs = func
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|arg| inner_value!(arg, syn::FnArg::Typed(t) => t))
+        .filter(|t| matches!(&*t.ty, syn::Type::Reference(r) if r.mutability.is_some()))
+        .filter(|t| !is_builtin(&t.attrs))
+        .count();
+
+    let return_output = match &func.sig.output {
+        syn::ReturnType::Type(_, ty) => {
+            !matches!(&**ty, syn::Type::Tuple(tuple) if tuple.elems.is_empty())
+        }
+        syn::ReturnType::Default => false,
+    };
+
+    arg_outputs + return_output as usize
+}
+
+/// Numeric types of the Vulkan formats, following their components as in `R16G16_UNORM`
+const NUMERIC_TYPES: &[&str] = &[
+    "UNORM", "SNORM", "USCALED", "SSCALED", "UINT", "SINT", "UFLOAT", "SFLOAT", "SRGB",
+];
+
+/// Looks for a `format = "..."` Vulkan format override in a pipewriter `MetaList`
+fn get_vertex_format(pipewriter: &syn::MetaList) -> Result<Option<syn::LitStr>, ShaderError> {
+    if let Some(name_value) = get_meta_name_value(pipewriter, "format") {
+        let format = inner_value!(&name_value.lit, syn::Lit::Str(s) => s)
+            .filter(|s| {
+                let value = s.value();
+                let numeric_type = value.split('_').nth(1).unwrap_or_default();
+                value
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+                    && NUMERIC_TYPES.contains(&numeric_type)
+            })
+            .ok_or_else(|| ShaderError::InvalidAttribute {
+                message: "Expected a Vulkan format name, such as \"B8G8R8A8_UNORM\"".into(),
+                span: name_value.lit.span(),
+            })?;
+        Ok(Some(format.clone()))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Returns the vertex buffer binding of a vertex input, if set with `#[pipewriter(binding = N)]`
+fn get_vertex_binding(pipewriter: &syn::MetaList) -> Result<Option<u32>, ShaderError> {
+    let binding = get_spirv_value(pipewriter, "binding")?;
+    match (binding, get_meta_name_value(pipewriter, "binding")) {
+        // Every device supports at least 16 vertex bindings
+        (Some(binding), Some(name_value)) if binding >= 16 => Err(ShaderError::InvalidAttribute {
+            message: "Expected a vertex binding between 0 and 15".into(),
+            span: name_value.lit.span(),
+        }),
+        _ => Ok(binding),
+    }
+}
+
+fn get_spirv_value(spirv: &syn::MetaList, id: &str) -> Result<Option<u32>, ShaderError> {
+    if let Some(name_value) = get_meta_name_value(spirv, id) {
+        let value = inner_value!(&name_value.lit, syn::Lit::Int(i) => i)
+            .and_then(|i| i.base10_parse::<u32>().ok())
+            .ok_or_else(|| ShaderError::InvalidAttribute {
+                message: format!("Expected an integer value for {}", id),
+                span: name_value.lit.span(),
+            })?;
+        Ok(Some(value))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Collects the `#[spirv(push_constant)]` arguments of a shader function
+fn get_push_constants(
+    func: &syn::ItemFn,
+    structs: &HashMap<String, usize>,
+) -> Result<Vec<PushConstant>, ShaderError> {
+    let mut push_constants = vec![];
+
+    let shader_type = get_shader_type(func).expect("Can not get push constants from this function");
+
+    for arg in &func.sig.inputs {
+        if let syn::FnArg::Typed(arg) = arg {
+            let push_constant = matches!(
+                get_spirv(&arg.attrs),
+                Some(spirv) if has_meta_path(&spirv, "push_constant")
+            );
+            if !push_constant {
+                continue;
+            }
+
+            let ty = match &*arg.ty {
+                syn::Type::Reference(r) => (*r.elem).clone(),
+                ty => ty.clone(),
+            };
+            let size =
+                get_std140_size(&ty, structs).ok_or_else(|| ShaderError::UnsupportedType {
+                    what: "push constant",
+                    ty: quote::quote!(#ty).to_string(),
+                    span: ty.span(),
+                })?;
+            let name = get_arg_name(arg).ok_or_else(|| {
+                let pat = &arg.pat;
+                ShaderError::UnsupportedType {
+                    what: "push constant argument pattern",
+                    ty: quote::quote!(#pat).to_string(),
+                    span: arg.pat.span(),
+                }
+            })?;
+
+            push_constants.push(PushConstant {
+                name,
+                size,
+                stages: vec![shader_type],
+            });
+        }
+    }
+
+    Ok(push_constants)
+}
+
+/// Attributes of uniforms accepted as `#[pipewriter(..)]` paths, with their `vk::DescriptorBindingFlags`
+const BINDING_FLAGS: &[(&str, &str)] = &[
+    ("partially_bound", "PARTIALLY_BOUND"),
+    ("update_after_bind", "UPDATE_AFTER_BIND"),
+];
+
+/// Returns the last identifier of a path or macro type, such as `Image2d` of `spirv_std::image::Image2d`
+fn get_type_ident(ty: &syn::Type) -> Option<syn::Ident> {
+    match ty {
+        syn::Type::Path(p) => p.path.segments.last().map(|seg| seg.ident.clone()),
+        syn::Type::Macro(m) => m.mac.path.get_ident().cloned(),
+        _ => None,
+    }
+}
+
+/// Whether a type is an image or a sampler, which can be bound as an array of descriptors
+fn is_image_or_sampler(ty: &syn::Type) -> bool {
+    let descriptor_type =
+        get_type_ident(ty).and_then(|ident| DescriptorType::from_ident(&ident, ty));
+    !matches!(descriptor_type, None | Some(DescriptorType::UniformBuffer))
+}
+
+/// Returns the element type and length of an array of image or sampler descriptors, such as
+/// `[Image2d; 4]`. The length of a `RuntimeArray` or a slice is `None`, to be set by a count
+/// attribute. Arrays of other types are the content of a single uniform buffer.
+fn get_descriptor_array(ty: &syn::Type) -> Option<(syn::Type, Option<u32>)> {
+    match ty {
+        syn::Type::Slice(slice) if is_image_or_sampler(&slice.elem) => {
+            Some(((*slice.elem).clone(), None))
+        }
+        syn::Type::Array(array) => {
+            if !is_image_or_sampler(&array.elem) {
+                return None;
+            }
+            let len = match &array.len {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Int(len),
+                    ..
+                }) => len.base10_parse::<u32>().ok()?,
+                _ => return None,
+            };
+            Some(((*array.elem).clone(), Some(len)))
+        }
+        syn::Type::Path(path) => {
+            let segment = path.path.segments.last()?;
+            if segment.ident != "RuntimeArray" {
+                return None;
+            }
+            match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) => match args.args.first()? {
+                    syn::GenericArgument::Type(elem) => Some((elem.clone(), None)),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn get_uniforms(
+    func: &syn::ItemFn,
+    structs: &HashMap<String, usize>,
+) -> Result<Vec<Uniform>, ShaderError> {
+    let mut uniforms = vec![];
+
+    let shader_type = get_shader_type(func).expect("Can not get uniforms from this function");
+
+    for arg in &func.sig.inputs {
+        if let syn::FnArg::Typed(arg) = arg {
+            let spirv = get_spirv(&arg.attrs);
+            if let Some(spirv) = spirv {
+                if let Some(desc_set) = get_spirv_value(&spirv, "descriptor_set")? {
+                    let ty = match &*arg.ty {
+                        syn::Type::Reference(r) => (*r.elem).clone(),
+                        ty => ty.clone(),
+                    };
+                    // Storage buffers may hold any type, runtime arrays included
+                    let storage_buffer = has_meta_path(&spirv, "storage_buffer");
+                    let array = if storage_buffer {
+                        None
+                    } else {
+                        get_descriptor_array(&ty)
+                    };
+                    let (ty, array_len) = match &array {
+                        Some((elem, len)) => (elem.clone(), *len),
+                        None => (ty, Some(1)),
+                    };
+
+                    let unsupported = |what| ShaderError::UnsupportedType {
+                        what,
+                        ty: quote::quote!(#ty).to_string(),
+                        span: ty.span(),
+                    };
+
+                    let name = get_arg_name(arg).ok_or_else(|| {
+                        let pat = &arg.pat;
+                        ShaderError::UnsupportedType {
+                            what: "uniform argument pattern",
+                            ty: quote::quote!(#pat).to_string(),
+                            span: arg.pat.span(),
+                        }
+                    })?;
+                    let binding = get_spirv_value(&spirv, "binding")?.ok_or_else(|| {
+                        ShaderError::InvalidAttribute {
+                            message: format!("Missing binding for uniform {}", name),
+                            span: name.span(),
+                        }
+                    })?;
+                    let descriptor_type = if storage_buffer {
+                        DescriptorType::StorageBuffer
+                    } else {
+                        let ident = match &array {
+                            Some(_) => get_type_ident(&ty),
+                            None => get_arg_type(arg),
+                        }
+                        .ok_or_else(|| unsupported("uniform"))?;
+                        match DescriptorType::from_ident(&ident, &ty) {
+                            Some(descriptor_type) => descriptor_type,
+                            // Structs of the shader crate back uniform buffers
+                            None if structs.contains_key(&ident.to_string()) => {
+                                DescriptorType::UniformBuffer
+                            }
+                            None => return Err(unsupported("uniform")),
+                        }
+                    };
+                    let range = if descriptor_type == DescriptorType::UniformBuffer {
+                        let size = get_std140_size(&ty, structs)
+                            .ok_or_else(|| unsupported("uniform buffer"))?;
+                        Some(size)
+                    } else {
+                        None
+                    };
+
+                    let dynamic = matches!(
+                        get_pipewriter(&arg.attrs),
+                        Some(pipewriter) if has_meta_path(&pipewriter, "dynamic")
+                    );
+                    let descriptor_type = if !dynamic {
+                        descriptor_type
+                    } else if descriptor_type == DescriptorType::UniformBuffer {
+                        DescriptorType::UniformBufferDynamic
+                    } else {
+                        return Err(ShaderError::InvalidAttribute {
+                            message: format!("Only uniform buffers can be dynamic, not {}", name),
+                            span: name.span(),
+                        });
+                    };
+
+                    // Runtime arrays of descriptors need the count of their binding
+                    let count = get_pipewriter(&arg.attrs)
+                        .map(|pipewriter| get_spirv_value(&pipewriter, "count"))
+                        .transpose()?
+                        .flatten();
+                    let count = match (array_len, count) {
+                        (None, Some(count)) if count > 0 => count,
+                        (None, _) => {
+                            return Err(ShaderError::InvalidAttribute {
+                                message: format!(
+                                    "Missing count for runtime array {}, set it with #[pipewriter(count = N)]",
+                                    name
+                                ),
+                                span: name.span(),
+                            })
+                        }
+                        (Some(len), None) => len,
+                        (Some(_), Some(_)) => {
+                            return Err(ShaderError::InvalidAttribute {
+                                message: format!("Only runtime arrays need a count, not {}", name),
+                                span: name.span(),
+                            })
+                        }
+                    };
+
+                    let binding_flags = get_pipewriter(&arg.attrs)
+                        .map(|pipewriter| {
+                            BINDING_FLAGS
+                                .iter()
+                                .filter(|(path, _)| has_meta_path(&pipewriter, path))
+                                .map(|(_, flag)| *flag)
+                                .collect()
+                        })
+                        .unwrap_or_default();
+
+                    let mut uniform = Uniform::new(
+                        name,
+                        ty,
+                        range,
+                        descriptor_type,
+                        desc_set,
+                        binding,
+                        shader_type,
+                    );
+                    uniform.count = count;
+                    uniform.binding_flags = binding_flags;
+                    uniforms.push(uniform)
+                }
+            }
+        }
+    }
+
+    Ok(uniforms)
+}
+
+#[test]
+fn no_depth() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn overlay_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(no_depth)]
+        #[spirv(fragment)]
+        pub fn overlay_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(!pipelines[0].state.depth_test);
+    assert!(!pipelines[0].state.depth_write);
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn overlay_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(depth = "off")]
+        #[spirv(fragment)]
+        pub fn overlay_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(!pipelines[0].state.depth_test);
+    assert!(!pipelines[0].state.depth_write);
+}
+
+#[test]
+fn shader_errors() {
+    let missing = CrateModule::new("does/not/exist".into());
+    assert!(matches!(missing, Err(ShaderError::MissingCrate(_))));
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn main_fs(#[spirv(uniform, descriptor_set = 0, binding = 0)] light: &Light) {}
+    };
+    let unsupported = get_pipelines(&file);
+    assert!(matches!(
+        unsupported,
+        Err(ShaderError::UnsupportedType { .. })
+    ));
+
+    let file = syn::parse_quote! {
+        #[spirv(fragment)]
+        pub fn lonely_fs(out_color: &mut Vec4) {}
+    };
+    let missing_vertex = get_pipelines(&file);
+    assert!(matches!(
+        missing_vertex,
+        Err(ShaderError::MissingEntryPoint { .. })
+    ));
+}
+
+#[test]
+fn patch_size() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn terrain_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(tessellation_control(output_vertices = 4))]
+        pub fn terrain_tcs() {}
+
+        #[spirv(tessellation_evaluation)]
+        pub fn terrain_tes() {}
+
+        #[pipewriter(patch = 4)]
+        #[spirv(fragment)]
+        pub fn terrain_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.patch_control_points == Some(4));
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn terrain_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(tessellation_control(output_vertices = 3))]
+        pub fn terrain_tcs() {}
+
+        #[spirv(tessellation_evaluation)]
+        pub fn terrain_tes() {}
+
+        #[pipewriter(patch = 4)]
+        #[spirv(fragment)]
+        pub fn terrain_fs(out_color: &mut Vec4) {}
+    };
+    let mismatch = get_pipelines(&file);
+    assert!(matches!(
+        mismatch,
+        Err(ShaderError::InvalidAttribute { .. })
+    ));
+}
+
+#[test]
+fn tessellation_stages() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn terrain_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(tessellation_control(output_vertices = 4))]
+        pub fn terrain_tcs() {}
+
+        #[spirv(tessellation_evaluation(spacing_equal, quads))]
+        pub fn terrain_tes(#[spirv(uniform, descriptor_set = 0, binding = 0)] height: &f32) {}
+
+        #[spirv(fragment)]
+        pub fn terrain_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(
+        pipelines[0].stages
+            == [
+                ShaderType::TessellationControl,
+                ShaderType::TessellationEvaluation
+            ]
+    );
+    assert!(pipelines[0].uniforms[0].stages == [ShaderType::TessellationEvaluation]);
+    // The patch size defaults to the output vertices of the control shader
+    assert!(pipelines[0].state.patch_control_points == Some(4));
+    assert!(pipelines[0].features() == ["tessellation_shader"]);
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn terrain_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(tessellation_control(output_vertices = 4))]
+        pub fn terrain_tcs() {}
+
+        #[spirv(fragment)]
+        pub fn terrain_fs(out_color: &mut Vec4) {}
+    };
+    let missing = get_pipelines(&file);
+    assert!(matches!(
+        missing,
+        Err(ShaderError::MissingEntryPoint {
+            stage: ShaderType::TessellationEvaluation,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn primitive_restart() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        #[pipewriter(primitive_restart)]
+        pub fn ribbon_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(topology = "triangle_strip")]
+        #[spirv(fragment)]
+        pub fn ribbon_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.primitive_restart);
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn ribbon_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(primitive_restart)]
+        #[spirv(fragment)]
+        pub fn ribbon_fs(out_color: &mut Vec4) {}
+    };
+    let list = get_pipelines(&file);
+    assert!(
+        matches!(list, Err(ShaderError::InvalidAttribute { message, .. }) if message.contains("triangle_list"))
+    );
+}
+
+#[test]
+fn topology() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn points_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(topology = "point_list")]
+        #[spirv(fragment)]
+        pub fn points_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.topology.as_deref() == Some("POINT_LIST"));
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn points_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(topology = "dots")]
+        #[spirv(fragment)]
+        pub fn points_fs(out_color: &mut Vec4) {}
+    };
+    let unknown = get_pipelines(&file);
+    assert!(matches!(unknown, Err(ShaderError::InvalidAttribute { .. })));
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn terrain_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(patch = 4, topology = "line_list")]
+        #[spirv(fragment)]
+        pub fn terrain_fs(out_color: &mut Vec4) {}
+    };
+    let conflict = get_pipelines(&file);
+    assert!(matches!(
+        conflict,
+        Err(ShaderError::InvalidAttribute { .. })
+    ));
+}
+
+#[test]
+fn culling() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn opaque_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(cull_mode = "back", front_face = "clockwise")]
+        #[spirv(fragment)]
+        pub fn opaque_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.cull_mode.as_deref() == Some("BACK"));
+    assert!(pipelines[0].state.front_face.as_deref() == Some("CLOCKWISE"));
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn opaque_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(cull_mode = "backwards")]
+        #[spirv(fragment)]
+        pub fn opaque_fs(out_color: &mut Vec4) {}
+    };
+    let unknown = get_pipelines(&file);
+    assert!(matches!(unknown, Err(ShaderError::InvalidAttribute { .. })));
+}
+
+#[test]
+fn depth_compare() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn decal_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(depth_compare = "less_or_equal")]
+        #[spirv(fragment)]
+        pub fn decal_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.depth_compare.as_deref() == Some("LESS_OR_EQUAL"));
+}
+
+#[test]
+fn stencil_ops() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn portal_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(
+            stencil(compare = "equal", reference = 2, compare_mask = 0x0f),
+            stencil_back(fail = "zero", depth_fail = "invert", write_mask = 0)
+        )]
+        #[spirv(fragment)]
+        pub fn portal_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    let stencil = pipelines[0].state.stencil.as_ref().unwrap();
+    assert!(stencil.front.compare == "EQUAL");
+    assert!(stencil.front.reference == 2);
+    assert!(stencil.front.compare_mask == 0x0f);
+    assert!(stencil.front.fail == "KEEP");
+    assert!(stencil.front.write_mask == 0xff);
+    assert!(stencil.back.compare == "EQUAL");
+    assert!(stencil.back.fail == "ZERO");
+    assert!(stencil.back.depth_fail == "INVERT");
+    assert!(stencil.back.write_mask == 0);
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn portal_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(stencil(pass = "swap"))]
+        #[spirv(fragment)]
+        pub fn portal_fs(out_color: &mut Vec4) {}
+    };
+    assert!(matches!(
+        get_pipelines(&file),
+        Err(ShaderError::InvalidAttribute { .. })
+    ));
+}
+
+#[test]
+fn compute_shader() {
+    let file = syn::parse_quote! {
+        #[spirv(compute(threads(8, 8)))]
+        pub fn blur_cs(
+            #[spirv(global_invocation_id)] id: UVec3,
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] params: &Vec4,
+        ) {
+        }
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].name == "Blur");
+    assert!(pipelines[0].workgroup_size == Some([8, 8, 1]));
+    assert!(pipelines[0].uniforms[0].stages == [ShaderType::Compute]);
+
+    let file = syn::parse_quote! {
+        #[spirv(compute(threads(8, 8, 8, 8)))]
+        pub fn blur_cs() {}
+    };
+    let invalid = get_pipelines(&file);
+    assert!(matches!(invalid, Err(ShaderError::InvalidAttribute { .. })));
+}
+
+#[test]
+fn geometry_shader() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn particle_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(geometry(input_points = 1, output_triangle_strip = 4))]
+        pub fn particle_gs(#[spirv(uniform, descriptor_set = 0, binding = 0)] size: &f32) {}
+
+        #[spirv(fragment)]
+        pub fn particle_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].stages == [ShaderType::Geometry]);
+    assert!(pipelines[0].uniforms[0].stages == [ShaderType::Geometry]);
+    assert!(pipelines[0].features() == ["geometry_shader"]);
+}
+
+#[test]
+fn push_constants() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(
+            in_pos: Vec3,
+            #[spirv(push_constant)] transform: &Mat4,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn main_fs(#[spirv(push_constant)] transform: &Mat4, out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    let push_constants = &pipelines[0].push_constants;
+    assert!(push_constants.len() == 1);
+    assert!(push_constants[0].name == "transform");
+    assert!(push_constants[0].size == 64);
+    assert!(push_constants[0].stages == [ShaderType::Vertex, ShaderType::Fragment]);
+    // Push constants are not vertex inputs nor uniforms
+    assert!(pipelines[0].inputs.len() == 1);
+    assert!(pipelines[0].uniforms.is_empty());
+}
+
+#[test]
+fn storage_buffer() {
+    let file = syn::parse_quote! {
+        #[spirv(compute(threads(64)))]
+        pub fn sum_cs(
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] params: &Vec4,
+            #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] values: &mut [f32],
+        ) {
+        }
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    let uniforms = &pipelines[0].uniforms;
+    assert!(uniforms[0].descriptor_type == DescriptorType::UniformBuffer);
+    assert!(uniforms[1].descriptor_type == DescriptorType::StorageBuffer);
+    assert!(uniforms[1].get_write_set_type().to_string() == "vkr_core :: Buffer");
+    assert!(uniforms[1]
+        .get_info()
+        .to_string()
+        .contains(". range (vk :: WHOLE_SIZE)"));
+}
+
+#[test]
+fn storage_image() {
+    let file = syn::parse_quote! {
+        #[spirv(compute(threads(8, 8)))]
+        pub fn blit_cs(
+            #[spirv(descriptor_set = 0, binding = 0)] output: &Image!(2D, format=rgba8, sampled=false),
+        ) {
+        }
+
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn main_fs(
+            #[spirv(descriptor_set = 0, binding = 0, input_attachment_index = 0)] color: &Image!(subpass, type=f32, sampled=false),
+            out_color: &mut Vec4,
+        ) {
+        }
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    let blit = pipelines.iter().find(|p| p.name == "Blit").unwrap();
+    assert!(blit.uniforms[0].descriptor_type == DescriptorType::StorageImage);
+    assert!(blit.uniforms[0]
+        .get_info()
+        .to_string()
+        .contains("vk :: ImageLayout :: GENERAL"));
+    let main = pipelines.iter().find(|p| p.name == "Main").unwrap();
+    assert!(main.uniforms[0].descriptor_type == DescriptorType::InputAttachment);
+}
+
+#[test]
+fn input_attachments() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn lighting_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(subpass = 1)]
+        #[spirv(fragment)]
+        pub fn lighting_fs(
+            #[spirv(descriptor_set = 0, binding = 0, input_attachment_index = 0)] albedo: &Image!(subpass, type=f32, sampled=false),
+            #[spirv(descriptor_set = 0, binding = 1, input_attachment_index = 1)] normal: &Image!(subpass, type=f32, sampled=false),
+            out_color: &mut Vec4,
+        ) {
+        }
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    let uniforms = &pipelines[0].uniforms;
+    assert!(uniforms[1].descriptor_type == DescriptorType::InputAttachment);
+    assert!(uniforms[1].get_write_set_type().to_string() == "vkr_core :: ImageView");
+
+    let info = uniforms[1].get_info().to_string();
+    assert!(info.contains(". image_view (normal . view)"));
+    assert!(!info.contains("sampler"));
+
+    let gen = gen::set_layout_bindings(uniforms, 0).to_string();
+    assert!(gen
+        .contains(". binding (1u32) . descriptor_type (vk :: DescriptorType :: INPUT_ATTACHMENT)"));
+}
+
+#[test]
+fn separate_sampler() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn atlas_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn atlas_fs(
+            #[spirv(descriptor_set = 0, binding = 0)] sampler: &Sampler,
+            #[spirv(descriptor_set = 0, binding = 1)] atlas: &Image!(2D, type=f32, sampled),
+            #[spirv(descriptor_set = 0, binding = 2)] detail: &Image2d,
+            out_color: &mut Vec4,
+        ) {
+        }
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    let uniforms = &pipelines[0].uniforms;
+    assert!(uniforms[0].descriptor_type == DescriptorType::Sampler);
+    assert!(uniforms[1].descriptor_type == DescriptorType::SampledImage);
+    assert!(uniforms[2].descriptor_type == DescriptorType::SampledImage);
+
+    let sampler_info = uniforms[0].get_info().to_string();
+    assert!(sampler_info.contains("sampler (sampler . sampler)"));
+    assert!(!sampler_info.contains("image_view"));
+    let image_info = uniforms[1].get_info().to_string();
+    assert!(image_info.contains("image_view (atlas . view)"));
+    assert!(!image_info.contains("sampler"));
+}
+
+#[test]
+fn shared_uniform_stages() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(
+            in_pos: Vec3,
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] view: &Mat4,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn main_fs(
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] view: &Mat4,
+            #[spirv(descriptor_set = 0, binding = 1)] albedo: &SampledImage<Image2d>,
+            out_color: &mut Vec4,
+        ) {
+        }
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    let uniforms = &pipelines[0].uniforms;
+    assert!(uniforms.len() == 2);
+    assert!(uniforms[0].stages == [ShaderType::Vertex, ShaderType::Fragment]);
+    assert!(uniforms[1].stages == [ShaderType::Fragment]);
+
+    let bindings = gen::set_layout_bindings(uniforms, 0).to_string();
+    assert!(bindings.contains(
+        ". binding (0u32) . descriptor_type (vk :: DescriptorType :: UNIFORM_BUFFER) . descriptor_count (1u32) . stage_flags (vk :: ShaderStageFlags :: VERTEX | vk :: ShaderStageFlags :: FRAGMENT)"
+    ));
+    assert!(bindings.contains(
+        ". binding (1u32) . descriptor_type (vk :: DescriptorType :: COMBINED_IMAGE_SAMPLER) . descriptor_count (1u32) . stage_flags (vk :: ShaderStageFlags :: FRAGMENT)"
+    ));
+
+    let writes = gen::write_set_methods(uniforms).to_string();
+    assert!(writes.contains(
+        "pub fn write_set_0 (& self , set : vk :: DescriptorSet , view : & vkr_core :: Buffer , albedo : & Texture)"
+    ));
+}
+
+#[test]
+fn conflicting_bindings() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(
+            in_pos: Vec3,
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] view: &Mat4,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn main_fs(
+            #[spirv(descriptor_set = 0, binding = 0)] albedo: &SampledImage<Image2d>,
+            out_color: &mut Vec4,
+        ) {
+        }
+    };
+    match get_pipelines(&file) {
+        Err(ShaderError::InvalidAttribute { message, .. }) => assert!(message.contains(
+            "Set 0 binding 0 of pipeline Main is 1 UNIFORM_BUFFER as view but 1 COMBINED_IMAGE_SAMPLER as albedo"
+        )),
+        _ => panic!("Expected a conflicting binding error"),
+    }
+}
+
+#[test]
+fn descriptor_arrays() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn scene_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn scene_fs(
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] weights: &[f32; 4],
+            #[spirv(descriptor_set = 0, binding = 1)] shadows: &[Image2d; 4],
+            #[pipewriter(count = 1024, partially_bound, update_after_bind)]
+            #[spirv(descriptor_set = 1, binding = 0)] textures: &RuntimeArray<Image2d>,
+            #[spirv(descriptor_set = 1, binding = 1)] materials: &[SampledImage<Image2d>; 64],
+            #[pipewriter(count = 16)]
+            #[spirv(descriptor_set = 1, binding = 2)] decals: &[SampledImage<Image2d>],
+            out_color: &mut Vec4,
+        ) {
+        }
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    let uniforms = &pipelines[0].uniforms;
+    // Arrays of plain types are still the content of a uniform buffer
+    assert!(uniforms[0].descriptor_type == DescriptorType::UniformBuffer);
+    assert!(uniforms[0].count == 1);
+    assert!(uniforms[1].descriptor_type == DescriptorType::SampledImage);
+    assert!(uniforms[1].count == 4);
+    assert!(uniforms[1].binding_flags.is_empty());
+    assert!(uniforms[2].descriptor_type == DescriptorType::SampledImage);
+    assert!(uniforms[2].count == 1024);
+    assert!(uniforms[2].binding_flags == ["PARTIALLY_BOUND", "UPDATE_AFTER_BIND"]);
+    assert!(uniforms[3].descriptor_type == DescriptorType::CombinedImageSampler);
+    assert!(uniforms[3].count == 64);
+    assert!(uniforms[4].descriptor_type == DescriptorType::CombinedImageSampler);
+    assert!(uniforms[4].count == 16);
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn scene_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn scene_fs(
+            #[spirv(descriptor_set = 1, binding = 0)] textures: &RuntimeArray<Image2d>,
+            out_color: &mut Vec4,
+        ) {
+        }
+    };
+    match get_pipelines(&file) {
+        Err(ShaderError::InvalidAttribute { message, .. }) => {
+            assert!(message.contains("Missing count for runtime array textures"))
+        }
+        _ => panic!("Expected a missing count error"),
+    }
+}
+
+#[test]
+fn struct_uniform() {
+    let file = syn::parse_quote! {
+        pub struct Camera {
+            pub view: Mat4,
+            pub proj: Mat4,
+            pub eye: Vec3,
+        }
+
+        #[spirv(vertex)]
+        pub fn main_vs(
+            in_pos: Vec3,
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] camera: &Camera,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    let uniform = &pipelines[0].uniforms[0];
+    assert!(uniform.descriptor_type == DescriptorType::UniformBuffer);
+    assert!(uniform.range == Some(144));
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(
+            in_pos: Vec3,
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] camera: &Camera,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    assert!(matches!(
+        get_pipelines(&file),
+        Err(ShaderError::UnsupportedType {
+            what: "uniform",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn dynamic_uniform() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(
+            in_pos: Vec3,
+            #[pipewriter(dynamic)]
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] model: &Mat4,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    let uniform = &pipelines[0].uniforms[0];
+    assert!(uniform.descriptor_type == DescriptorType::UniformBufferDynamic);
+    assert!(uniform.get_info().to_string().contains(". range (64usize"));
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn main_fs(
+            #[pipewriter(dynamic)]
+            #[spirv(descriptor_set = 0, binding = 0)] albedo: &SampledImage<Image2d>,
+            out_color: &mut Vec4,
+        ) {
+        }
+    };
+    assert!(get_pipelines(&file).is_err());
+}
+
+#[test]
+fn pipeline_names() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_method_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn main_method_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].name == "MainMethod");
+
+    for file in [
+        syn::parse_quote! {
+            #[spirv(vertex)]
+            pub fn main(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+        },
+        syn::parse_quote! {
+            #[spirv(vertex)]
+            pub fn _vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+        },
+        syn::parse_quote! {
+            #[spirv(vertex)]
+            pub fn _2d_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+        },
+        syn::parse_quote! {
+            #[spirv(compute(threads(1)))]
+            pub fn self_cs() {}
+        },
+    ] {
+        assert!(matches!(
+            get_pipelines(&file),
+            Err(ShaderError::InvalidEntryPointName { .. })
+        ));
+    }
+}
+
+#[test]
+fn entry_points() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_method_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn main_method_fragment(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].entry_point(ShaderType::Vertex) == "main_method_vs");
+    assert!(pipelines[0].entry_point(ShaderType::Fragment) == "main_method_fragment");
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn main_frag(out_color: &mut Vec4) {}
+    };
+    assert!(matches!(
+        get_pipelines(&file),
+        Err(ShaderError::InvalidAttribute { .. })
+    ));
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+    };
+    assert!(matches!(
+        get_pipelines(&file),
+        Err(ShaderError::MissingEntryPoint {
+            stage: ShaderType::Fragment,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn inline_modules() {
+    let file = syn::parse_quote! {
+        mod types {
+            pub struct Camera {
+                pub view: Mat4,
+            }
+        }
+
+        mod mesh {
+            #[spirv(vertex)]
+            pub fn mesh_vs(
+                in_pos: Vec3,
+                #[spirv(uniform, descriptor_set = 0, binding = 0)] camera: &Camera,
+                #[spirv(position)] out_pos: &mut Vec4,
+            ) {
+            }
+
+            mod lit {
+                #[spirv(fragment)]
+                pub fn mesh_fs(out_color: &mut Vec4) {}
+            }
+        }
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines.len() == 1);
+    assert!(pipelines[0].entry_point(ShaderType::Fragment) == "mesh_fs");
+    assert!(pipelines[0].uniforms[0].range == Some(64));
+}
+
+#[test]
+fn shared_vertex_shader() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn mesh_vs(
+            in_pos: Vec3,
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] view: &Mat4,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[pipewriter(vertex = "mesh_vs")]
+        #[spirv(fragment)]
+        pub fn lit_fs(out_color: &mut Vec4) {}
+
+        #[pipewriter(vertex = "mesh_vs")]
+        #[spirv(fragment)]
+        pub fn unlit_fs(out_color: &mut Vec4) {}
+    };
+    let mut pipelines = get_pipelines(&file).unwrap();
+    pipelines.sort_by(|a, b| a.name.cmp(&b.name));
+    assert!(pipelines.len() == 2);
+    for (pipeline, fs) in pipelines.iter().zip(["lit_fs", "unlit_fs"]) {
+        assert!(pipeline.entry_point(ShaderType::Vertex) == "mesh_vs");
+        assert!(pipeline.entry_point(ShaderType::Fragment) == fs);
+        assert!(pipeline.inputs.len() == 1);
+        assert!(pipeline.uniforms.len() == 1);
+    }
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn mesh_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn mesh_fs(out_color: &mut Vec4) {}
+
+        #[pipewriter(vertex = "mesh_vs")]
+        #[spirv(fragment)]
+        pub fn shadow_fs(out_color: &mut Vec4) {}
+    };
+    assert!(get_pipelines(&file).unwrap().len() == 2);
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn mesh_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(vertex = "mesh_fs")]
+        #[spirv(fragment)]
+        pub fn mesh_fs(out_color: &mut Vec4) {}
+    };
+    assert!(matches!(
+        get_pipelines(&file),
+        Err(ShaderError::InvalidAttribute { .. })
+    ));
+}
+
+#[test]
+fn color_attachments() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn gbuffer_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(color_attachments = 3)]
+        #[spirv(fragment)]
+        pub fn gbuffer_fs(out_albedo: &mut Vec4) {}
+
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    let gbuffer = pipelines.iter().find(|p| p.name == "Gbuffer").unwrap();
+    assert!(gbuffer.color_outputs == 1);
+    assert!(gbuffer.color_attachments() == 3);
+    let main = pipelines.iter().find(|p| p.name == "Main").unwrap();
+    assert!(main.color_attachments() == 1);
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(color_attachments = 9)]
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    assert!(get_pipelines(&file).is_err());
+}
+
+#[test]
+fn blend_modes() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(blend = "additive")]
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.blend.as_deref() == Some("ADDITIVE"));
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(blend = "multiply")]
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    assert!(get_pipelines(&file).is_err());
+}
+
+#[test]
+fn samples() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(samples = 4)]
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.samples == 4);
+    assert!(!pipelines[0].state.alpha_to_coverage);
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn foliage_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(samples = 4, alpha_to_coverage)]
+        #[spirv(fragment)]
+        pub fn foliage_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.alpha_to_coverage);
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(samples = 3)]
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    assert!(get_pipelines(&file).is_err());
+}
+
+#[test]
+fn polygon_mode() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn wireframe_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(polygon_mode = "line")]
+        #[spirv(fragment)]
+        pub fn wireframe_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.polygon_mode.as_deref() == Some("LINE"));
+    assert!(pipelines[0].features() == ["fill_mode_non_solid"]);
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(polygon_mode = "fill")]
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].features().is_empty());
+}
+
+#[test]
+fn generate_crate() {
+    let crate_dir =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../vkr-pipe-test/shader/./simple");
+    let crate_module = CrateModule::load(&crate_dir).unwrap();
+    assert!(!crate_module.crate_path.to_string_lossy().contains("/./"));
+
+    let gen = generate(&crate_module, &Options::default())
+        .unwrap()
+        .to_string();
+    assert!(gen.contains("pub struct PipelineCache {"));
+    assert!(gen.contains("pub struct PipelineMain {"));
+
+    let options = Options {
+        descriptors_only: true,
+        ..Default::default()
+    };
+    let gen = generate(&crate_module, &options).unwrap().to_string();
+    assert!(!gen.contains("pub struct PipelineCache {"));
+}
+
+#[test]
+fn stage_interface() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(
+            in_pos: Vec3,
+            in_uv: Vec2,
+            #[spirv(uniform, descriptor_set = 0, binding = 0)] view: &Mat4,
+            #[spirv(position)] out_pos: &mut Vec4,
+            out_uv: &mut Vec2,
+            #[spirv(flat)] out_id: &mut u32,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn main_fs(
+            #[spirv(frag_coord)] coord: Vec4,
+            in_uv: Vec2,
+            #[spirv(flat)] in_id: u32,
+            #[spirv(spec_constant(id = 0))] mode: u32,
+            out_color: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(vertex)]
+        pub fn unlit_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4, out_uv: &mut Vec2) {}
+
+        #[spirv(fragment)]
+        pub fn unlit_fs(out_color: &mut Vec4) {}
+    };
+    assert!(get_pipelines(&file).unwrap().len() == 2);
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4, out_normal: &mut Vec3) {}
+
+        #[spirv(fragment)]
+        pub fn main_fs(in_uv: Vec2, out_color: &mut Vec4) {}
+    };
+    match get_pipelines(&file) {
+        Err(ShaderError::InvalidAttribute { message, .. }) => assert!(
+            message
+                == "Fragment input in_uv: Vec2 of pipeline Main does not match vertex output out_normal: Vec3"
+        ),
+        _ => panic!("Expected a mismatch of the vertex outputs"),
+    }
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[spirv(fragment)]
+        pub fn main_fs(in_uv: Vec2, out_color: &mut Vec4) {}
+    };
+    match get_pipelines(&file) {
+        Err(ShaderError::InvalidAttribute { message, .. }) => {
+            assert!(message
+                .contains("Fragment input in_uv of pipeline Main has no matching vertex output"))
+        }
+        _ => panic!("Expected a missing vertex output"),
+    }
+}
+
+#[test]
+fn interpolation_qualifiers() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(
+            in_pos: Vec3,
+            #[spirv(flat)] in_material: u32,
+            #[spirv(flat)] in_joints: UVec4,
+            #[spirv(vertex_index)] vertex_index: i32,
+            #[spirv(instance_index)] instance_index: i32,
+            #[spirv(position)] out_pos: &mut Vec4,
+            #[spirv(flat)] out_material: &mut u32,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn main_fs(#[spirv(flat)] in_material: u32, out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    let inputs = &pipelines[0].inputs;
+    assert!(inputs.len() == 3);
+    assert!(inputs[1].ty == "u32");
+    assert!(inputs[2].ty == "UVec4");
+}
+
+#[test]
+fn unknown_attributes() {
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(cul_mode = "back")]
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    match get_pipelines(&file) {
+        Err(ShaderError::InvalidAttribute { message, .. }) => {
+            assert!(message == "Unknown pipewriter attribute: cul_mode")
+        }
+        _ => panic!("Expected an unknown attribute"),
+    }
+
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn mesh_vs(
+            #[pipewriter(format = "R16G16B16A16_SFLOAT", binding = 1)] in_pos: Vec4,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[pipewriter(vertex = "mesh_vs", cull_mode = "back")]
+        #[spirv(fragment)]
+        pub fn lit_fs(out_color: &mut Vec4) {}
+    };
+    let pipelines = get_pipelines(&file).unwrap();
+    assert!(pipelines[0].state.cull_mode.as_deref() == Some("BACK"));
+
+    // Argument keys are unknown to shader functions
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+        #[pipewriter(count = 4)]
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    match get_pipelines(&file) {
+        Err(ShaderError::InvalidAttribute { message, .. }) => {
+            assert!(message == "Unknown pipewriter attribute: count")
+        }
+        _ => panic!("Expected an unknown attribute"),
+    }
+
+    // And pipeline state keys to shader arguments
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(
+            #[pipewriter(cull_mode = "back")] in_pos: Vec3,
+            #[spirv(position)] out_pos: &mut Vec4,
+        ) {
+        }
+
+        #[spirv(fragment)]
+        pub fn main_fs(out_color: &mut Vec4) {}
+    };
+    match get_pipelines(&file) {
+        Err(ShaderError::InvalidAttribute { message, .. }) => {
+            assert!(message == "Unknown pipewriter attribute: cull_mode")
+        }
+        _ => panic!("Expected an unknown attribute"),
+    }
+}
@@ -0,0 +1,298 @@
+// Copyright © 2021-2022
+// Author: Antonio Caggiano <info@antoniocaggiano.eu>
+// SPDX-License-Identifier: MIT
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::ShaderError;
+
+pub struct CrateModule {
+    pub crate_path: PathBuf,
+    pub name: String,
+    pub shader_path: PathBuf,
+    pub file: syn::File,
+    /// Vulkan formats of vertex types defined outside of the shader crate,
+    /// and of the `#[repr(C)]` structs of the shader file
+    pub vertex_formats: HashMap<String, Vec<String>>,
+    /// Precompiled SPIR-V module the crate was reflected from, embedded instead of the one built by rust-gpu
+    pub spirv_path: Option<PathBuf>,
+}
+
+impl CrateModule {
+    fn read_file(path: &Path) -> Result<String, ShaderError> {
+        std::fs::read_to_string(path).map_err(|err| {
+            if err.kind() == std::io::ErrorKind::NotFound {
+                ShaderError::MissingFile(path.into())
+            } else {
+                ShaderError::UnreadableFile(path.into(), err)
+            }
+        })
+    }
+
+    /// Parses a file of the shader crate. Parsed files are not cached across macro invocations,
+    /// as their tokens and spans are handles of the compiler only valid within one expansion.
+    fn parse_file(shader_path: &Path) -> Result<syn::File, ShaderError> {
+        let code = Self::read_file(shader_path)?;
+        syn::parse_file(&code)
+            .map_err(|err| ShaderError::Parse(shader_path.into(), err.to_string()))
+    }
+
+    /// Replaces the `mod name;` declarations of a file with the items of their modules,
+    /// looking for them in the `dir` of the file as rustc does, and so on recursively.
+    /// Shader functions and structs are then found in a single file, whatever module they belong to.
+    fn load_modules(file: &mut syn::File, dir: &Path) -> Result<(), ShaderError> {
+        let mut items = vec![];
+
+        for item in std::mem::take(&mut file.items) {
+            let module = match item {
+                syn::Item::Mod(module) if module.content.is_none() => module,
+                item => {
+                    items.push(item);
+                    continue;
+                }
+            };
+
+            let name = module.ident.to_string();
+            let (path, module_dir) = match Self::get_path_attr(&module.attrs) {
+                Some(path) => {
+                    let path = dir.join(path);
+                    let module_dir = path.parent().unwrap_or(dir).to_path_buf();
+                    (path, module_dir)
+                }
+                None => {
+                    let module_dir = dir.join(&name);
+                    let path = dir.join(format!("{}.rs", name));
+                    if path.exists() {
+                        (path, module_dir)
+                    } else {
+                        (module_dir.join("mod.rs"), module_dir)
+                    }
+                }
+            };
+
+            let mut module_file = Self::parse_file(&path).map_err(|err| match err {
+                ShaderError::MissingFile(path) => ShaderError::MissingModule { module: name, path },
+                err => err,
+            })?;
+            Self::load_modules(&mut module_file, &module_dir)?;
+            items.extend(module_file.items);
+        }
+
+        file.items = items;
+        Ok(())
+    }
+
+    /// Returns the value of a `#[path = "file.rs"]` attribute of a module
+    fn get_path_attr(attrs: &[syn::Attribute]) -> Option<String> {
+        attrs.iter().find_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::NameValue(name_value)) if name_value.path.is_ident("path") => {
+                match name_value.lit {
+                    syn::Lit::Str(path) => Some(path.value()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+    }
+
+    /// Returns the crate name looking into its `Cargo.toml`
+    fn get_crate_name(cargo_toml: &toml::Value) -> Option<String> {
+        let table = cargo_toml.as_table()?;
+
+        for (key, value) in table {
+            if key == "package" {
+                let package = value.as_table()?;
+                for (key, value) in package {
+                    if key == "name" {
+                        return value.as_str().map(String::from);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the shader file name looking into its `Cargo.toml`
+    fn get_shader_path(cargo_toml: &toml::Value) -> PathBuf {
+        let table = cargo_toml
+            .as_table()
+            .expect("Failed to get Cargo.toml table");
+
+        for (key, value) in table {
+            if key == "lib" {
+                let lib = value.as_table().expect("Failed to get lib table");
+                for (key, value) in lib {
+                    if key == "path" {
+                        return value.as_str().expect("Failed to get lib value").into();
+                    }
+                }
+            }
+        }
+        "src/lib.rs".into() // default value
+    }
+
+    /// Returns the vertex formats found in the `package.metadata.pipewriter.vertex-formats`
+    /// table of `Cargo.toml`, where a type maps to one format or to a list of formats
+    fn get_vertex_formats(
+        cargo_toml: &toml::Value,
+    ) -> Result<HashMap<String, Vec<String>>, String> {
+        let mut vertex_formats = HashMap::new();
+
+        let table = cargo_toml
+            .get("package")
+            .and_then(|package| package.get("metadata"))
+            .and_then(|metadata| metadata.get("pipewriter"))
+            .and_then(|pipewriter| pipewriter.get("vertex-formats"))
+            .and_then(|formats| formats.as_table());
+
+        if let Some(table) = table {
+            for (key, value) in table {
+                let formats = match value {
+                    toml::Value::String(format) => vec![format.to_uppercase()],
+                    toml::Value::Array(formats) => formats
+                        .iter()
+                        .map(|format| format.as_str().map(str::to_uppercase))
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or(format!("vertex formats of {} should be strings", key))?,
+                    _ => return Err(format!("vertex formats of {} should be strings", key)),
+                };
+                vertex_formats.insert(key.clone(), formats);
+            }
+        }
+
+        Ok(vertex_formats)
+    }
+
+    pub fn new(crate_path: PathBuf) -> Result<Self, ShaderError> {
+        if !crate_path.is_dir() {
+            return Err(ShaderError::MissingCrate(crate_path));
+        }
+
+        let cargo_toml_path = crate_path.join("Cargo.toml");
+        let cargo_toml_str = Self::read_file(&cargo_toml_path)?;
+        let cargo_toml: toml::Value = toml::from_str(&cargo_toml_str)
+            .map_err(|err| ShaderError::Parse(cargo_toml_path.clone(), err.to_string()))?;
+
+        let name = Self::get_crate_name(&cargo_toml).ok_or_else(|| {
+            ShaderError::Parse(cargo_toml_path.clone(), "missing package name".into())
+        })?;
+        let shader_path = crate_path.join(Self::get_shader_path(&cargo_toml));
+        let mut file = Self::parse_file(&shader_path)?;
+        let shader_dir = shader_path.parent().unwrap_or(&crate_path).to_path_buf();
+        Self::load_modules(&mut file, &shader_dir)?;
+        let mut vertex_formats = Self::get_vertex_formats(&cargo_toml)
+            .map_err(|err| ShaderError::Parse(cargo_toml_path, err))?;
+        let vertex_structs = crate::get_vertex_structs(&file, &vertex_formats);
+        vertex_formats.extend(vertex_structs);
+
+        Ok(Self {
+            crate_path,
+            name,
+            shader_path,
+            file,
+            vertex_formats,
+            spirv_path: None,
+        })
+    }
+
+    /// Loads the shader crate at `path`, or reflects the SPIR-V module when the path ends with `.spv`
+    pub fn load(path: &Path) -> Result<Self, ShaderError> {
+        let path = crate::normalize_path(path);
+        if matches!(path.extension(), Some(ext) if ext == "spv") {
+            Self::from_spirv(path)
+        } else {
+            Self::new(path)
+        }
+    }
+
+    /// Reflects a precompiled SPIR-V module, such as one compiled from GLSL or WGSL,
+    /// into the functions of a shader crate named after the file
+    pub fn from_spirv(spirv_path: PathBuf) -> Result<Self, ShaderError> {
+        let code = std::fs::read(&spirv_path).map_err(|err| {
+            if err.kind() == std::io::ErrorKind::NotFound {
+                ShaderError::MissingFile(spirv_path.clone())
+            } else {
+                ShaderError::UnreadableFile(spirv_path.clone(), err)
+            }
+        })?;
+        let source = crate::spirv::reflect(&code, &HashMap::new())
+            .map_err(|err| ShaderError::Parse(spirv_path.clone(), err))?;
+        let file = syn::parse_file(&source)
+            .map_err(|err| ShaderError::Parse(spirv_path.clone(), err.to_string()))?;
+
+        // Extensions are dropped, as in `shaders` for `shaders.vert.spv`
+        let name = spirv_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.split('.').next())
+            .unwrap_or("shader")
+            .to_string();
+
+        Ok(Self {
+            crate_path: spirv_path.parent().unwrap_or(&spirv_path).to_path_buf(),
+            name,
+            shader_path: spirv_path.clone(),
+            file,
+            vertex_formats: HashMap::new(),
+            spirv_path: Some(spirv_path),
+        })
+    }
+}
+
+#[test]
+fn load_crate() {
+    let cargo_toml = toml::toml!(
+    [package]
+    name = "simple-shader"
+    [workspace]
+    [lib]
+    crate-type = ["lib", "dylib"]
+    path = "src/simple.rs"
+        );
+
+    let name = CrateModule::get_crate_name(&cargo_toml).unwrap();
+    assert!(name == "simple-shader");
+
+    let shader_path = CrateModule::get_shader_path(&cargo_toml);
+    assert!(shader_path == std::path::Path::new("src/simple.rs"));
+}
+
+#[test]
+fn load_modules() {
+    let crate_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../vkr-pipe/tests/ui/shader/modules");
+    let crate_module = CrateModule::new(crate_dir).unwrap();
+
+    let mut pipelines: Vec<String> = crate::get_pipelines(&crate_module.file)
+        .unwrap()
+        .into_iter()
+        .map(|p| p.name)
+        .collect();
+    pipelines.sort();
+    assert!(pipelines == ["Blur", "Mesh", "Tonemap"]);
+
+    let crate_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../vkr-pipe/tests/ui/shader/missing_module");
+    assert!(matches!(
+        CrateModule::new(crate_dir),
+        Err(ShaderError::MissingModule { module, .. }) if module == "gone"
+    ));
+}
+
+#[test]
+fn load_vertex_formats() {
+    let cargo_toml = toml::toml!(
+    [package]
+    name = "simple-shader"
+    [package.metadata.pipewriter.vertex-formats]
+    PackedNormal = "a2b10g10r10_snorm_pack32"
+    StandardVertex = ["R32G32B32_SFLOAT", "R32G32B32_SFLOAT", "R32G32_SFLOAT"]
+        );
+
+    let vertex_formats = CrateModule::get_vertex_formats(&cargo_toml).unwrap();
+    assert!(vertex_formats["PackedNormal"] == ["A2B10G10R10_SNORM_PACK32"]);
+    assert!(vertex_formats["StandardVertex"].len() == 3);
+}
@@ -0,0 +1,805 @@
+// Copyright © 2021-2022
+// Author: Antonio Caggiano <info@antoniocaggiano.eu>
+// SPDX-License-Identifier: MIT
+
+use std::collections::{BTreeMap, HashMap};
+
+use quote::{quote, ToTokens};
+
+use crate::Camelcase;
+
+/// Stage of a shader function, ordered as in a graphics pipeline
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ShaderType {
+    Vertex,
+    TessellationControl,
+    TessellationEvaluation,
+    Geometry,
+    Fragment,
+    Compute,
+}
+
+impl ShaderType {
+    /// Returns the suffix of the shader functions of this stage, such as `vs` for `main_vs`
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            ShaderType::Vertex => "vs",
+            ShaderType::TessellationControl => "tcs",
+            ShaderType::TessellationEvaluation => "tes",
+            ShaderType::Geometry => "gs",
+            ShaderType::Fragment => "fs",
+            ShaderType::Compute => "cs",
+        }
+    }
+}
+
+impl ToTokens for ShaderType {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            ShaderType::Vertex => tokens.extend(quote! { vk::ShaderStageFlags::VERTEX }),
+            ShaderType::TessellationControl => {
+                tokens.extend(quote! { vk::ShaderStageFlags::TESSELLATION_CONTROL })
+            }
+            ShaderType::TessellationEvaluation => {
+                tokens.extend(quote! { vk::ShaderStageFlags::TESSELLATION_EVALUATION })
+            }
+            ShaderType::Geometry => tokens.extend(quote! { vk::ShaderStageFlags::GEOMETRY }),
+            ShaderType::Fragment => tokens.extend(quote! { vk::ShaderStageFlags::FRAGMENT }),
+            ShaderType::Compute => tokens.extend(quote! { vk::ShaderStageFlags::COMPUTE }),
+        }
+    }
+}
+
+/// Returns the size of a uniform buffer member type following the std140 layout rules,
+/// where the stride of array elements is rounded up to 16 bytes.
+/// Struct types are looked up by name in the `structs` sizes of the shader crate.
+pub fn get_std140_size(ty: &syn::Type, structs: &HashMap<String, usize>) -> Option<usize> {
+    let f32_size = std::mem::size_of::<f32>();
+
+    match ty {
+        syn::Type::Array(array) => {
+            let len = match &array.len {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Int(len),
+                    ..
+                }) => len.base10_parse::<usize>().ok()?,
+                _ => return None,
+            };
+            let elem_size = get_std140_size(&array.elem, structs)?;
+            let stride = elem_size + (16 - elem_size % 16) % 16;
+            Some(stride * len)
+        }
+        syn::Type::Path(path) => match path.path.segments.last()?.ident.to_string().as_str() {
+            "f32" | "u32" | "i32" => Some(f32_size),
+            "Vec2" => Some(f32_size * 2),
+            "Vec3" => Some(f32_size * 3),
+            "Vec4" => Some(f32_size * 4),
+            "Mat3" => Some(f32_size * 9),
+            "Mat4" => Some(f32_size * 16),
+            name => structs.get(name).copied(),
+        },
+        _ => None,
+    }
+}
+
+/// Returns the base alignment of a std140 member type, where vectors of three or four
+/// components, matrices, arrays, and structs are all aligned to 16 bytes
+fn get_std140_align(ty: &syn::Type) -> usize {
+    match ty {
+        syn::Type::Path(path) => match path.path.segments.last() {
+            Some(segment)
+                if segment.ident == "f32" || segment.ident == "u32" || segment.ident == "i32" =>
+            {
+                4
+            }
+            Some(segment) if segment.ident == "Vec2" => 8,
+            _ => 16,
+        },
+        _ => 16,
+    }
+}
+
+/// Returns the std140 sizes of the structs declared by a shader file, by name.
+/// Structs with members of unsupported types are left out.
+pub fn get_struct_sizes(file: &syn::File) -> HashMap<String, usize> {
+    let structs: Vec<&syn::ItemStruct> = crate::get_items(&file.items)
+        .into_iter()
+        .filter_map(|item| match item {
+            syn::Item::Struct(s) => Some(s),
+            _ => None,
+        })
+        .collect();
+
+    // Structs may contain other structs declared later on,
+    // so keep going until no more sizes can be resolved
+    let mut sizes = HashMap::new();
+    loop {
+        let resolved = sizes.len();
+        for item in &structs {
+            let name = item.ident.to_string();
+            if sizes.contains_key(&name) {
+                continue;
+            }
+
+            let mut offset = 0;
+            let mut complete = true;
+            for field in &item.fields {
+                match get_std140_size(&field.ty, &sizes) {
+                    Some(size) => {
+                        let align = get_std140_align(&field.ty);
+                        offset += (align - offset % align) % align + size;
+                    }
+                    None => {
+                        complete = false;
+                        break;
+                    }
+                }
+            }
+            if complete {
+                sizes.insert(name, offset + (16 - offset % 16) % 16);
+            }
+        }
+        if sizes.len() == resolved {
+            return sizes;
+        }
+    }
+}
+
+/// Returns the descriptor type of an `Image!`, telling apart subpass inputs,
+/// storage images which are not sampled, and sampled images
+fn get_image_descriptor_type(ty: &syn::Type) -> DescriptorType {
+    match ty {
+        syn::Type::Macro(m) => {
+            let tokens = m.mac.tokens.to_string().replace(' ', "");
+            if tokens.contains("subpass") {
+                DescriptorType::InputAttachment
+            } else if tokens.contains("sampled=false") {
+                DescriptorType::StorageImage
+            } else {
+                DescriptorType::SampledImage
+            }
+        }
+        _ => DescriptorType::SampledImage,
+    }
+}
+
+/// Type of the descriptor used to bind a uniform
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DescriptorType {
+    UniformBuffer,
+    /// Uniform buffer bound with an offset when binding its descriptor set
+    UniformBufferDynamic,
+    StorageBuffer,
+    CombinedImageSampler,
+    /// Sampler bound separately from the images it samples
+    Sampler,
+    /// Image sampled through a separate `Sampler`
+    SampledImage,
+    StorageImage,
+    InputAttachment,
+}
+
+impl DescriptorType {
+    /// Returns the descriptor type suitable for a uniform of type `ident`,
+    /// looking into the full type `ty` to tell images apart
+    pub fn from_ident(ident: &syn::Ident, ty: &syn::Type) -> Option<Self> {
+        match ident.to_string().as_str() {
+            "f32" | "u32" | "i32" | "Vec2" | "Vec3" | "Vec4" | "Mat3" | "Mat4" => {
+                Some(DescriptorType::UniformBuffer)
+            }
+            "SampledImage" => Some(DescriptorType::CombinedImageSampler),
+            "Sampler" => Some(DescriptorType::Sampler),
+            "Image" => Some(get_image_descriptor_type(ty)),
+            // Aliases of sampled images provided by spirv-std
+            "Image1d" | "Image2d" | "Image3d" | "Image2dArray" | "Cubemap" => {
+                Some(DescriptorType::SampledImage)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the corresponding `vk::DescriptorType`
+    pub fn name(&self) -> &'static str {
+        match self {
+            DescriptorType::UniformBuffer => "UNIFORM_BUFFER",
+            DescriptorType::UniformBufferDynamic => "UNIFORM_BUFFER_DYNAMIC",
+            DescriptorType::StorageBuffer => "STORAGE_BUFFER",
+            DescriptorType::CombinedImageSampler => "COMBINED_IMAGE_SAMPLER",
+            DescriptorType::Sampler => "SAMPLER",
+            DescriptorType::SampledImage => "SAMPLED_IMAGE",
+            DescriptorType::StorageImage => "STORAGE_IMAGE",
+            DescriptorType::InputAttachment => "INPUT_ATTACHMENT",
+        }
+    }
+}
+
+impl ToTokens for DescriptorType {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let name = syn::Ident::new(self.name(), proc_macro2::Span::call_site());
+        tokens.extend(quote! { vk::DescriptorType::#name })
+    }
+}
+
+pub struct Uniform {
+    pub name: syn::Ident,
+    /// Full type of the argument, without reference
+    pub ty: syn::Type,
+    /// Size in bytes of the range bound for uniform buffers
+    pub range: Option<usize>,
+    pub descriptor_type: DescriptorType,
+    pub descriptor_set: u32,
+    pub binding: u32,
+    /// Stages of the shaders declaring the uniform, visible to all of them
+    pub stages: Vec<ShaderType>,
+    /// Number of descriptors of the binding, more than 1 for arrays of descriptors
+    pub count: u32,
+    /// Names of the `vk::DescriptorBindingFlags` of the binding, such as `PARTIALLY_BOUND`
+    pub binding_flags: Vec<&'static str>,
+}
+
+impl Uniform {
+    pub fn new(
+        name: syn::Ident,
+        ty: syn::Type,
+        range: Option<usize>,
+        descriptor_type: DescriptorType,
+        descriptor_set: u32,
+        binding: u32,
+        stage: ShaderType,
+    ) -> Self {
+        Self {
+            name,
+            ty,
+            range,
+            descriptor_type,
+            descriptor_set,
+            binding,
+            stages: vec![stage],
+            count: 1,
+            binding_flags: vec![],
+        }
+    }
+
+    /// Returns the vkr_core type referenced by a `write_set_N` method for this uniform.
+    /// Raw Vulkan handles and ranges are extracted from it by the generated code.
+    pub fn get_write_set_type(&self) -> proc_macro2::TokenStream {
+        match self.descriptor_type {
+            DescriptorType::UniformBuffer
+            | DescriptorType::UniformBufferDynamic
+            | DescriptorType::StorageBuffer => quote! { vkr_core::Buffer },
+            DescriptorType::CombinedImageSampler
+            | DescriptorType::Sampler
+            | DescriptorType::SampledImage
+            | DescriptorType::StorageImage => {
+                quote! { Texture }
+            }
+            // Attachments of the render pass are read without a sampler. Not imported
+            // by the header, as the user may already import it next to the macro
+            DescriptorType::InputAttachment => quote! { vkr_core::ImageView },
+        }
+    }
+
+    /// Returns the buffer or image info of the argument of this uniform, to be bound to a local
+    /// which outlives the `WriteDescriptorSet` referencing it
+    pub fn get_info(&self) -> proc_macro2::TokenStream {
+        let name = &self.name;
+        self.get_descriptor_info(&quote! { #name })
+    }
+
+    /// Returns the `WriteDescriptorSet` builder method taking the infos of this uniform
+    pub fn get_info_setter(&self) -> syn::Ident {
+        let setter = match self.descriptor_type {
+            DescriptorType::UniformBuffer
+            | DescriptorType::UniformBufferDynamic
+            | DescriptorType::StorageBuffer => "buffer_info",
+            _ => "image_info",
+        };
+        syn::Ident::new(setter, proc_macro2::Span::call_site())
+    }
+
+    /// Returns the descriptor info of a resource of this uniform, such as an element of an array
+    pub fn get_descriptor_info(
+        &self,
+        resource: &proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        match self.descriptor_type {
+            DescriptorType::UniformBuffer | DescriptorType::UniformBufferDynamic => {
+                // A dynamic uniform buffer binds one element, found at the dynamic offset
+                let range = self.range.unwrap();
+                quote! {
+                    vk::DescriptorBufferInfo::builder()
+                        .range(#range as vk::DeviceSize)
+                        .buffer(#resource.buffer)
+                        .build()
+                }
+            }
+            DescriptorType::StorageBuffer => {
+                // Storage buffers may be runtime arrays, so bind the whole buffer
+                quote! {
+                    vk::DescriptorBufferInfo::builder()
+                        .range(vk::WHOLE_SIZE)
+                        .buffer(#resource.buffer)
+                        .build()
+                }
+            }
+            DescriptorType::StorageImage => {
+                // Storage images are accessed without a sampler in the general layout
+                quote! {
+                    vk::DescriptorImageInfo::builder()
+                        .image_layout(vk::ImageLayout::GENERAL)
+                        .image_view(#resource.view)
+                        .build()
+                }
+            }
+            DescriptorType::Sampler => {
+                // Only the sampler of the texture is bound
+                quote! {
+                    vk::DescriptorImageInfo::builder()
+                        .sampler(#resource.sampler)
+                        .build()
+                }
+            }
+            DescriptorType::SampledImage => {
+                // Only the view of the texture is bound, to be sampled with a separate sampler
+                quote! {
+                    vk::DescriptorImageInfo::builder()
+                        .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                        .image_view(#resource.view)
+                        .build()
+                }
+            }
+            DescriptorType::InputAttachment => {
+                // Input attachments are read at the fragment position, so they need no sampler
+                quote! {
+                    vk::DescriptorImageInfo::builder()
+                        .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                        .image_view(#resource.view)
+                        .build()
+                }
+            }
+            DescriptorType::CombinedImageSampler => {
+                quote! {
+                    vk::DescriptorImageInfo::builder()
+                        .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                        .image_view(#resource.view)
+                        .sampler(#resource.sampler)
+                        .build()
+                }
+            }
+        }
+    }
+}
+
+/// Fixed-function state of a pipeline, configured by `#[pipewriter(...)]`
+/// attributes found on its shader functions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineState {
+    pub depth_test: bool,
+    pub depth_write: bool,
+    pub depth_clamp: bool,
+    /// Whether depth bias is enabled, as a dynamic state set when recording commands
+    pub depth_bias: bool,
+    /// Whether the line width is a dynamic state set when recording commands
+    pub dynamic_line_width: bool,
+    /// Whether a special index value restarts strips and fans
+    pub primitive_restart: bool,
+    pub sample_shading: bool,
+    /// Whether the alpha of the first color output sets the coverage of the samples of its pixel
+    pub alpha_to_coverage: bool,
+    /// Number of control points per patch, which makes the pipeline draw patches
+    pub patch_control_points: Option<u32>,
+    /// Shared vertex layout the vertex inputs read a subset of
+    pub vertex_layout: Option<String>,
+    /// Whether depth goes from 1 (near) to 0 (far), or the crate default when not set
+    pub reverse_z: Option<bool>,
+    /// Number of views rendered at once with multiview, 1 without multiview
+    pub views: u32,
+    /// Name of the `vk::PrimitiveTopology` to draw, triangle list when not set
+    pub topology: Option<String>,
+    /// Name of the `vk::CompareOp` of the depth test, which depends on reverse-Z when not set
+    pub depth_compare: Option<String>,
+    /// Name of the `vk::CullModeFlags` to use, no culling when not set
+    pub cull_mode: Option<String>,
+    /// Name of the default `vk::FrontFace`, counter clockwise when not set
+    pub front_face: Option<String>,
+    /// Number of samples per pixel, matching the attachments of the render pass
+    pub samples: u32,
+    /// Index of the subpass of the render pass this pipeline is used in
+    pub subpass: u32,
+    /// Name of the `vk::PolygonMode` to rasterize with, filled polygons when not set
+    pub polygon_mode: Option<String>,
+    /// Name of the blend preset of the color attachments, alpha blending when not set
+    pub blend: Option<String>,
+    /// Number of color attachments of the subpass, the color outputs of the fragment shader when not set
+    pub color_attachments: Option<usize>,
+    /// Stencil operations of front and back faces, which enable the stencil test when set
+    pub stencil: Option<StencilState>,
+}
+
+impl PipelineState {
+    /// Returns the names of the `vk::PhysicalDeviceFeatures` this state needs
+    pub fn features(&self) -> Vec<&'static str> {
+        let mut features = vec![];
+        if self.depth_clamp {
+            features.push("depth_clamp");
+        }
+        if self.sample_shading {
+            features.push("sample_rate_shading");
+        }
+        if self.patch_control_points.is_some() {
+            features.push("tessellation_shader");
+        }
+        if matches!(self.polygon_mode.as_deref(), Some("LINE" | "POINT")) {
+            features.push("fill_mode_non_solid");
+        }
+        features
+    }
+
+    /// Returns the names of the device extensions this state needs
+    pub fn extensions(&self) -> Vec<&'static str> {
+        let mut extensions = vec![];
+        if self.views > 1 {
+            extensions.push("VK_KHR_multiview");
+        }
+        extensions
+    }
+}
+
+impl Default for PipelineState {
+    fn default() -> Self {
+        Self {
+            depth_test: true,
+            depth_write: true,
+            depth_clamp: false,
+            depth_bias: false,
+            primitive_restart: false,
+            dynamic_line_width: false,
+            sample_shading: false,
+            alpha_to_coverage: false,
+            patch_control_points: None,
+            vertex_layout: None,
+            reverse_z: None,
+            views: 1,
+            topology: None,
+            depth_compare: None,
+            cull_mode: None,
+            front_face: None,
+            samples: 1,
+            subpass: 0,
+            polygon_mode: None,
+            blend: None,
+            color_attachments: None,
+            stencil: None,
+        }
+    }
+}
+
+/// Stencil operations of a pipeline, one for each facing of the primitives
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StencilState {
+    pub front: StencilOps,
+    pub back: StencilOps,
+}
+
+/// Stencil operations of a face, generated as a `vk::StencilOpState`.
+/// The ops are names of `vk::StencilOp` and `vk::CompareOp` values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StencilOps {
+    pub fail: String,
+    pub pass: String,
+    pub depth_fail: String,
+    pub compare: String,
+    pub compare_mask: u32,
+    pub write_mask: u32,
+    pub reference: u32,
+}
+
+impl Default for StencilOps {
+    /// Always passes, keeping the stencil buffer untouched
+    fn default() -> Self {
+        Self {
+            fail: "KEEP".into(),
+            pass: "KEEP".into(),
+            depth_fail: "KEEP".into(),
+            compare: "ALWAYS".into(),
+            compare_mask: 0xff,
+            write_mask: 0xff,
+            reference: 0,
+        }
+    }
+}
+
+impl ToTokens for StencilOps {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let ident = |name: &str| syn::Ident::new(name, proc_macro2::Span::call_site());
+        let fail = ident(&self.fail);
+        let pass = ident(&self.pass);
+        let depth_fail = ident(&self.depth_fail);
+        let compare = ident(&self.compare);
+        let compare_mask = self.compare_mask;
+        let write_mask = self.write_mask;
+        let reference = self.reference;
+        tokens.extend(quote! {
+            vk::StencilOpState {
+                fail_op: vk::StencilOp::#fail,
+                pass_op: vk::StencilOp::#pass,
+                depth_fail_op: vk::StencilOp::#depth_fail,
+                compare_op: vk::CompareOp::#compare,
+                compare_mask: #compare_mask,
+                write_mask: #write_mask,
+                reference: #reference,
+            }
+        })
+    }
+}
+
+/// A specialization constant, with the default value declared by the shader
+#[derive(Clone)]
+pub struct SpecConstant {
+    pub name: syn::Ident,
+    pub ty: syn::Ident,
+    pub id: u32,
+    pub default: proc_macro2::TokenStream,
+}
+
+/// A push constant block, shared by the stages declaring an argument with the same name
+pub struct PushConstant {
+    pub name: syn::Ident,
+    /// Size in bytes of the block, which starts at offset 0
+    pub size: usize,
+    pub stages: Vec<ShaderType>,
+}
+
+/// An input argument of a vertex shader, fed by vertex attributes
+pub struct VertexInput {
+    pub ty: syn::Ident,
+    /// Vulkan format overriding the one inferred from the type, such as
+    /// `B8G8R8A8_UNORM` for colors stored in BGRA order
+    pub format: Option<syn::LitStr>,
+    /// Index of the attribute read from a shared vertex layout
+    pub attribute: Option<u32>,
+    /// Whether the input is read once per instance from the instance binding
+    pub instance: bool,
+    /// Vertex buffer binding to read the input from, instead of the vertex or instance binding
+    pub binding: Option<u32>,
+}
+
+pub struct PipelineBuilder {
+    pub name: String,
+    pub inputs: Vec<VertexInput>,
+    pub uniforms: Vec<Uniform>,
+    pub color_outputs: usize,
+    pub spec_constants: Vec<SpecConstant>,
+    pub state: PipelineState,
+    pub workgroup_size: Option<[u32; 3]>,
+    pub stages: Vec<ShaderType>,
+    pub entry_points: BTreeMap<ShaderType, String>,
+    pub push_constants: Vec<PushConstant>,
+}
+
+impl PipelineBuilder {
+    pub fn new() -> Self {
+        Self {
+            name: String::default(),
+            inputs: Vec::default(),
+            uniforms: Vec::default(),
+            color_outputs: 0,
+            spec_constants: Vec::default(),
+            state: PipelineState::default(),
+            workgroup_size: None,
+            stages: Vec::default(),
+            entry_points: BTreeMap::default(),
+            push_constants: Vec::default(),
+        }
+    }
+
+    pub fn name(mut self, name: String) -> Self {
+        self.name = name;
+        self
+    }
+
+    pub fn inputs(&mut self, inputs: Vec<VertexInput>) {
+        self.inputs = inputs;
+    }
+
+    pub fn color_outputs(&mut self, color_outputs: usize) {
+        self.color_outputs = color_outputs;
+    }
+
+    pub fn workgroup_size(&mut self, workgroup_size: [u32; 3]) {
+        self.workgroup_size = Some(workgroup_size);
+    }
+
+    /// Adds an optional stage, keeping them in pipeline order
+    pub fn add_stage(&mut self, stage: ShaderType) {
+        if !self.stages.contains(&stage) {
+            self.stages.push(stage);
+            self.stages.sort();
+        }
+    }
+
+    /// Sets the name of the shader function of a stage, returning the previous one if any
+    pub fn entry_point(&mut self, stage: ShaderType, function: String) -> Option<String> {
+        self.entry_points.insert(stage, function)
+    }
+
+    /// Adds push constants, merging the stages of the ones with the same name
+    pub fn add_push_constants(&mut self, push_constants: Vec<PushConstant>) {
+        for push_constant in push_constants {
+            match self
+                .push_constants
+                .iter_mut()
+                .find(|p| p.name == push_constant.name)
+            {
+                Some(existing) => {
+                    existing.size = existing.size.max(push_constant.size);
+                    existing.stages.extend(push_constant.stages);
+                }
+                None => self.push_constants.push(push_constant),
+            }
+        }
+    }
+
+    /// Adds uniforms, merging the stages of the ones with the same set and binding
+    pub fn add_uniforms(&mut self, uniforms: Vec<Uniform>) {
+        for uniform in uniforms {
+            match self.uniforms.iter_mut().find(|u| {
+                u.descriptor_set == uniform.descriptor_set && u.binding == uniform.binding
+            }) {
+                Some(existing) => {
+                    for stage in uniform.stages {
+                        if !existing.stages.contains(&stage) {
+                            existing.stages.push(stage);
+                        }
+                    }
+                }
+                None => self.uniforms.push(uniform),
+            }
+        }
+    }
+
+    /// Adds specialization constants, skipping the ids already known
+    pub fn add_spec_constants(&mut self, spec_constants: Vec<SpecConstant>) {
+        for spec_constant in spec_constants {
+            if self.spec_constants.iter().all(|s| s.id != spec_constant.id) {
+                self.spec_constants.push(spec_constant);
+            }
+        }
+    }
+
+    pub fn build(self) -> Pipeline {
+        let mut pipeline = Pipeline::new(self.name, self.inputs, self.uniforms);
+        pipeline.color_outputs = self.color_outputs;
+        pipeline.spec_constants = self.spec_constants;
+        pipeline.state = self.state;
+        pipeline.workgroup_size = self.workgroup_size;
+        pipeline.stages = self.stages;
+        pipeline.entry_points = self.entry_points;
+        pipeline.push_constants = self.push_constants;
+        pipeline
+    }
+}
+
+pub struct Pipeline {
+    pub name: String,
+    pub inputs: Vec<VertexInput>,
+    pub uniforms: Vec<Uniform>,
+    /// Number of color attachments written by the fragment shader
+    pub color_outputs: usize,
+    /// Specialization constants of both shaders, sorted by id
+    pub spec_constants: Vec<SpecConstant>,
+    pub state: PipelineState,
+    /// Threads per workgroup of a compute pipeline, `None` for a graphics pipeline
+    pub workgroup_size: Option<[u32; 3]>,
+    /// Optional stages between the vertex and fragment shaders, in pipeline order
+    pub stages: Vec<ShaderType>,
+    /// Names of the shader functions of each stage, used as entry points
+    pub entry_points: BTreeMap<ShaderType, String>,
+    pub push_constants: Vec<PushConstant>,
+    /// Start of the names of the generated types, `Pipeline` unless set by the `prefix` macro argument
+    pub prefix: String,
+}
+
+impl Pipeline {
+    pub fn builder() -> PipelineBuilder {
+        PipelineBuilder::new()
+    }
+
+    pub fn new(name: String, inputs: Vec<VertexInput>, uniforms: Vec<Uniform>) -> Self {
+        Self {
+            name,
+            inputs,
+            uniforms,
+            color_outputs: 0,
+            spec_constants: Vec::default(),
+            state: PipelineState::default(),
+            workgroup_size: None,
+            stages: Vec::default(),
+            entry_points: BTreeMap::default(),
+            push_constants: Vec::default(),
+            prefix: String::from("Pipeline"),
+        }
+    }
+
+    /// Returns the name of a generated type of this pipeline, such as `PipelineMain` for an empty
+    /// suffix or `PipelineMainSpec`, starting with the prefix of the pipeline
+    pub fn type_name(&self, suffix: &str) -> String {
+        format!("{}{}{}", self.prefix, self.name.to_camelcase(), suffix)
+    }
+
+    /// Returns the name of the descriptor cache of this pipeline, such as `PipelineCacheMain`
+    pub fn cache_name(&self) -> String {
+        format!("{}Cache{}", self.prefix, self.name.to_camelcase())
+    }
+
+    /// Returns the names of the `vk::PhysicalDeviceFeatures` needed by its state and stages
+    pub fn features(&self) -> Vec<&'static str> {
+        let mut features = self.state.features();
+        if self.stages.contains(&ShaderType::Geometry) {
+            features.push("geometry_shader");
+        }
+        features
+    }
+
+    /// Returns the names of the device extensions this pipeline needs
+    pub fn extensions(&self) -> Vec<&'static str> {
+        self.state.extensions()
+    }
+
+    /// Returns the name of the shader function of a stage, which `get_pipelines` records for all the stages
+    pub fn entry_point(&self, stage: ShaderType) -> &str {
+        self.entry_points
+            .get(&stage)
+            .map(String::as_str)
+            .expect("Missing entry point")
+    }
+
+    pub fn is_compute(&self) -> bool {
+        self.workgroup_size.is_some()
+    }
+
+    /// Returns the number of blend attachments, matching the color attachments of the subpass
+    pub fn color_attachments(&self) -> usize {
+        self.state.color_attachments.unwrap_or(self.color_outputs)
+    }
+}
+
+#[test]
+fn std140_array_stride() {
+    let structs = HashMap::new();
+    assert!(get_std140_size(&syn::parse_quote!(Mat4), &structs) == Some(64));
+    assert!(get_std140_size(&syn::parse_quote!([Vec4; 4]), &structs) == Some(64));
+    assert!(get_std140_size(&syn::parse_quote!([f32; 4]), &structs) == Some(64));
+    assert!(get_std140_size(&syn::parse_quote!([Mat4; 2]), &structs) == Some(128));
+}
+
+#[test]
+fn std140_struct_size() {
+    let file = syn::parse_quote! {
+        pub struct Scene {
+            pub camera: Camera,
+            pub lights: [Light; 2],
+            pub light_count: u32,
+        }
+
+        pub struct Camera {
+            pub view: Mat4,
+            pub proj: Mat4,
+        }
+
+        pub struct Light {
+            pub intensity: f32,
+            pub position: Vec3,
+        }
+
+        pub struct Unknown {
+            pub sampler: Sampler,
+        }
+    };
+    let structs = get_struct_sizes(&file);
+    assert!(structs["Camera"] == 128);
+    // The position is aligned to 16 bytes after the intensity
+    assert!(structs["Light"] == 32);
+    assert!(structs["Scene"] == 128 + 64 + 16);
+    assert!(!structs.contains_key("Unknown"));
+}
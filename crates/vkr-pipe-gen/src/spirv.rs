@@ -0,0 +1,982 @@
+// Copyright © 2022
+// Author: Antonio Caggiano <info@antoniocaggiano.eu>
+// SPDX-License-Identifier: MIT
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{get_struct_sizes, inner_value, DescriptorType, Pipeline, Uniform};
+
+const MAGIC: u32 = 0x0723_0203;
+
+// Instructions read by the reflection, all the others only matter for the references of functions
+const OP_NAME: u32 = 5;
+const OP_ENTRY_POINT: u32 = 15;
+const OP_EXECUTION_MODE: u32 = 16;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_SPEC_CONSTANT_TRUE: u32 = 48;
+const OP_SPEC_CONSTANT_FALSE: u32 = 49;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_FUNCTION: u32 = 54;
+const OP_FUNCTION_END: u32 = 56;
+const OP_FUNCTION_CALL: u32 = 57;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_INPUT: u32 = 1;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_OUTPUT: u32 = 3;
+const STORAGE_PUSH_CONSTANT: u32 = 9;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+const EXECUTION_MODE_LOCAL_SIZE: u32 = 17;
+const EXECUTION_MODE_OUTPUT_VERTICES: u32 = 26;
+
+/// Types declared by a SPIR-V module, referring to other types by their id
+enum Type {
+    Bool,
+    Int {
+        width: u32,
+        signed: bool,
+    },
+    Float {
+        width: u32,
+    },
+    Vector {
+        component: u32,
+        count: u32,
+    },
+    Matrix {
+        column: u32,
+        count: u32,
+    },
+    Image {
+        dim: u32,
+        arrayed: bool,
+        sampled: u32,
+    },
+    Sampler,
+    SampledImage {
+        image: u32,
+    },
+    /// The length is the id of a constant
+    Array {
+        element: u32,
+        length: u32,
+    },
+    RuntimeArray {
+        element: u32,
+    },
+    Struct {
+        members: Vec<u32>,
+    },
+    Pointer {
+        pointee: u32,
+    },
+}
+
+struct EntryPoint {
+    model: u32,
+    function: u32,
+    name: String,
+    /// Input and output variables, as well as any global variable since SPIR-V 1.4
+    interface: Vec<u32>,
+}
+
+struct Variable {
+    id: u32,
+    pointer: u32,
+    storage_class: u32,
+}
+
+struct SpecConstant {
+    id: u32,
+    ty: Option<u32>,
+    /// Literal bits of the default value, or the value of a boolean
+    value: u32,
+}
+
+#[derive(Default)]
+struct Function {
+    /// Every id used by the instructions of the function
+    references: HashSet<u32>,
+    calls: Vec<u32>,
+}
+
+/// What pipewriter needs of a SPIR-V module, read instruction by instruction
+#[derive(Default)]
+struct Module {
+    names: HashMap<u32, String>,
+    entry_points: Vec<EntryPoint>,
+    local_sizes: HashMap<u32, [u32; 3]>,
+    output_vertices: HashMap<u32, u32>,
+    types: HashMap<u32, Type>,
+    constants: HashMap<u32, u32>,
+    spec_constants: Vec<SpecConstant>,
+    variables: Vec<Variable>,
+    /// First literal of the decorations of an id, or 0 for decorations without one
+    decorations: HashMap<(u32, u32), u32>,
+    /// Literals of the decorations of the members of structs, keyed by struct, member, and decoration
+    member_decorations: HashMap<(u32, u32, u32), u32>,
+    functions: HashMap<u32, Function>,
+}
+
+/// Reads a nul-terminated literal string, returning it with the number of words it takes
+fn parse_string(words: &[u32]) -> (String, usize) {
+    let mut bytes = vec![];
+    for (i, word) in words.iter().enumerate() {
+        for &byte in word.to_le_bytes().iter() {
+            if byte == 0 {
+                return (String::from_utf8_lossy(&bytes).into_owned(), i + 1);
+            }
+            bytes.push(byte);
+        }
+    }
+    (String::from_utf8_lossy(&bytes).into_owned(), words.len())
+}
+
+impl Module {
+    fn parse(code: &[u8]) -> Result<Self, String> {
+        let chunks = code.chunks_exact(4);
+        if !chunks.remainder().is_empty() || code.len() < 20 {
+            return Err("not a SPIR-V module".into());
+        }
+        let mut words: Vec<u32> = chunks
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        if words[0] == MAGIC.swap_bytes() {
+            words.iter_mut().for_each(|word| *word = word.swap_bytes());
+        } else if words[0] != MAGIC {
+            return Err("not a SPIR-V module".into());
+        }
+
+        let mut module = Self::default();
+        let mut function = None;
+
+        // Instructions follow the five words of the header
+        let mut index = 5;
+        while index < words.len() {
+            let count = (words[index] >> 16) as usize;
+            let opcode = words[index] & 0xffff;
+            if count == 0 || index + count > words.len() {
+                return Err(format!("truncated instruction at word {}", index));
+            }
+            let operands = &words[index + 1..index + count];
+            module.parse_instruction(opcode, operands, &mut function)?;
+            index += count;
+        }
+
+        Ok(module)
+    }
+
+    fn parse_instruction(
+        &mut self,
+        opcode: u32,
+        operands: &[u32],
+        function: &mut Option<u32>,
+    ) -> Result<(), String> {
+        let operand = |i: usize| {
+            operands
+                .get(i)
+                .copied()
+                .ok_or_else(|| format!("missing operands of instruction {}", opcode))
+        };
+
+        match opcode {
+            OP_NAME => {
+                let (name, _) = parse_string(&operands[1.min(operands.len())..]);
+                self.names.insert(operand(0)?, name);
+            }
+            OP_ENTRY_POINT => {
+                let (name, words) = parse_string(&operands[2.min(operands.len())..]);
+                self.entry_points.push(EntryPoint {
+                    model: operand(0)?,
+                    function: operand(1)?,
+                    name,
+                    interface: operands[(2 + words).min(operands.len())..].to_vec(),
+                });
+            }
+            OP_EXECUTION_MODE => match operand(1)? {
+                EXECUTION_MODE_LOCAL_SIZE => {
+                    let size = [operand(2)?, operand(3)?, operand(4)?];
+                    self.local_sizes.insert(operand(0)?, size);
+                }
+                EXECUTION_MODE_OUTPUT_VERTICES => {
+                    self.output_vertices.insert(operand(0)?, operand(2)?);
+                }
+                _ => (),
+            },
+            OP_TYPE_BOOL => {
+                self.types.insert(operand(0)?, Type::Bool);
+            }
+            OP_TYPE_INT => {
+                let ty = Type::Int {
+                    width: operand(1)?,
+                    signed: operand(2)? != 0,
+                };
+                self.types.insert(operand(0)?, ty);
+            }
+            OP_TYPE_FLOAT => {
+                let ty = Type::Float { width: operand(1)? };
+                self.types.insert(operand(0)?, ty);
+            }
+            OP_TYPE_VECTOR | OP_TYPE_MATRIX => {
+                let (inner, count) = (operand(1)?, operand(2)?);
+                let ty = if opcode == OP_TYPE_VECTOR {
+                    Type::Vector {
+                        component: inner,
+                        count,
+                    }
+                } else {
+                    Type::Matrix {
+                        column: inner,
+                        count,
+                    }
+                };
+                self.types.insert(operand(0)?, ty);
+            }
+            OP_TYPE_IMAGE => {
+                let ty = Type::Image {
+                    dim: operand(2)?,
+                    arrayed: operand(4)? != 0,
+                    sampled: operand(6)?,
+                };
+                self.types.insert(operand(0)?, ty);
+            }
+            OP_TYPE_SAMPLER => {
+                self.types.insert(operand(0)?, Type::Sampler);
+            }
+            OP_TYPE_SAMPLED_IMAGE => {
+                let ty = Type::SampledImage { image: operand(1)? };
+                self.types.insert(operand(0)?, ty);
+            }
+            OP_TYPE_ARRAY => {
+                let ty = Type::Array {
+                    element: operand(1)?,
+                    length: operand(2)?,
+                };
+                self.types.insert(operand(0)?, ty);
+            }
+            OP_TYPE_RUNTIME_ARRAY => {
+                let ty = Type::RuntimeArray {
+                    element: operand(1)?,
+                };
+                self.types.insert(operand(0)?, ty);
+            }
+            OP_TYPE_STRUCT => {
+                let ty = Type::Struct {
+                    members: operands[1.min(operands.len())..].to_vec(),
+                };
+                self.types.insert(operand(0)?, ty);
+            }
+            OP_TYPE_POINTER => {
+                let ty = Type::Pointer {
+                    pointee: operand(2)?,
+                };
+                self.types.insert(operand(0)?, ty);
+            }
+            OP_CONSTANT => {
+                self.constants.insert(operand(1)?, operand(2)?);
+            }
+            OP_SPEC_CONSTANT_TRUE | OP_SPEC_CONSTANT_FALSE | OP_SPEC_CONSTANT => {
+                let value = match opcode {
+                    OP_SPEC_CONSTANT => operand(2)?,
+                    OP_SPEC_CONSTANT_TRUE => 1,
+                    _ => 0,
+                };
+                let ty = if opcode == OP_SPEC_CONSTANT {
+                    Some(operand(0)?)
+                } else {
+                    None
+                };
+                self.spec_constants.push(SpecConstant {
+                    id: operand(1)?,
+                    ty,
+                    value,
+                });
+            }
+            OP_FUNCTION => {
+                let id = operand(1)?;
+                self.functions.entry(id).or_default();
+                *function = Some(id);
+            }
+            OP_FUNCTION_END => *function = None,
+            OP_VARIABLE if function.is_none() => self.variables.push(Variable {
+                id: operand(1)?,
+                pointer: operand(0)?,
+                storage_class: operand(2)?,
+            }),
+            OP_DECORATE => {
+                let value = operands.get(2).copied().unwrap_or_default();
+                self.decorations.insert((operand(0)?, operand(1)?), value);
+            }
+            OP_MEMBER_DECORATE => {
+                let value = operands.get(3).copied().unwrap_or_default();
+                let key = (operand(0)?, operand(1)?, operand(2)?);
+                self.member_decorations.insert(key, value);
+            }
+            _ => {
+                // Variables are referenced through their ids, wherever they appear
+                if let Some(function) = function.and_then(|f| self.functions.get_mut(&f)) {
+                    if opcode == OP_FUNCTION_CALL {
+                        function.calls.push(operand(2)?);
+                    }
+                    function.references.extend(operands);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn decoration(&self, id: u32, decoration: u32) -> Option<u32> {
+        self.decorations.get(&(id, decoration)).copied()
+    }
+
+    /// Returns the ids used by a function and by the functions it calls
+    fn get_references(&self, function: u32) -> HashSet<u32> {
+        let mut references = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut stack = vec![function];
+        while let Some(function) = stack.pop() {
+            if !visited.insert(function) {
+                continue;
+            }
+            if let Some(function) = self.functions.get(&function) {
+                references.extend(&function.references);
+                stack.extend(&function.calls);
+            }
+        }
+        references
+    }
+
+    /// Returns the size in bytes of a type, following the offsets and strides of its layout
+    fn get_size(&self, ty: u32) -> Option<usize> {
+        match self.types.get(&ty)? {
+            Type::Bool => Some(4),
+            Type::Int { width, .. } | Type::Float { width } => Some(*width as usize / 8),
+            Type::Vector { component, count } => Some(self.get_size(*component)? * *count as usize),
+            // Columns of matrices are aligned as vectors of four components
+            Type::Matrix { column, count } => {
+                let size = self.get_size(*column)?;
+                Some((size + (16 - size % 16) % 16) * *count as usize)
+            }
+            Type::Array { element, length } => {
+                let stride = match self.decoration(ty, DECORATION_ARRAY_STRIDE) {
+                    Some(stride) => stride as usize,
+                    None => self.get_size(*element)?,
+                };
+                Some(stride * *self.constants.get(length)? as usize)
+            }
+            Type::Struct { members } => {
+                let mut size = 0;
+                for (index, member) in members.iter().enumerate() {
+                    let key = |decoration| (ty, index as u32, decoration);
+                    let offset = match self.member_decorations.get(&key(DECORATION_OFFSET)) {
+                        Some(offset) => *offset as usize,
+                        None => size,
+                    };
+                    let member_size = match (
+                        self.types.get(member),
+                        self.member_decorations.get(&key(DECORATION_MATRIX_STRIDE)),
+                    ) {
+                        (Some(Type::Matrix { count, .. }), Some(stride)) => {
+                            *stride as usize * *count as usize
+                        }
+                        _ => self.get_size(*member)?,
+                    };
+                    size = size.max(offset + member_size);
+                }
+                Some(size)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the Rust type of a scalar, vector, or matrix input of a vertex shader
+    fn get_value_type(&self, ty: u32) -> Option<String> {
+        let name = match self.types.get(&ty)? {
+            Type::Float { width: 32 } => "f32".into(),
+            Type::Float { width: 64 } => "f64".into(),
+            Type::Int {
+                width: 32,
+                signed: true,
+            } => "i32".into(),
+            Type::Int {
+                width: 32,
+                signed: false,
+            } => "u32".into(),
+            Type::Vector { component, count } => {
+                let prefix = match self.types.get(component)? {
+                    Type::Float { width: 32 } => "",
+                    Type::Float { width: 64 } => "D",
+                    Type::Int { signed: true, .. } => "I",
+                    Type::Int { signed: false, .. } => "U",
+                    _ => return None,
+                };
+                format!("{}Vec{}", prefix, count)
+            }
+            Type::Matrix { count, .. } => format!("Mat{}", count),
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    /// Returns the Rust type rust-gpu would use for an image, a sampler, or an array of them
+    fn get_image_type(&self, ty: u32) -> Result<String, String> {
+        let name = match self.types.get(&ty) {
+            Some(Type::Image {
+                dim,
+                arrayed,
+                sampled,
+                ..
+            }) => match (dim, arrayed, sampled) {
+                (6, _, _) => "Image!(subpass, type = f32, sampled = false)".into(),
+                (0..=3, _, 2) => {
+                    let dim = ["1D", "2D", "3D", "cube"][*dim as usize];
+                    let arrayed = if *arrayed { ", arrayed" } else { "" };
+                    format!(
+                        "Image!({}, format = rgba8, sampled = false{})",
+                        dim, arrayed
+                    )
+                }
+                (1, true, _) => "Image2dArray".into(),
+                (0..=3, false, _) => {
+                    ["Image1d", "Image2d", "Image3d", "Cubemap"][*dim as usize].into()
+                }
+                _ => return Err(format!("image dimension {}", dim)),
+            },
+            Some(Type::Sampler) => "Sampler".into(),
+            Some(Type::SampledImage { image }) => {
+                format!("SampledImage<{}>", self.get_image_type(*image)?)
+            }
+            Some(Type::Array { element, length }) => {
+                let length = self
+                    .constants
+                    .get(length)
+                    .ok_or("arrays with a specialized length")?;
+                format!("[{}; {}]", self.get_image_type(*element)?, length)
+            }
+            Some(Type::RuntimeArray { element }) => {
+                format!("RuntimeArray<{}>", self.get_image_type(*element)?)
+            }
+            _ => return Err(format!("the type of id {}", ty)),
+        };
+        Ok(name)
+    }
+
+    /// Returns a snake case identifier for a variable, from its name or the name of its type
+    fn get_variable_name(&self, variable: &Variable, pointee: u32) -> Option<String> {
+        let names = [self.names.get(&variable.id), self.names.get(&pointee)];
+        let name = names.iter().flatten().find(|name| !name.is_empty())?;
+
+        let mut ident = String::with_capacity(name.len());
+        let mut lowercase = false;
+        for c in name.chars() {
+            if c.is_ascii_uppercase() && lowercase {
+                ident.push('_');
+            }
+            lowercase = c.is_ascii_lowercase() || c.is_ascii_digit();
+            ident.push(if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            });
+        }
+
+        // Names like `type.ubo` or `self` can not name arguments
+        syn::parse_str::<syn::Ident>(&ident).ok()?;
+        Some(ident)
+    }
+}
+
+/// Reflects the entry points of a SPIR-V module into the source of the Rust functions
+/// rust-gpu would compile it from, so that pipelines are collected as for shader crates.
+/// Entry points keep their names, so they need a stage suffix such as `main_vs`, and
+/// arguments are only declared for the variables used by each of them.
+/// Runtime arrays of descriptors take their count from `counts`, keyed by set and binding.
+pub fn reflect(code: &[u8], counts: &HashMap<(u32, u32), u32>) -> Result<String, String> {
+    let module = Module::parse(code)?;
+    let unsupported = |what: String| format!("SPIR-V reflection does not support {}", what);
+
+    let mut source = String::new();
+
+    // Structs back uniform buffers and push constants with the size of their block
+    let mut structs = HashSet::new();
+    let mut declare_struct = |source: &mut String, ty: u32| -> Result<String, String> {
+        let name = format!("SpirvStruct{}", ty);
+        if structs.insert(ty) {
+            let size = module
+                .get_size(ty)
+                .ok_or_else(|| unsupported(format!("the layout of struct {}", name)))?;
+            *source += &format!(
+                "pub struct {} {{ pub data: [Vec4; {}] }}\n",
+                name,
+                (size + (16 - size % 16) % 16) / 16
+            );
+        }
+        Ok(name)
+    };
+
+    for spec_constant in &module.spec_constants {
+        let constant_id = match module.decoration(spec_constant.id, DECORATION_SPEC_ID) {
+            Some(constant_id) => constant_id,
+            None => continue,
+        };
+        let (ty, default) = match spec_constant.ty.and_then(|ty| module.types.get(&ty)) {
+            None => ("bool", (spec_constant.value != 0).to_string()),
+            Some(Type::Int { width: 32, signed }) if *signed => {
+                ("i32", (spec_constant.value as i32).to_string())
+            }
+            Some(Type::Int { width: 32, .. }) => ("u32", spec_constant.value.to_string()),
+            Some(Type::Float { width: 32 }) => {
+                ("f32", format!("{:?}", f32::from_bits(spec_constant.value)))
+            }
+            _ => {
+                return Err(unsupported(format!(
+                    "specialization constant {}",
+                    constant_id
+                )))
+            }
+        };
+        let name = module
+            .names
+            .get(&spec_constant.id)
+            .map(|name| name.to_uppercase())
+            .filter(|name| syn::parse_str::<syn::Ident>(name).is_ok())
+            .unwrap_or_else(|| format!("SPEC_CONSTANT_{}", constant_id));
+        source += &format!(
+            "#[spirv(constant_id = {})] const {}: {} = {};\n",
+            constant_id, name, ty, default
+        );
+    }
+
+    // Arguments named after the variables, unless another binding already took the name
+    let mut names = HashMap::new();
+    let mut taken = HashSet::new();
+
+    for entry_point in &module.entry_points {
+        let stage = match entry_point.model {
+            0 => "vertex".into(),
+            1 => match module.output_vertices.get(&entry_point.function) {
+                Some(vertices) => format!("tessellation_control(output_vertices = {})", vertices),
+                None => "tessellation_control".into(),
+            },
+            2 => "tessellation_evaluation".into(),
+            3 => "geometry".into(),
+            4 => "fragment".into(),
+            5 => {
+                let [x, y, z] = module
+                    .local_sizes
+                    .get(&entry_point.function)
+                    .copied()
+                    .unwrap_or([1, 1, 1]);
+                format!("compute(threads({}, {}, {}))", x, y, z)
+            }
+            model => {
+                return Err(unsupported(format!(
+                    "execution model {} of {}",
+                    model, entry_point.name
+                )))
+            }
+        };
+        if syn::parse_str::<syn::Ident>(&entry_point.name).is_err() {
+            return Err(format!(
+                "entry point {} is not a valid function name",
+                entry_point.name
+            ));
+        }
+
+        let references = module.get_references(entry_point.function);
+        let mut args = vec![];
+
+        // Inputs and outputs are sorted by location, as vertex attributes follow the arguments
+        let mut locations = vec![];
+
+        for variable in &module.variables {
+            let used =
+                references.contains(&variable.id) || entry_point.interface.contains(&variable.id);
+            let pointee = match module.types.get(&variable.pointer) {
+                Some(Type::Pointer { pointee, .. }) if used => *pointee,
+                _ => continue,
+            };
+            let builtin = module
+                .decoration(variable.id, DECORATION_BUILT_IN)
+                .is_some();
+            let location = module.decoration(variable.id, DECORATION_LOCATION);
+
+            match variable.storage_class {
+                STORAGE_INPUT if entry_point.model == 0 && !builtin => {
+                    let ty = module.get_value_type(pointee).ok_or_else(|| {
+                        unsupported(format!("the type of vertex input {}", variable.id))
+                    })?;
+                    let location = location.unwrap_or_default();
+                    locations.push((location, format!("location_{}: {}", location, ty)));
+                }
+                STORAGE_OUTPUT if entry_point.model == 4 && !builtin => {
+                    // Arrays of outputs take a color attachment per element
+                    let count = match module.types.get(&pointee) {
+                        Some(Type::Array { length, .. }) => {
+                            module.constants.get(length).copied().unwrap_or(1)
+                        }
+                        _ => 1,
+                    };
+                    let location = location.unwrap_or_default();
+                    for i in 0..count {
+                        let arg = format!("output_{}: &mut Vec4", location + i);
+                        locations.push((location + i, arg));
+                    }
+                }
+                STORAGE_UNIFORM_CONSTANT
+                | STORAGE_UNIFORM
+                | STORAGE_STORAGE_BUFFER
+                | STORAGE_PUSH_CONSTANT => {
+                    let set = module.decoration(variable.id, DECORATION_DESCRIPTOR_SET);
+                    let binding = module.decoration(variable.id, DECORATION_BINDING);
+                    let name = names.entry(variable.id).or_insert_with(|| {
+                        let name = module
+                            .get_variable_name(variable, pointee)
+                            .filter(|name| !taken.contains(name))
+                            .unwrap_or_else(|| match (set, binding) {
+                                (Some(set), Some(binding)) => {
+                                    format!("set_{}_binding_{}", set, binding)
+                                }
+                                _ => format!("variable_{}", variable.id),
+                            });
+                        taken.insert(name.clone());
+                        name
+                    });
+
+                    let block = module.decoration(pointee, DECORATION_BLOCK).is_some();
+                    let buffer_block = module
+                        .decoration(pointee, DECORATION_BUFFER_BLOCK)
+                        .is_some();
+                    let arg = match (variable.storage_class, set, binding) {
+                        (STORAGE_PUSH_CONSTANT, _, _) => {
+                            let ty = declare_struct(&mut source, pointee)?;
+                            format!("#[spirv(push_constant)] {}: &{}", name, ty)
+                        }
+                        (_, Some(set), Some(binding)) => {
+                            let spirv = format!("descriptor_set = {}, binding = {}", set, binding);
+                            let storage = variable.storage_class == STORAGE_STORAGE_BUFFER
+                                || (variable.storage_class == STORAGE_UNIFORM && buffer_block);
+                            if storage {
+                                format!("#[spirv(storage_buffer, {})] {}: &[u32]", spirv, name)
+                            } else if block {
+                                let ty = declare_struct(&mut source, pointee)?;
+                                format!("#[spirv(uniform, {})] {}: &{}", spirv, name, ty)
+                            } else {
+                                let ty = module.get_image_type(pointee).map_err(|what| {
+                                    unsupported(format!("{} of descriptor {}", what, name))
+                                })?;
+                                let count = match module.types.get(&pointee) {
+                                    Some(Type::RuntimeArray { .. }) => {
+                                        let count = counts.get(&(set, binding)).ok_or_else(|| {
+                                            unsupported(format!(
+                                                "runtime arrays of descriptors without a count, such as {}",
+                                                name
+                                            ))
+                                        })?;
+                                        format!("#[pipewriter(count = {})] ", count)
+                                    }
+                                    _ => String::new(),
+                                };
+                                format!("#[spirv({})] {}{}: &{}", spirv, count, name, ty)
+                            }
+                        }
+                        _ => continue,
+                    };
+                    args.push(arg);
+                }
+                _ => (),
+            }
+        }
+
+        locations.sort_by_key(|(location, _)| *location);
+        args.extend(locations.into_iter().map(|(_, arg)| arg));
+
+        source += &format!(
+            "#[spirv({})]\npub fn {}({}) {{}}\n",
+            stage,
+            entry_point.name,
+            args.join(", ")
+        );
+    }
+
+    Ok(source)
+}
+
+/// Replaces the uniforms parsed from the attributes of a shader crate with the ones reflected from
+/// the SPIR-V it compiles to, which only has the bindings the shaders use, with their actual stages
+/// and layouts. Names, binding flags, and dynamic offsets only exist in the source, so they are
+/// kept for the bindings found in both, while the counts of runtime arrays come from the source.
+pub fn reflect_uniforms(pipelines: &mut [Pipeline], code: &[u8]) -> Result<(), String> {
+    let counts = pipelines
+        .iter()
+        .flat_map(|pipeline| &pipeline.uniforms)
+        .map(|uniform| ((uniform.descriptor_set, uniform.binding), uniform.count))
+        .collect();
+    let source = reflect(code, &counts)?;
+    let file = syn::parse_file(&source).map_err(|err| err.to_string())?;
+    let struct_sizes = get_struct_sizes(&file);
+    let items = crate::get_items(&file.items);
+    let functions: HashMap<String, &syn::ItemFn> = items
+        .iter()
+        .filter_map(|item| inner_value!(item, syn::Item::Fn(f) => f))
+        .map(|func| (func.sig.ident.to_string(), func))
+        .collect();
+
+    for pipeline in pipelines {
+        let mut reflected: Vec<Uniform> = vec![];
+        for function in pipeline.entry_points.values() {
+            let func = functions
+                .get(function)
+                .ok_or_else(|| format!("missing entry point {}", function))?;
+            let uniforms =
+                crate::get_uniforms(func, &struct_sizes).map_err(|err| err.to_string())?;
+            for uniform in uniforms {
+                let existing = reflected.iter_mut().find(|u| {
+                    u.descriptor_set == uniform.descriptor_set && u.binding == uniform.binding
+                });
+                match existing {
+                    Some(existing) => existing.stages.extend(uniform.stages),
+                    None => reflected.push(uniform),
+                }
+            }
+        }
+        for uniform in &mut reflected {
+            uniform.stages.sort();
+            uniform.stages.dedup();
+        }
+
+        // Bindings keep the order of the source, followed by the ones only found by reflection
+        let mut uniforms = vec![];
+        for parsed in pipeline.uniforms.drain(..) {
+            let index = reflected.iter().position(|u| {
+                u.descriptor_set == parsed.descriptor_set && u.binding == parsed.binding
+            });
+            if let Some(index) = index {
+                let mut uniform = reflected.remove(index);
+                if parsed.descriptor_type == DescriptorType::UniformBufferDynamic
+                    && uniform.descriptor_type == DescriptorType::UniformBuffer
+                {
+                    uniform.descriptor_type = DescriptorType::UniformBufferDynamic;
+                }
+                if parsed.descriptor_type == uniform.descriptor_type {
+                    uniform.ty = parsed.ty;
+                }
+                uniform.name = parsed.name;
+                uniform.binding_flags = parsed.binding_flags;
+                uniforms.push(uniform);
+            }
+        }
+        uniforms.append(&mut reflected);
+        pipeline.uniforms = uniforms;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+fn instruction(opcode: u32, operands: &[u32]) -> Vec<u32> {
+    let mut words = vec![((operands.len() as u32 + 1) << 16) | opcode];
+    words.extend(operands);
+    words
+}
+
+#[cfg(test)]
+fn literal_string(string: &str) -> Vec<u32> {
+    let mut bytes = string.as_bytes().to_vec();
+    bytes.resize(bytes.len() / 4 * 4 + 4, 0);
+    bytes
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+/// Assembles the module of a GLSL pipeline drawing a textured mesh
+#[cfg(test)]
+fn textured_module() -> Vec<u8> {
+    let named =
+        |id: u32, name: &str| instruction(OP_NAME, &[&[id][..], &literal_string(name)].concat());
+    let entry_point = |model: u32, function: u32, name: &str, interface: &[u32]| {
+        let operands = [&[model, function][..], &literal_string(name), interface].concat();
+        instruction(OP_ENTRY_POINT, &operands)
+    };
+    const OP_TYPE_VOID: u32 = 19;
+    const OP_TYPE_FUNCTION: u32 = 33;
+    const OP_LABEL: u32 = 248;
+    const OP_LOAD: u32 = 61;
+    const OP_RETURN: u32 = 253;
+
+    let instructions = vec![
+        instruction(17, &[1]),
+        instruction(14, &[0, 1]),
+        entry_point(0, 30, "main_vs", &[12, 14, 16]),
+        entry_point(4, 40, "main_fs", &[21]),
+        named(8, "Camera"),
+        named(10, "camera"),
+        named(20, "albedoMap"),
+        named(24, ""),
+        named(22, "Push"),
+        named(25, "exposure"),
+        instruction(OP_DECORATE, &[10, DECORATION_DESCRIPTOR_SET, 0]),
+        instruction(OP_DECORATE, &[10, DECORATION_BINDING, 0]),
+        instruction(OP_DECORATE, &[8, DECORATION_BLOCK]),
+        instruction(OP_MEMBER_DECORATE, &[8, 0, DECORATION_OFFSET, 0]),
+        instruction(OP_MEMBER_DECORATE, &[8, 0, DECORATION_MATRIX_STRIDE, 16]),
+        instruction(OP_DECORATE, &[12, DECORATION_LOCATION, 0]),
+        instruction(OP_DECORATE, &[14, DECORATION_LOCATION, 1]),
+        instruction(OP_DECORATE, &[16, DECORATION_BUILT_IN, 0]),
+        instruction(OP_DECORATE, &[20, DECORATION_DESCRIPTOR_SET, 1]),
+        instruction(OP_DECORATE, &[20, DECORATION_BINDING, 0]),
+        instruction(OP_DECORATE, &[21, DECORATION_LOCATION, 0]),
+        instruction(OP_DECORATE, &[22, DECORATION_BLOCK]),
+        instruction(OP_MEMBER_DECORATE, &[22, 0, DECORATION_OFFSET, 0]),
+        instruction(OP_DECORATE, &[25, DECORATION_SPEC_ID, 3]),
+        instruction(OP_TYPE_VOID, &[1]),
+        instruction(OP_TYPE_FUNCTION, &[2, 1]),
+        instruction(OP_TYPE_FLOAT, &[3, 32]),
+        instruction(OP_TYPE_VECTOR, &[4, 3, 2]),
+        instruction(OP_TYPE_VECTOR, &[5, 3, 3]),
+        instruction(OP_TYPE_VECTOR, &[6, 3, 4]),
+        instruction(OP_TYPE_MATRIX, &[7, 6, 4]),
+        instruction(OP_TYPE_STRUCT, &[8, 7]),
+        instruction(OP_TYPE_POINTER, &[9, STORAGE_UNIFORM, 8]),
+        instruction(OP_VARIABLE, &[9, 10, STORAGE_UNIFORM]),
+        instruction(OP_TYPE_POINTER, &[11, STORAGE_INPUT, 5]),
+        instruction(OP_VARIABLE, &[11, 12, STORAGE_INPUT]),
+        instruction(OP_TYPE_POINTER, &[13, STORAGE_INPUT, 4]),
+        instruction(OP_VARIABLE, &[13, 14, STORAGE_INPUT]),
+        instruction(OP_TYPE_POINTER, &[15, STORAGE_OUTPUT, 6]),
+        instruction(OP_VARIABLE, &[15, 16, STORAGE_OUTPUT]),
+        instruction(OP_TYPE_IMAGE, &[17, 3, 1, 0, 0, 0, 1, 0]),
+        instruction(OP_TYPE_SAMPLED_IMAGE, &[18, 17]),
+        instruction(OP_TYPE_POINTER, &[19, STORAGE_UNIFORM_CONSTANT, 18]),
+        instruction(OP_VARIABLE, &[19, 20, STORAGE_UNIFORM_CONSTANT]),
+        instruction(OP_VARIABLE, &[15, 21, STORAGE_OUTPUT]),
+        instruction(OP_TYPE_STRUCT, &[22, 3]),
+        instruction(OP_TYPE_POINTER, &[23, STORAGE_PUSH_CONSTANT, 22]),
+        instruction(OP_VARIABLE, &[23, 24, STORAGE_PUSH_CONSTANT]),
+        instruction(OP_SPEC_CONSTANT, &[3, 25, 1.5f32.to_bits()]),
+        // The vertex shader transforms positions by the camera
+        instruction(OP_FUNCTION, &[1, 30, 0, 2]),
+        instruction(OP_LABEL, &[31]),
+        instruction(OP_LOAD, &[8, 50, 10]),
+        instruction(OP_LOAD, &[5, 51, 12]),
+        instruction(OP_LOAD, &[4, 52, 14]),
+        instruction(OP_RETURN, &[]),
+        instruction(OP_FUNCTION_END, &[]),
+        // The fragment shader samples the albedo through a helper function
+        instruction(OP_FUNCTION, &[1, 40, 0, 2]),
+        instruction(OP_LABEL, &[41]),
+        instruction(OP_FUNCTION_CALL, &[1, 53, 60]),
+        instruction(OP_LOAD, &[22, 54, 24]),
+        instruction(OP_RETURN, &[]),
+        instruction(OP_FUNCTION_END, &[]),
+        instruction(OP_FUNCTION, &[1, 60, 0, 2]),
+        instruction(OP_LABEL, &[61]),
+        instruction(OP_LOAD, &[18, 62, 20]),
+        instruction(OP_RETURN, &[]),
+        instruction(OP_FUNCTION_END, &[]),
+    ];
+
+    let header = vec![MAGIC, 0x0001_0000, 0, 100, 0];
+    [header, instructions.concat()]
+        .concat()
+        .iter()
+        .flat_map(|word| word.to_le_bytes().to_vec())
+        .collect()
+}
+
+#[test]
+fn reflect_module() {
+    let source = reflect(&textured_module(), &HashMap::new()).unwrap();
+    assert!(source.contains("pub struct SpirvStruct8 { pub data: [Vec4; 4] }"));
+    assert!(source.contains("#[spirv(constant_id = 3)] const EXPOSURE: f32 = 1.5;"));
+    assert!(source.contains("#[spirv(vertex)]\npub fn main_vs(#[spirv(uniform, descriptor_set = 0, binding = 0)] camera: &SpirvStruct8, location_0: Vec3, location_1: Vec2) {}"));
+    assert!(source.contains("#[spirv(fragment)]\npub fn main_fs(#[spirv(descriptor_set = 1, binding = 0)] albedo_map: &SampledImage<Image2d>, #[spirv(push_constant)] push: &SpirvStruct22, output_0: &mut Vec4) {}"));
+
+    let file = syn::parse_file(&source).unwrap();
+    let pipelines = crate::get_pipelines(&file).unwrap();
+    assert!(pipelines.len() == 1);
+    let main = &pipelines[0];
+    assert!(main.name == "Main");
+    assert!(main
+        .inputs
+        .iter()
+        .map(|i| i.ty.to_string())
+        .eq(["Vec3", "Vec2"].iter().copied()));
+    assert!(main.color_outputs == 1);
+    assert!(main.uniforms.len() == 2);
+    assert!(main.uniforms[0].name == "camera" && main.uniforms[0].range == Some(64));
+    assert!(main.uniforms[0].stages == [crate::ShaderType::Vertex]);
+    assert!(main.uniforms[1].descriptor_type == crate::DescriptorType::CombinedImageSampler);
+    assert!(main.uniforms[1].stages == [crate::ShaderType::Fragment]);
+    assert!(main.push_constants.len() == 1 && main.push_constants[0].size == 16);
+    assert!(main.spec_constants.len() == 1 && main.spec_constants[0].name == "exposure");
+}
+
+#[test]
+fn reflect_errors() {
+    assert!(reflect(b"#version 450", &HashMap::new()).is_err());
+
+    // An instruction claiming more words than the module has left
+    let mut module = textured_module();
+    module.extend(&((3 << 16) | OP_NAME).to_le_bytes());
+    let err = reflect(&module, &HashMap::new()).unwrap_err();
+    assert!(err.starts_with("truncated instruction"), "{}", err);
+}
+
+#[test]
+fn reflect_crate_uniforms() {
+    // Attributes of the source disagree with what the shaders compiled to
+    let file = syn::parse_quote! {
+        #[spirv(vertex)]
+        pub fn main_vs(
+            #[spirv(uniform, descriptor_set = 0, binding = 0)]
+            #[pipewriter(dynamic)]
+            view: &Mat4,
+            #[spirv(descriptor_set = 1, binding = 0)] albedo: &SampledImage<Image2d>,
+            position: Vec3,
+            uv: Vec2,
+        ) {}
+
+        #[spirv(fragment)]
+        pub fn main_fs(#[spirv(uniform, descriptor_set = 2, binding = 0)] unused: &Vec4, output: &mut Vec4) {}
+    };
+    let mut pipelines = crate::get_pipelines(&file).unwrap();
+    assert!(pipelines[0].uniforms.len() == 3);
+
+    reflect_uniforms(&mut pipelines, &textured_module()).unwrap();
+    let uniforms = &pipelines[0].uniforms;
+    assert!(uniforms.len() == 2);
+    assert!(uniforms[0].name == "view" && uniforms[0].range == Some(64));
+    assert!(uniforms[0].descriptor_type == DescriptorType::UniformBufferDynamic);
+    assert!(uniforms[1].name == "albedo");
+    assert!(uniforms[1].stages == [crate::ShaderType::Fragment]);
+}
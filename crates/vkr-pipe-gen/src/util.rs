@@ -0,0 +1,154 @@
+// Copyright © 2021-2022
+// Author: Antonio Caggiano <info@antoniocaggiano.eu>
+// SPDX-License-Identifier: MIT
+
+pub trait Camelcase {
+    /// Converts a string to camelcase, removing all `-` and `_` characters
+    /// and capitalizing the character following them, as well as the first one.
+    /// Digits and uppercase characters are kept as they are, so `ssao2_blur`
+    /// becomes `Ssao2Blur` and `hdr_SSAO` becomes `HdrSSAO`.
+    fn to_camelcase(self) -> String;
+}
+
+impl Camelcase for &str {
+    fn to_camelcase(self) -> String {
+        let mut name = String::with_capacity(self.len());
+        let mut capitalize = true;
+
+        for c in self.chars() {
+            if c == '-' || c == '_' {
+                capitalize = true;
+            } else if capitalize {
+                name.extend(c.to_uppercase());
+                capitalize = false;
+            } else {
+                name.push(c);
+            }
+        }
+
+        name
+    }
+}
+
+#[test]
+fn test_to_camelcase() {
+    assert!("main".to_camelcase() == "Main");
+    assert!("main_method".to_camelcase() == "MainMethod");
+    assert!("simple-shader".to_camelcase() == "SimpleShader");
+    assert!("ssao2_blur".to_camelcase() == "Ssao2Blur");
+    assert!("blur_2x".to_camelcase() == "Blur2x");
+    assert!("hdr_SSAO".to_camelcase() == "HdrSSAO");
+    assert!("2d_blur".to_camelcase() == "2dBlur");
+    assert!("main__method".to_camelcase() == "MainMethod");
+    assert!("_main-_method_".to_camelcase() == "MainMethod");
+    assert!("".to_camelcase().is_empty());
+}
+
+/// Resolves the `.` and `..` components of a path without touching the file system,
+/// so that error messages show where a path leads even when it does not exist
+pub fn normalize_path(path: &std::path::Path) -> std::path::PathBuf {
+    let mut ret = std::path::PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => (),
+            std::path::Component::ParentDir if ret.file_name().is_some() => {
+                ret.pop();
+            }
+            component => ret.push(component),
+        }
+    }
+    ret
+}
+
+#[test]
+fn test_normalize_path() {
+    let path = normalize_path(std::path::Path::new(
+        "/work/target/tests/../../crates/./shader",
+    ));
+    assert!(path == std::path::Path::new("/work/crates/shader"));
+    let path = normalize_path(std::path::Path::new("../shader"));
+    assert!(path == std::path::Path::new("../shader"));
+}
+
+/// Returns the items of a file, followed by the ones nested in its inline modules
+/// such as `mod post { .. }`, at any depth
+pub fn get_items(items: &[syn::Item]) -> Vec<&syn::Item> {
+    let mut ret: Vec<&syn::Item> = items.iter().collect();
+    for item in items {
+        if let syn::Item::Mod(syn::ItemMod {
+            content: Some((_, module_items)),
+            ..
+        }) = item
+        {
+            ret.extend(get_items(module_items));
+        }
+    }
+    ret
+}
+
+#[test]
+fn test_get_items() {
+    let file: syn::File = syn::parse_quote! {
+        fn main_vs() {}
+        mod post {
+            fn blur_cs() {}
+            mod tonemap {
+                fn tonemap_fs() {}
+            }
+        }
+        mod declared;
+    };
+    let functions: Vec<String> = get_items(&file.items)
+        .into_iter()
+        .filter_map(|item| match item {
+            syn::Item::Fn(f) => Some(f.sig.ident.to_string()),
+            _ => None,
+        })
+        .collect();
+    assert!(functions == ["main_vs", "blur_cs", "tonemap_fs"]);
+}
+
+/// Suffixes telling the stage of a shader function, such as `vs` in `main_vs`
+const STAGE_SUFFIXES: &[&str] = &[
+    "vs", "tcs", "tes", "gs", "fs", "cs", "vert", "tesc", "tese", "geom", "frag", "comp", "vertex",
+    "fragment", "geometry", "compute",
+];
+
+/// This function returns the prefix of `name`, which is the
+/// name of a shader function without its stage suffix, such as `vs` or `fs`,
+/// or `None` when there is no prefix or no known stage suffix
+pub fn get_prefix(name: &str) -> Option<String> {
+    let (prefix, suffix) = name.rsplit_once('_')?;
+    if prefix.is_empty() || !STAGE_SUFFIXES.contains(&suffix) {
+        return None;
+    }
+    Some(prefix.to_string())
+}
+
+#[test]
+fn test_get_prefix() {
+    let prefix = get_prefix("main_method_vs");
+    assert!(prefix.as_deref() == Some("main_method"));
+    assert!(get_prefix("deferred_light_fs").as_deref() == Some("deferred_light"));
+    assert!(get_prefix("main_fs").as_deref() == Some("main"));
+    assert!(get_prefix("blur_compute").as_deref() == Some("blur"));
+    assert!(get_prefix("main").is_none());
+    assert!(get_prefix("deferred_light").is_none());
+    assert!(get_prefix("main_vs_").is_none());
+    assert!(get_prefix("_vs").is_none());
+}
+
+/// Returns `Some(v)` with the inner value of an enum or `None`
+/// ```rust
+/// inner_value!(enum_value, EnumType(value) => value);
+/// ```
+macro_rules! inner_value {
+    ($value:expr, $pattern:pat => $extracted_value:expr) => {
+        match $value {
+            $pattern => Some($extracted_value),
+            _ => None,
+        }
+    };
+}
+
+pub(crate) use inner_value;
@@ -11,5 +11,11 @@ fn main() -> Result<(), Box<dyn Error>> {
         .print_metadata(MetadataPrintout::Full)
         .capability(Capability::InputAttachment)
         .build()?;
+    SpirvBuilder::new("shader/compute", "spirv-unknown-vulkan1.1")
+        .print_metadata(MetadataPrintout::Full)
+        .build()?;
+    SpirvBuilder::new("shader/multiview", "spirv-unknown-vulkan1.1")
+        .print_metadata(MetadataPrintout::Full)
+        .build()?;
     Ok(())
 }
@@ -0,0 +1,21 @@
+// Copyright © 2022
+// Author: Antonio Caggiano <info@antoniocaggiano.eu>
+// SPDX-License-Identifier: MIT
+
+#![cfg_attr(
+    target_arch = "spirv",
+    feature(register_attr),
+    register_attr(spirv, pipewriter),
+    no_std
+)]
+#![deny(warnings)]
+
+use spirv_std::glam::{UVec3, Vec4};
+
+#[spirv(compute(threads(64)))]
+pub fn blur_cs(
+    #[spirv(global_invocation_id)] _id: UVec3,
+    #[spirv(uniform, descriptor_set = 0, binding = 0)] _params: &Vec4,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] _pixels: &mut [Vec4],
+) {
+}
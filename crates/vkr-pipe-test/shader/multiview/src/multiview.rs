@@ -0,0 +1,25 @@
+// Copyright © 2022
+// Author: Antonio Caggiano <info@antoniocaggiano.eu>
+// SPDX-License-Identifier: MIT
+
+#![cfg_attr(
+    target_arch = "spirv",
+    feature(register_attr),
+    register_attr(spirv, pipewriter),
+    no_std
+)]
+#![deny(warnings)]
+
+use spirv_std::glam::{vec4, Vec3, Vec4};
+
+// The view mask of a subpass has 32 bits, so this is the largest view count
+#[spirv(vertex)]
+pub fn cube_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {
+    *out_pos = vec4(in_pos.x, in_pos.y, in_pos.z, 1.0);
+}
+
+#[pipewriter(views = 32)]
+#[spirv(fragment)]
+pub fn cube_fs(out_color: &mut Vec4) {
+    *out_color = vec4(1.0, 0.0, 0.0, 1.0)
+}
@@ -5,7 +5,7 @@
 #![cfg_attr(
     target_arch = "spirv",
     feature(register_attr),
-    register_attr(spirv),
+    register_attr(spirv, pipewriter),
     no_std
 )]
 #![deny(warnings)]
@@ -0,0 +1,26 @@
+// Copyright © 2022
+// Author: Antonio Caggiano <info@antoniocaggiano.eu>
+// SPDX-License-Identifier: MIT
+
+use vkr_core::Ctx;
+use vkr_pipe::pipewriter;
+
+pipewriter!("shader/compute");
+
+#[test]
+fn build_compute_pipeline() {
+    let ctx = Ctx::builder().debug(true).build();
+    let dev = Dev::new(&ctx, None);
+
+    let mut cache = PipelineCache::new(&dev);
+    let mut blur = PipelineBlur::new(cache.get_shader_module());
+    assert!(PipelineBlur::WORKGROUP_SIZE == [64, 1, 1]);
+    assert!(blur.get_name() == "Blur");
+    assert!(blur.get_pipeline() != ash::vk::Pipeline::null());
+
+    let set_layouts = blur.get_set_layouts().to_vec();
+    let sets = blur.get_cache(0).allocate(&set_layouts);
+    assert!(sets.len() == 1);
+
+    dev.wait();
+}
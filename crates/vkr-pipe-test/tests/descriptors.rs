@@ -0,0 +1,31 @@
+// Copyright © 2022
+// Author: Antonio Caggiano <info@antoniocaggiano.eu>
+// SPDX-License-Identifier: MIT
+
+use vkr_core::{Ctx, Dev};
+use vkr_pipe::pipewriter;
+
+pipewriter!("shader/simple", module = "sets", descriptors_only = true);
+
+use sets::PipelineUniform;
+
+#[test]
+fn build_descriptors_only() {
+    let ctx = Ctx::builder().debug(true).build();
+    let dev = Dev::new(&ctx, None);
+
+    let mut uniform = PipelineUniform::new(&dev.device);
+    assert!(uniform.get_name() == "Uniform");
+    assert!(uniform.get_layout() != ash::vk::PipelineLayout::null());
+
+    let set_layouts = uniform.get_set_layouts().to_vec();
+    let sets = uniform.get_cache(0).allocate(&set_layouts);
+
+    let view_buffer = vkr_core::Buffer::new::<[f32; 16]>(
+        &dev.allocator,
+        ash::vk::BufferUsageFlags::UNIFORM_BUFFER,
+    );
+    uniform.write_set_0(sets[0], &view_buffer);
+
+    dev.wait();
+}
@@ -0,0 +1,49 @@
+// Copyright © 2022
+// Author: Antonio Caggiano <info@antoniocaggiano.eu>
+// SPDX-License-Identifier: MIT
+
+use vkr_pipe::pipewriter;
+
+pipewriter!("shader/simple", module = "pipelines", reverse_z = false);
+
+use vkr_core::{Frame, Model, Node};
+use vkr_util::Handle;
+
+impl pipelines::PipelineUniform {
+    fn bind_impl(&self, _frame: &mut Frame, _model: &Model, _node: Handle<Node>) {}
+
+    fn draw_impl(&self, _frame: &mut Frame, _model: &Model, _node: Handle<Node>) {}
+}
+
+impl pipelines::PipelineMain {
+    fn bind_impl(&self, _frame: &mut Frame, _model: &Model, _node: Handle<Node>) {}
+
+    fn draw_impl(&self, _frame: &mut Frame, _model: &Model, _node: Handle<Node>) {}
+}
+
+impl pipelines::PipelineSecondary {
+    fn bind_impl(&self, _frame: &mut Frame, _model: &Model, _node: Handle<Node>) {}
+
+    fn draw_impl(&self, _frame: &mut Frame, _model: &Model, _node: Handle<Node>) {}
+}
+
+#[test]
+fn nested_module() {
+    assert!(pipelines::PipelineMain::VERTEX_STRIDE == 12);
+    assert!(pipelines::PipelineSecondary::VERTEX_STRIDE == 20);
+    assert!(matches!(
+        pipelines::ShaderSimpleShader::Main,
+        pipelines::ShaderSimpleShader::Main
+    ));
+}
+
+#[test]
+fn standard_depth_range() {
+    let viewport = pipelines::PipelineMain::viewport(1920.0, 1080.0);
+    assert!(viewport.min_depth == 0.0);
+    assert!(viewport.max_depth == 1.0);
+
+    let scissor = pipelines::PipelineMain::scissor(1920, 1080);
+    assert!(scissor.extent.width == 1920);
+    assert!(scissor.extent.height == 1080);
+}
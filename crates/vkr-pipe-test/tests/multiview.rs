@@ -0,0 +1,22 @@
+// Copyright © 2022
+// Author: Antonio Caggiano <info@antoniocaggiano.eu>
+// SPDX-License-Identifier: MIT
+
+use vkr_core::{Frame, Model, Node};
+use vkr_pipe::pipewriter;
+use vkr_util::Handle;
+
+pipewriter!("shader/multiview", module = "pipelines");
+
+impl pipelines::PipelineCube {
+    fn bind_impl(&self, _frame: &mut Frame, _model: &Model, _node: Handle<Node>) {}
+
+    fn draw_impl(&self, _frame: &mut Frame, _model: &Model, _node: Handle<Node>) {}
+}
+
+#[test]
+fn all_views() {
+    assert!(pipelines::PipelineCube::VIEW_COUNT == 32);
+    assert!(pipelines::PipelineCube::VIEW_MASK == u32::MAX);
+    pipelines::PipelineCube::check_view_mask(u32::MAX);
+}
@@ -7,7 +7,7 @@ use std::any::Any;
 use vkr_core::{Buffer, Ctx, DescriptorPool, Image, ImageView, Sampler};
 use vkr_pipe::*;
 
-pipewriter!("crates/vkr-pipe-test/shader/simple");
+pipewriter!("shader/simple");
 
 impl PipelineUniform {
     fn bind_impl(&self, _frame: &mut Frame, _model: &Model, _node: Handle<Node>) {
@@ -54,6 +54,11 @@ fn build_simple_shader() {
     let ctx = Ctx::builder().debug(true).build();
     let dev = Dev::new(&ctx, None);
 
+    // The simple shader does not need any optional feature
+    let features = PipelineCache::required_features();
+    assert!(features.depth_clamp == vk::FALSE);
+    assert!(PipelineCache::required_extensions().is_empty());
+
     let mut cache = PipelineCache::new(&dev);
 
     let main_pipeline = cache.get(ShaderSimpleShader::Main);
@@ -68,6 +73,11 @@ fn build_simple_shader() {
 
     let uniform_pipeline = as_uni(uniform_pipeline.as_any_mut());
 
+    // View, transform, material, and input attachment sets
+    assert!(PipelineUniform::set_count() == 4);
+    assert!(PipelineUniform::binding_count(2) == 2);
+    assert!(PipelineUniform::binding_count(4) == 0);
+
     let mut pool = DescriptorPool::new(&dev.device, 4, 3, 1, 2);
     let sets = pool.allocate(&uniform_pipeline.set_layouts);
 
@@ -90,7 +100,133 @@ fn build_simple_shader() {
     let albedo = Texture::new(white_view.view, white_sampler.sampler);
     uniform_pipeline.write_set_2(sets[2], &color_buffer, &albedo);
 
-    let _cache = uniform_pipeline.get_cache(0);
+    let set_layouts = uniform_pipeline.set_layouts.clone();
+    let cache = uniform_pipeline.get_cache(0);
+
+    // Allocate more sets than the default pool can hold
+    let uniform_pool_size = vk::DescriptorPoolSize::builder()
+        .descriptor_count(128)
+        .ty(vk::DescriptorType::UNIFORM_BUFFER)
+        .build();
+    let sampler_pool_size = vk::DescriptorPoolSize::builder()
+        .descriptor_count(64)
+        .ty(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .build();
+    let input_pool_size = vk::DescriptorPoolSize::builder()
+        .descriptor_count(64)
+        .ty(vk::DescriptorType::INPUT_ATTACHMENT)
+        .build();
+    cache.resize(&[uniform_pool_size, sampler_pool_size, input_pool_size], 64);
+    for _ in 0..8 {
+        let sets = cache.allocate(&set_layouts);
+        assert!(sets.len() == set_layouts.len());
+    }
+
+    // Pools for 3 frames in flight hold 3 copies of each descriptor
+    let pool_sizes = PipelineCacheUniform::default_pool_sizes(1);
+    let tripled_sizes = PipelineCacheUniform::default_pool_sizes(3);
+    for (size, tripled) in pool_sizes.iter().zip(&tripled_sizes) {
+        assert!(tripled.descriptor_count == size.descriptor_count * 3);
+    }
+    assert!(
+        PipelineCacheUniform::default_max_sets(3) == PipelineCacheUniform::default_max_sets(1) * 3
+    );
+    let mut frames_cache = PipelineCacheUniform::with_frames_in_flight(&dev.device, 3);
+    let sets = frames_cache.allocate(&set_layouts);
+    assert!(sets.len() == set_layouts.len());
+
+    dev.wait();
+}
+
+#[test]
+#[should_panic(expected = "exceeds maxVertexInputBindingStride")]
+fn vertex_stride_limit() {
+    // A limited device which can not even fit a position
+    let limits = vk::PhysicalDeviceLimits {
+        max_vertex_input_binding_stride: 8,
+        ..Default::default()
+    };
+    PipelineMain::check_limits(&limits);
+}
+
+#[test]
+fn build_cache_with_options() {
+    let ctx = Ctx::builder().debug(true).build();
+    let dev = Dev::new(&ctx, None);
+
+    let pass = Pass::new(&dev);
+    let mut cache = PipelineCache::builder(&dev)
+        .render_pass(pass.render)
+        .frames_in_flight(3)
+        .sets_per_frame(4)
+        .eager(true)
+        .build();
+    assert!(cache.get_pass().is_none());
+    assert!(cache.get_render_pass() == pass.render);
+
+    let uniform_pipeline = as_uni(cache.get_mut(ShaderSimpleShader::Uniform).as_any_mut());
+    let set_layouts = uniform_pipeline.set_layouts.clone();
+    let sets = uniform_pipeline.get_cache(0).allocate(&set_layouts);
+    assert!(sets.len() == set_layouts.len());
+
+    // The pool holds 4 copies of each set for each of the 3 frames in flight
+    for _ in 1..12 {
+        uniform_pipeline.get_cache(0).allocate(&set_layouts);
+    }
+
+    dev.wait();
+}
+
+#[test]
+fn validate_simple_shader() {
+    let ctx = Ctx::builder().debug(true).build();
+    let dev = Dev::new(&ctx, None);
+
+    let mut cache = PipelineCache::new(&dev);
+    assert!(cache.validate().is_ok());
 
     dev.wait();
 }
+
+#[test]
+fn single_view() {
+    assert!(PipelineMain::VIEW_COUNT == 1);
+    // Render passes without multiview have an empty view mask
+    PipelineMain::check_view_mask(0);
+}
+
+#[test]
+fn build_mirrored_pipeline() {
+    let ctx = Ctx::builder().debug(true).build();
+    let dev = Dev::new(&ctx, None);
+
+    const CODE: &[u8] = include_bytes!(env!("simple_shader.spv"));
+    let shader_module = ShaderModule::new(&dev.device, CODE);
+    let pass = Pass::new(&dev);
+
+    let main_pipeline = PipelineMain::new(&shader_module, pass.render);
+    assert!(main_pipeline.get_front_face() == vk::FrontFace::COUNTER_CLOCKWISE);
+
+    // Mirrored geometry flips the winding order
+    let mirrored_pipeline = PipelineMain::new_mirrored(&shader_module, pass.render);
+    assert!(mirrored_pipeline.get_front_face() == vk::FrontFace::CLOCKWISE);
+
+    dev.wait();
+}
+
+#[test]
+fn bind_model_buffers() {
+    // Primitives of vkr_core models have 16-bit indices
+    assert!(PipelineMain::INDEX_TYPE == vk::IndexType::UINT16);
+
+    // Binding the buffers of a primitive once is enough to draw it twice
+    let _draw_twice = |pipeline: &PipelineMain,
+                       frame: &Frame,
+                       model: &Model,
+                       primitive: Handle<vkr_core::Primitive>| {
+        pipeline.bind_model_buffers(frame, model, primitive);
+        let command_buffer = frame.cache.command_buffer.command_buffer;
+        pipeline.draw_instanced(command_buffer, 3, 1, 0, 0, 0);
+        pipeline.draw_instanced(command_buffer, 3, 1, 0, 0, 1);
+    };
+}
@@ -4,296 +4,152 @@
 
 extern crate proc_macro;
 
-use std::collections::{HashMap, HashSet};
-
 use proc_macro::*;
 
-mod util;
-use util::*;
-
-mod shader;
-use shader::*;
-
-mod module;
-use module::*;
-
-mod gen;
-
-#[proc_macro]
-pub fn pipewriter(input: TokenStream) -> TokenStream {
-    let shader_crate = input.to_string().replace("\"", "");
-    let current_dir = std::env::current_dir().expect("Failed to get current directory");
-    let crate_dir = current_dir.join(&shader_crate);
-
-    let crate_module = CrateModule::new(crate_dir);
-
-    // Build the Pipeline implementation
-    gen_pipelines(&crate_module)
+use vkr_pipe_gen::{CrateModule, Options};
+
+/// Arguments of the `pipewriter!` macro: the path of the shader crate, optionally followed by
+/// - `module = "name"` to generate everything in a `pub mod name`
+/// - `descriptors_only = true` to generate only descriptor and layout code, without graphics pipelines
+/// - `reverse_z = false` to use a standard depth range instead of reverse-Z for all pipelines
+/// - `prefix = "Gfx"` to start the names of the generated types with `Gfx` instead of `Pipeline`
+/// - `reflect = true` to take the uniforms from the SPIR-V of the crate instead of its attributes
+struct MacroArgs {
+    shader_crate: syn::LitStr,
+    module: Option<syn::Ident>,
+    descriptors_only: bool,
+    reverse_z: bool,
+    prefix: Option<String>,
+    reflect: bool,
 }
 
-fn gen_pipelines(crate_module: &CrateModule) -> TokenStream {
-    let mut gen = gen::header();
-
-    let pipelines = get_pipelines(&crate_module.file);
-
-    gen.extend(gen::cache(crate_module, &pipelines));
-
-    for pipeline in &pipelines {
-        let pipeline_gen = gen::pipeline(pipeline);
-        gen.extend(pipeline_gen);
-    }
-
-    gen.into()
-}
-
-/// Collects all the pipelines found in a shader file
-fn get_pipelines(file: &syn::File) -> Vec<Pipeline> {
-    let functions = file
-        .items
-        .iter()
-        .filter_map(|i| inner_value!(i, syn::Item::Fn(f) => f));
-
-    // Collect names first
-    let names: HashSet<String> = functions
-        .clone()
-        .filter(|func| {
-            return matches!(get_shader_type(func), Some(ShaderType::Vertex));
-        })
-        .map(|func| get_prefix(&func.sig.ident.to_string()).to_camelcase())
-        .collect();
-
-    // TODO contruct pipelines now and then populate args and uniforms?
-    let mut builders: HashMap<String, PipelineBuilder> = names
-        .into_iter()
-        .map(|name| (name.clone(), Pipeline::builder().name(name)))
-        .collect();
-
-    // Go through all the functions of the file
-    for func in functions {
-        // Analyze spirv attribute
-        if let Some(shader_type) = get_shader_type(func) {
-            // Extract prefix of function
-            let prefix = get_prefix(&func.sig.ident.to_string());
-            // Convert to camelcase and use it to name the pipeline
-            let name = prefix.to_camelcase();
-
-            let builder = builders.get_mut(&name).expect(&format!(
-                "Failed to get builder name for {}: {}:{}",
-                name,
-                file!(),
-                line!(),
-            ));
-
-            if shader_type == ShaderType::Vertex {
-                let arg_types = get_args_type(func);
-                builder.arg_types(arg_types);
-            }
-
-            builder.add_uniforms(get_uniforms(func));
-        }
-    }
-
-    builders.into_iter().map(|(_, b)| b.build()).collect()
-}
-
-/// Analyzes the attributes of a function, looking for a spirv `MetaList`
-fn get_spirv(attrs: &[syn::Attribute]) -> Option<syn::MetaList> {
-    attrs
-        .iter()
-        // which are metas
-        .filter_map(|attr| attr.parse_meta().ok())
-        // which are lists
-        .filter_map(|meta| inner_value!(meta, syn::Meta::List(l) => l))
-        // which idents are spirv
-        .filter(|list| list.path.get_ident().is_some() && list.path.get_ident().unwrap() == "spirv")
-        .next() // and take first
-}
-
-#[allow(unused)]
-fn dump_meta<'m>(list: &'m syn::MetaList) {
-    for nested in &list.nested {
-        if let syn::NestedMeta::Meta(meta) = nested {
-            if let syn::Meta::Path(path) = meta {
-                if let Some(id) = path.get_ident() {
-                    eprintln!("path: {}", id);
+impl syn::parse::Parse for MacroArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let shader_crate = input.parse()?;
+        let mut module = None;
+        let mut descriptors_only = false;
+        let mut reverse_z = true;
+        let mut prefix = None;
+        let mut reflect = false;
+
+        while input.parse::<Option<syn::Token![,]>>()?.is_some() && !input.is_empty() {
+            let key: syn::Ident = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+
+            match key.to_string().as_str() {
+                "module" => {
+                    let name: syn::LitStr = input.parse()?;
+                    module = Some(name.parse::<syn::Ident>().map_err(|_| {
+                        syn::Error::new(
+                            name.span(),
+                            "Expected a module name, such as \"pipelines\", relative to the macro invocation",
+                        )
+                    })?);
                 }
-            }
-            if let syn::Meta::NameValue(name_value) = meta {
-                if let Some(id) = name_value.path.get_ident() {
-                    eprintln!("path: {}", id);
+                "descriptors_only" => {
+                    descriptors_only = input.parse::<syn::LitBool>()?.value;
                 }
-            }
-        }
-    }
-}
-
-fn get_meta_name_value<'m>(list: &'m syn::MetaList, ident: &str) -> Option<&'m syn::MetaNameValue> {
-    for nested in &list.nested {
-        if let syn::NestedMeta::Meta(meta) = nested {
-            if let syn::Meta::NameValue(name_value) = meta {
-                if let Some(id) = name_value.path.get_ident() {
-                    if id == ident {
-                        return Some(name_value);
-                    }
+                "reverse_z" => {
+                    reverse_z = input.parse::<syn::LitBool>()?.value;
+                }
+                "prefix" => {
+                    let name: syn::LitStr = input.parse()?;
+                    name.parse::<syn::Ident>().map_err(|_| {
+                        syn::Error::new(name.span(), "Expected a type name prefix, such as \"Gfx\"")
+                    })?;
+                    prefix = Some(name.value());
+                }
+                "reflect" => {
+                    reflect = input.parse::<syn::LitBool>()?.value;
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("Unknown pipewriter argument: {}", key),
+                    ))
                 }
             }
         }
-    }
-    None
-}
 
-/// Analyzes a function attributes, looking for vertex and fragment `Path`s
-/// and returns the corresponding shader type
-fn get_shader_type(func: &syn::ItemFn) -> Option<ShaderType> {
-    if let Some(spirv) = get_spirv(&func.attrs) {
-        for nested in &spirv.nested {
-            if let syn::NestedMeta::Meta(meta) = nested {
-                if let syn::Meta::Path(path) = meta {
-                    if let Some(ident) = path.get_ident() {
-                        if ident == "vertex" {
-                            return Some(ShaderType::Vertex);
-                        } else if ident == "fragment" {
-                            return Some(ShaderType::Fragment);
-                        }
-                    }
-                }
-            }
+        if !input.is_empty() {
+            return Err(input.error("Expected a comma between pipewriter arguments"));
         }
+
+        Ok(Self {
+            shader_crate,
+            module,
+            descriptors_only,
+            reverse_z,
+            prefix,
+            reflect,
+        })
     }
-    None
 }
 
-fn get_arg_name(arg: &syn::PatType) -> Option<syn::Ident> {
-    match &*arg.pat {
-        syn::Pat::Box(_) => todo!(),
-        syn::Pat::Ident(i) => return Some(i.ident.clone()),
-        syn::Pat::Lit(_) => todo!(),
-        syn::Pat::Macro(_) => todo!(),
-        syn::Pat::Or(_) => todo!(),
-        syn::Pat::Path(_) => todo!(),
-        syn::Pat::Range(_) => todo!(),
-        syn::Pat::Reference(_) => todo!(),
-        syn::Pat::Rest(_) => todo!(),
-        syn::Pat::Slice(_) => todo!(),
-        syn::Pat::Struct(_) => todo!(),
-        syn::Pat::Tuple(_) => todo!(),
-        syn::Pat::TupleStruct(_) => todo!(),
-        syn::Pat::Type(_) => todo!(),
-        syn::Pat::Verbatim(_) => todo!(),
-        syn::Pat::Wild(_) => todo!(),
-        syn::Pat::__TestExhaustive(_) => todo!(),
+impl MacroArgs {
+    fn options(&self) -> Options {
+        Options {
+            descriptors_only: self.descriptors_only,
+            reverse_z: self.reverse_z,
+            prefix: self.prefix.clone(),
+            reflect: self.reflect,
+        }
     }
 }
 
-fn get_arg_type(arg: &syn::PatType) -> Option<syn::Ident> {
-    match &*arg.ty {
-        syn::Type::Path(p) => {
-            if !p.path.segments.is_empty() {
-                return Some(p.path.segments[0].ident.clone());
-            }
-        }
-        syn::Type::Reference(r) => match &*r.elem {
-            syn::Type::Path(p) => {
-                if !p.path.segments.is_empty() {
-                    return Some(p.path.segments[0].ident.clone());
-                }
-            }
-            syn::Type::Macro(m) => {
-                if let Some(ident) = m.mac.path.get_ident() {
-                    return Some(ident.clone())
-                }
+#[proc_macro]
+pub fn pipewriter(input: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(input as MacroArgs);
+    // Cargo runs the compiler from the workspace root, so paths are relative to the invoking crate
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| std::env::current_dir().expect("Failed to get current directory"));
+    let crate_dir = manifest_dir.join(args.shader_crate.value());
+
+    // Build the Pipeline implementation, or a compile error explaining what went wrong
+    let gen = CrateModule::load(&crate_dir)
+        .and_then(|crate_module| vkr_pipe_gen::generate(&crate_module, &args.options()))
+        .unwrap_or_else(proc_macro2::TokenStream::from);
+
+    match args.module {
+        Some(module) => quote::quote! {
+            pub mod #module {
+                #gen
             }
-            syn::Type::Array(_) => eprintln!("Array"),
-            syn::Type::BareFn(_) => eprintln!("BF"),
-            syn::Type::Group(_) => eprintln!("G"),
-            syn::Type::ImplTrait(_) => eprintln!("IT"),
-            syn::Type::Infer(_) => eprintln!("In"),
-            syn::Type::Never(_) => eprintln!("Nev"),
-            syn::Type::Paren(_) => eprintln!("Pare"),
-            syn::Type::Ptr(_) => eprintln!("Ptr"),
-            syn::Type::Reference(_) => eprintln!("Ref"),
-            syn::Type::Slice(_) => eprintln!("Slic"),
-            syn::Type::TraitObject(_) => eprintln!("Trai"),
-            syn::Type::Tuple(_) => eprintln!("Tup"),
-            syn::Type::Verbatim(_) => eprintln!("Verb"),
-            syn::Type::__TestExhaustive(_) => eprintln!("Test"),
         },
-        _ => eprintln!("Unhandled Type"),
+        None => gen,
     }
-    None
+    .into()
 }
 
-/// Collects the arguments type of a function
-fn get_args_type(func: &syn::ItemFn) -> Vec<syn::Ident> {
-    let mut ret = vec![];
+#[test]
+fn macro_args() {
+    let args: MacroArgs = syn::parse_quote!("shader/simple");
+    assert!(args.shader_crate.value() == "shader/simple");
+    assert!(args.module.is_none());
 
-    for arg in &func.sig.inputs {
-        match arg {
-            syn::FnArg::Typed(t) => match &*t.ty {
-                syn::Type::Path(p) => {
-                    for seg in &p.path.segments {
-                        match seg.ident.to_string().as_str() {
-                            "Vec4" | "Vec3" | "Vec2" => ret.push(seg.ident.clone()),
-                            _ => todo!("Handle input {}: {}:{}", seg.ident, file!(), line!()),
-                        }
-                    }
-                }
-                syn::Type::Reference(_) => {
-                    // TODO: look for mutable output values
-                }
-                _ => (),
-            },
-            _ => (),
-        }
-    }
+    let args: MacroArgs = syn::parse_quote!("shader/simple", module = "pipelines");
+    assert!(args.module.unwrap() == "pipelines");
+    assert!(!args.descriptors_only);
 
-    ret
-}
+    let args: MacroArgs = syn::parse_quote!("shader/simple", descriptors_only = true,);
+    assert!(args.descriptors_only);
+    assert!(args.reverse_z);
 
-fn get_spirv_value(spirv: &syn::MetaList, id: &str) -> Option<u32> {
-    if let Some(desc_set) = get_meta_name_value(&spirv, id) {
-        Some(
-            inner_value!(&desc_set.lit, syn::Lit::Int(i) => i)
-                .unwrap()
-                .base10_parse::<u32>()
-                .unwrap(),
-        )
-    } else {
-        None
-    }
-}
+    let args: MacroArgs = syn::parse_quote!("shader/simple", reverse_z = false);
+    assert!(!args.reverse_z);
+    assert!(args.prefix.is_none());
 
-fn get_uniforms(func: &syn::ItemFn) -> Vec<Uniform> {
-    let mut uniforms = vec![];
+    let args: MacroArgs = syn::parse_quote!("shader/simple", prefix = "Gfx");
+    assert_eq!(args.prefix.as_deref(), Some("Gfx"));
+    assert!(!args.reflect);
 
-    let shader_type = get_shader_type(func).expect("Can not get uniforms from this function");
+    let args: MacroArgs = syn::parse_quote!("shader/simple", reflect = true);
+    assert!(args.reflect);
 
-    for arg in &func.sig.inputs {
-        match arg {
-            syn::FnArg::Typed(arg) => {
-                let spirv = get_spirv(&arg.attrs);
-                if let Some(spirv) = spirv {
-                    if let Some(desc_set) = get_spirv_value(&spirv, "descriptor_set") {
-                        let name = get_arg_name(arg).expect("Failed to get argument name");
-                        let ident = get_arg_type(arg).expect(&format!(
-                            "Failed to get segment for arg {}: {}:{}",
-                            name,
-                            file!(),
-                            line!()
-                        ));
-                        let binding = get_spirv_value(&spirv, "binding").expect(&format!(
-                            "Failed to get binding for arg {}: {}:{}",
-                            name,
-                            file!(),
-                            line!()
-                        ));
-                        uniforms.push(Uniform::new(name, ident, desc_set, binding, shader_type))
-                    }
-                }
-            }
-            _ => (),
-        }
-    }
+    let prefix = syn::parse_str::<MacroArgs>(r#""shader/simple", prefix = "Gfx::""#);
+    assert!(prefix.is_err());
 
-    uniforms
+    let path = syn::parse_str::<MacroArgs>(r#""shader/simple", module = "crate::gfx""#);
+    assert!(path.is_err());
 }
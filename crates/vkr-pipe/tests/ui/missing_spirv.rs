@@ -0,0 +1,4 @@
+// Paths are relative to the project trybuild generates in target/tests/trybuild
+vkr_pipe::pipewriter!("../../../../crates/vkr-pipe-test/shader/simple", reflect = true);
+
+fn main() {}
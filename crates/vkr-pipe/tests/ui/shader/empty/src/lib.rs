@@ -0,0 +1 @@
+pub fn not_a_shader() {}
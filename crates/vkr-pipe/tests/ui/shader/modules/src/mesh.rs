@@ -0,0 +1,5 @@
+#[spirv(vertex)]
+pub fn mesh_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
+
+#[spirv(fragment)]
+pub fn mesh_fs(out_color: &mut Vec4) {}
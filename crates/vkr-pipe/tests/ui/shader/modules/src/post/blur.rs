@@ -0,0 +1,2 @@
+#[spirv(compute(threads(64)))]
+pub fn blur_cs() {}
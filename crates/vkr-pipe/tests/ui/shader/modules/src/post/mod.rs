@@ -0,0 +1,7 @@
+mod blur;
+
+#[spirv(vertex)]
+pub fn tonemap_vs(in_pos: Vec2, #[spirv(position)] out_pos: &mut Vec4) {}
+
+#[spirv(fragment)]
+pub fn tonemap_fs(out_color: &mut Vec4) {}
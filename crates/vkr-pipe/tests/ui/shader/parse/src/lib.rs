@@ -0,0 +1,3 @@
+#[spirv(vertex)]
+pub fn main_vs(in_pos: Vec3 {
+}
@@ -0,0 +1 @@
+// Never read, as the lib path of this crate is its src directory
@@ -0,0 +1,9 @@
+#[spirv(fragment)]
+pub fn main_fs(
+    #[spirv(uniform, descriptor_set = 0, binding = 0)] light: &Light,
+    out_color: &mut Vec4,
+) {
+}
+
+#[spirv(vertex)]
+pub fn main_vs(in_pos: Vec3, #[spirv(position)] out_pos: &mut Vec4) {}
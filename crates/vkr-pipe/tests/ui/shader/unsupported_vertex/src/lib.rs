@@ -0,0 +1,5 @@
+#[spirv(fragment)]
+pub fn main_fs(out_color: &mut Vec4) {}
+
+#[spirv(vertex)]
+pub fn main_vs(in_pos: Vec3, in_color: Color, #[spirv(position)] out_pos: &mut Vec4) {}